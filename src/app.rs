@@ -1,37 +1,212 @@
-use crate::worker::{SnipTask, TaskResult};
+use crate::capture::{self, CaptureRegion, Display, Margins, ScreenshotFormat, ScreenshotSettings};
+use crate::platform::{self, EditorCommand, PasteTarget, TargetWindow};
+use crate::export::{self, ExportSnip};
+use crate::folders::{self, MathpixFolder};
+use crate::graphics::{self, GraphicsBackend};
+use crate::cost::{self, CostTracking};
+use crate::convert::{self, Normalization, OutputCleanup, OutputTemplates, TemplateFields, TexDelimiters, TexSource, TypstFormat};
+use crate::search;
+use crate::settings;
+use crate::backup::{self, AutoBackup, Backup};
+use crate::bridge::{Bridge, BridgeResult};
+use crate::store::SnipStore;
+use crate::StartupOptions;
+use crate::update::{self, Release};
+use crate::theme::{ThemePreset, ThemeSettings};
+use crate::i18n::{self, tr, Language};
+use crate::images::{ImageCache, Scaled};
+use crate::lock::AppLock;
+use crate::perf::PerfStats;
+use crate::result_file::{ResultFile, ResultFileWriter};
+use crate::hotkeys::{self, GlobalHotkeys, HotkeyAction, HotkeyBinding, HotkeyDispatcher};
+use crate::worker::{
+    self, ConversionSettings, ExtraFormats, OcrPreset, QueueFullPolicy, SnipMetadata, SnipTask, SnipTimings, TaskKind,
+    TaskResult, TlsSettings, WorkerEvent, WorkerLimits, SCRCPY_PROCESS_NAME,
+};
 use eframe::egui::{FontData, FontFamily};
 use eframe::{egui, App};
 use egui_extras;
 use egui_extras::Column;
+use egui_dock::{DockArea, DockState, NodeIndex};
 use egui_keybind::{Keybind, Shortcut};
 use egui_notify::Toasts;
-use livesplit_hotkey::{Hook, Hotkey, KeyCode, Modifiers};
-use std::str::FromStr;
-use std::sync::mpsc::{Receiver, Sender};
+use arboard::Clipboard;
+use livesplit_hotkey::Hotkey;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tex2typst_rs::text_and_tex2typst;
+use std::time::{Duration, Instant, SystemTime};
 use uuid::Uuid;
 
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct TypstScanData {
     pub mathpix_api_key: String,
-    snip_items: Vec<SnipItem>,
+    // kept in the snip store, only read here from the app data of older versions
+    #[serde(skip_serializing)]
+    pub(crate) snip_items: Vec<SnipItem>,
+    #[serde(skip_serializing)]
+    trash: Vec<TrashedSnip>,
+    trash_retention_days: u32,
     pub replace_rules: Vec<ReplaceRule>,
     main_view: MainView,
+    layout: WorkspaceLayout,
     selected_snip_item: Option<Uuid>,
     api_used: u64,
     api_limit: u64,
+    /// Periodic captures pause while fewer snips than this are left, 0 never pauses them.
+    low_quota_threshold: u64,
+    cost_tracking: CostTracking,
     pub hide_when_capturing: bool,
-    shortcut: Shortcut,
-    hotkey: Hotkey,
+    /// Crop captures to the content around their center before uploading.
+    pub smart_crop: bool,
+    /// Reuse the result of an earlier snip of the same image instead of sending it again.
+    pub reuse_results: bool,
+    pub screenshot: ScreenshotSettings,
+    pub tls: TlsSettings,
+    pub worker_limits: WorkerLimits,
     pub clipboard_mode: ClipboardMode,
+    pub ocr_preset: OcrPreset,
+    pub extra_formats: ExtraFormats,
+    /// Id of the Mathpix folder captures are uploaded into, empty for none.
+    pub upload_folder: String,
+    /// Folders of the Mathpix account as last fetched.
+    mathpix_folders: Vec<MathpixFolder>,
+    /// Folder for the captures of a preset instead of `upload_folder`.
+    preset_folders: HashMap<OcrPreset, String>,
+    pub tex_delimiters: TexDelimiters,
+    /// Output of Mathpix that equations are converted from.
+    pub tex_source: TexSource,
+    pub output_cleanup: OutputCleanup,
+    pub output_templates: OutputTemplates,
+    /// Document of the `.tex` export, the snips go at `{body}`.
+    latex_template: String,
+    /// Enabled rewrites of the TeX before it is converted to Typst.
+    pub normalizations: Vec<Normalization>,
+    pub typst_format: TypstFormat,
+    /// Put between the Typst of merged snips, `\n` stands for a line break.
+    pub merge_joiner: String,
     continuous_clipboard: String,
+    /// Text recently put on the clipboard, newest first.
+    copy_history: Vec<String>,
+    copy_history_size: usize,
+    /// Label entries of the continuous clipboard with a running number.
+    equation_numbering: bool,
+    /// Typst label put after each entry, `{n}` is replaced with the number.
+    equation_label_format: String,
+    next_equation_number: u32,
     replace_rules_json: String,
     pub bring_forward: bool,
     pub target_window_title: String,
     pub target_process_name: String,
+    pub auto_paste: bool,
+    pub send_to_editor: EditorCommand,
+    /// Listen on the local socket for editor plugins.
+    editor_bridge: bool,
+    result_file: ResultFile,
+    /// `None` in the app data of older versions, which pasted into the bring-forward window.
+    /// Missing fields would otherwise come from `default()`, which has a target.
+    #[serde(default)]
+    pub paste_target: Option<PasteTarget>,
+    pub last_region: Option<CaptureRegion>,
+    pub capture_process_name: String,
+    pub capture_window_title: String,
+    capture_display: Option<String>,
+    pub capture_delay_secs: u32,
+    #[serde(skip)]
+    pub auto_capture: bool,
+    pub auto_capture_interval_secs: u32,
+    mini_overlay: bool,
+    always_on_top: bool,
+    /// `None` follows the system locale.
+    language: Option<Language>,
+    theme: ThemeSettings,
+    extra_fonts: Vec<PathBuf>,
+    onboarding_done: bool,
+    check_for_updates: bool,
+    /// Show frame time, queues and image memory in a corner.
+    perf_overlay: bool,
+    app_lock: AppLock,
+    auto_backup: AutoBackup,
+    /// Release the user dismissed the update banner for.
+    dismissed_update: Option<String>,
+    pub scrcpy_window_title: String,
+    pub scrcpy_crop: Margins,
+    hotkeys: Vec<HotkeyBinding>,
+    // the single capture hotkey of older versions, folded into `hotkeys` on load
+    #[serde(skip_serializing)]
+    shortcut: Option<Shortcut>,
+    #[serde(skip_serializing)]
+    hotkey: Option<Hotkey>,
+}
+
+impl TypstScanData {
+    pub(crate) fn conversion_settings(&self) -> ConversionSettings<'_> {
+        ConversionSettings {
+            normalizations: &self.normalizations,
+            replace_rules: &self.replace_rules,
+            typst_format: &self.typst_format,
+            cleanup: &self.output_cleanup,
+        }
+    }
+
+    /// The snips whose Typst changes when they are converted again with the current settings, with the new Typst.
+    pub(crate) fn reconvert_changes(&self) -> Vec<(Uuid, String)> {
+        let settings = self.conversion_settings();
+        self.snip_items
+            .iter()
+            .filter_map(|item| {
+                let (typst, _) = item.convert(&settings, item.typst_source.unwrap_or(TexSource::Text));
+                (typst != item.typst).then_some((item.id, typst))
+            })
+            .collect()
+    }
+
+    /// Older versions pasted into the bring-forward window, keep pasting there.
+    pub(crate) fn migrate_paste_target(&mut self) {
+        if self.paste_target.is_some() {
+            return;
+        }
+        let mut paste_target = PasteTarget::default();
+        if self.bring_forward {
+            paste_target.process_name = self.target_process_name.clone();
+            paste_target.window_title = self.target_window_title.clone();
+        }
+        self.paste_target = Some(paste_target);
+    }
+
+    /// Whether a snip in the list or in the trash shows the screenshot at this path.
+    fn references_image(&self, path: &std::path::Path) -> bool {
+        self.snip_items
+            .iter()
+            .chain(self.trash.iter().map(|trashed| &trashed.snip_item))
+            .flat_map(|item| std::iter::once(&item.local_image).chain(item.extra_images.iter()))
+            .any(|image| capture::resolve_image_path(image) == path)
+    }
+
+    /// Snips left in the Mathpix plan.
+    pub(crate) fn remaining_snips(&self) -> u64 {
+        self.api_limit.saturating_sub(self.api_used)
+    }
+
+    /// Whether so few snips are left that only captures asked for by hand are sent.
+    pub(crate) fn quota_low(&self) -> bool {
+        self.low_quota_threshold > 0 && self.api_limit > 0 && self.remaining_snips() < self.low_quota_threshold
+    }
+
+    /// Id of the Mathpix folder the captures of this preset are uploaded into, empty for none.
+    pub(crate) fn folder_for(&self, preset: OcrPreset) -> &str {
+        match self.preset_folders.get(&preset) {
+            Some(folder_id) if !folder_id.is_empty() => folder_id,
+            _ => &self.upload_folder,
+        }
+    }
+
+    /// The newest recognized snip of an image with this hash.
+    pub(crate) fn cached_snip(&self, image_hash: &str) -> Option<&SnipItem> {
+        self.snip_items.iter().rev().find(|item| item.image_hash == image_hash && item.error.is_empty())
+    }
 }
 
 impl Default for TypstScanData {
@@ -39,29 +214,74 @@ impl Default for TypstScanData {
         Self {
             mathpix_api_key: String::new(),
             snip_items: Vec::new(),
+            trash: Vec::new(),
+            trash_retention_days: 30,
             replace_rules: Vec::new(),
             main_view: MainView::default(),
+            layout: WorkspaceLayout::default(),
             selected_snip_item: None,
             api_used: 0,
             api_limit: 60000,
+            low_quota_threshold: 100,
+            cost_tracking: CostTracking::default(),
             hide_when_capturing: false,
-            shortcut: Shortcut::new(
-                Some(egui::KeyboardShortcut::new(
-                    egui::Modifiers::CTRL | egui::Modifiers::ALT,
-                    egui::Key::Z,
-                )),
-                None,
-            ),
-            hotkey: Hotkey {
-                key_code: KeyCode::from_str("Z").unwrap(),
-                modifiers: Modifiers::CONTROL | Modifiers::ALT,
-            },
+            smart_crop: false,
+            reuse_results: true,
+            screenshot: ScreenshotSettings::default(),
+            tls: TlsSettings::default(),
+            worker_limits: WorkerLimits::default(),
             clipboard_mode: ClipboardMode::CopyTypst,
+            ocr_preset: OcrPreset::Equation,
+            extra_formats: ExtraFormats::default(),
+            upload_folder: String::new(),
+            mathpix_folders: Vec::new(),
+            preset_folders: HashMap::new(),
+            tex_delimiters: TexDelimiters::Raw,
+            tex_source: TexSource::Text,
+            output_cleanup: OutputCleanup::default(),
+            output_templates: OutputTemplates::default(),
+            latex_template: export::DEFAULT_LATEX_TEMPLATE.to_string(),
+            normalizations: Normalization::ALL.to_vec(),
+            typst_format: TypstFormat::default(),
+            merge_joiner: "\\n".to_string(),
             continuous_clipboard: String::new(),
+            copy_history: Vec::new(),
+            copy_history_size: 20,
+            equation_numbering: false,
+            equation_label_format: "<eq:{n}>".to_string(),
+            next_equation_number: 1,
             replace_rules_json: String::new(),
             bring_forward: false,
             target_window_title: String::new(),
             target_process_name: String::new(),
+            auto_paste: false,
+            send_to_editor: EditorCommand::default(),
+            editor_bridge: false,
+            result_file: ResultFile::default(),
+            paste_target: Some(PasteTarget::default()),
+            last_region: None,
+            capture_process_name: String::new(),
+            capture_window_title: String::new(),
+            capture_display: None,
+            capture_delay_secs: 0,
+            auto_capture: false,
+            auto_capture_interval_secs: 10,
+            mini_overlay: false,
+            always_on_top: false,
+            language: None,
+            theme: ThemeSettings::default(),
+            extra_fonts: Vec::new(),
+            onboarding_done: false,
+            check_for_updates: false,
+            perf_overlay: false,
+            app_lock: AppLock::default(),
+            auto_backup: AutoBackup::default(),
+            dismissed_update: None,
+            scrcpy_window_title: String::new(),
+            scrcpy_crop: Margins::default(),
+            hotkeys: hotkeys::default_bindings(),
+            shortcut: None,
+            hotkey: None,
         }
     }
 }
@@ -69,341 +289,3915 @@ impl Default for TypstScanData {
 pub struct TypstScan {
     data: TypstScanData,
     task_sender: Sender<SnipTask>,
-    result_receiver: Receiver<TaskResult>,
+    event_receiver: Receiver<WorkerEvent>,
     global_app_data: Arc<Mutex<TypstScanData>>,
-    hotkey_hook: Hook,
+    hotkey_hook: GlobalHotkeys,
+    hotkey_dispatcher: HotkeyDispatcher,
+    hotkey_action_receiver: Receiver<HotkeyAction>,
     toasts: Toasts,
+    running_windows: Vec<TargetWindow>,
+    region_overlay: Option<CaptureRegion>,
+    displays: Vec<Display>,
+    region_drag_start: Option<egui::Pos2>,
+    countdown: Option<u32>,
+    quick_open: Option<QuickOpen>,
+    /// Scroll the snip list to the selected snip on the next frame.
+    scroll_to_snip: bool,
+    /// Selected entry of the quick copy popup while it is open.
+    quick_copy: Option<usize>,
+    /// Whether Mathpix was reachable at the last check.
+    online: bool,
+    /// Captures waiting for the network to come back.
+    queued_uploads: usize,
+    onboarding: Option<Onboarding>,
+    update_receiver: Option<Receiver<Release>>,
+    folders_receiver: Option<Receiver<Result<Vec<MathpixFolder>, String>>>,
+    /// Share links being requested, by snip.
+    share_link_receivers: Vec<(Uuid, Receiver<Result<String, String>>)>,
+    available_update: Option<Release>,
+    export_secrets: bool,
+    /// Snips stay hidden until the passcode of the app lock is entered.
+    locked: bool,
+    /// Passcode typed into the lock screen or the settings.
+    passcode_input: String,
+    was_minimized: bool,
+    /// Snips shown in windows of their own.
+    snip_windows: Vec<Uuid>,
+    /// Capture order of the newest result that was copied.
+    newest_copy: Option<u64>,
+    /// Scroll back to where the last session left off on the first frame.
+    restore_scroll: bool,
+    /// Encrypts exported backups when not empty, and decrypts restored ones.
+    backup_password: String,
+    /// When the last automatic backup was written.
+    last_auto_backup: Option<SystemTime>,
+    config_receiver: Option<Receiver<String>>,
+    config_path: Option<PathBuf>,
+    /// Snips picked with Ctrl/Cmd+click for merging.
+    merge_selection: Vec<Uuid>,
+    /// Strokes drawn on the canvas, points in canvas coordinates with the pen pressure.
+    strokes: Vec<Vec<(egui::Pos2, f32)>>,
+    /// New Typst of the snips a bulk reconversion would change, waiting for confirmation.
+    reconvert_preview: Option<Vec<(Uuid, String)>>,
+    image_viewer: Option<ImageViewer>,
+    /// Previews of the screenshots and eviction of images not shown for a while.
+    images: ImageCache,
+    perf: PerfStats,
+    bridge: Bridge,
+    result_file_writer: ResultFileWriter,
+    profile: Option<String>,
+    /// Renderer picked in the settings, used from the next launch.
+    renderer: GraphicsBackend,
+    /// Renderer the window was started with, after any fallback.
+    active_renderer: GraphicsBackend,
+    deleted_snip: Option<DeletedSnip>,
+    snip_store: Option<SnipStore>,
+}
+
+/// The last snip moved to the trash, for undoing.
+struct DeletedSnip {
+    id: Uuid,
+    index: usize,
+    deleted_at: Instant,
+}
+
+/// How long a deleted snip can be brought back.
+const UNDO_DELETE_SECS: u64 = 10;
+
+/// Lightbox for inspecting the images of a snip up close.
+struct ImageViewer {
+    images: Vec<String>,
+    /// Device pixel ratio of the images.
+    pixel_scale: f32,
+    zoom: f32,
+    /// Image Mathpix rendered from the OCR result, empty when there is none.
+    rendered_image: String,
+    /// Show the capture and the rendered image next to each other, zoomed and scrolled together.
+    compare: bool,
+    compare_offset: egui::Vec2,
+}
+
+/// State of the setup wizard shown on first launch.
+struct Onboarding {
+    step: OnboardingStep,
+    /// Result of the running or finished API key check.
+    key_check: Option<Receiver<Result<(), String>>>,
+    key_status: Option<Result<(), String>>,
+    /// Number of snips when the test capture was started, a new snip means it worked.
+    test_capture_from: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OnboardingStep {
+    Backend,
+    ApiKey,
+    Hotkey,
+    ClipboardMode,
+    TestCapture,
 }
 
 impl TypstScan {
     pub fn new(
         cc: &eframe::CreationContext<'_>,
         task_sender: Sender<SnipTask>,
-        result_receiver: Receiver<TaskResult>,
+        event_receiver: Receiver<WorkerEvent>,
         global_app_data: Arc<Mutex<TypstScanData>>,
+        options: StartupOptions,
     ) -> Self {
-        // add font
-        let mut fonts = egui::FontDefinitions::default();
-        fonts.font_data.insert(
-            "JB".to_owned(),
-            Arc::new(FontData::from_static(include_bytes!("../assets/fonts/JetBrainsMono-Regular.ttf"))),
-        );
-        fonts.font_data.insert(
-            "SC".to_owned(),
-            Arc::new(FontData::from_static(include_bytes!("../assets/fonts/NotoSansSC-Regular.ttf"))),
-        );
-        fonts.families.get_mut(&FontFamily::Monospace).unwrap().insert(0, "JB".to_owned());
-        fonts.families.get_mut(&FontFamily::Monospace).unwrap().insert(1, "SC".to_owned());
-        fonts
-            .families
-            .get_mut(&FontFamily::Proportional)
-            .unwrap()
-            .insert(1, "SC".to_owned());
-        cc.egui_ctx.set_fonts(fonts);
-
-        let typst_scan_data = if let Some(storage) = cc.storage {
+        let mut typst_scan_data: TypstScanData = if let Some(storage) = cc.storage {
             eframe::get_value(storage, "typst_scan_data").unwrap_or_default()
         } else {
             TypstScanData::default()
         };
 
-        global_app_data.lock().unwrap().mathpix_api_key = typst_scan_data.mathpix_api_key.clone();
+        let mut snip_store = SnipStore::new(options.profile.as_deref());
+        let stored = snip_store.as_mut().and_then(SnipStore::load);
+        // keep a copy of data from older versions before it is changed below
+        let migrating = (stored.is_none() && !typst_scan_data.snip_items.is_empty())
+            || typst_scan_data.paste_target.is_none()
+            || typst_scan_data.shortcut.is_some()
+            || stored.iter().flat_map(|(snip_items, _)| snip_items).any(|item| item.local_image.starts_with("file://"));
+        if let Some((snip_items, trash)) = stored {
+            typst_scan_data.snip_items = snip_items;
+            typst_scan_data.trash = trash;
+        }
+        if migrating && typst_scan_data.auto_backup.enabled {
+            write_auto_backup(&typst_scan_data, options.profile.as_deref(), "migration", false);
+        }
+        let TypstScanData { snip_items, trash, .. } = &mut typst_scan_data;
+        for snip_item in snip_items.iter_mut().chain(trash.iter_mut().map(|trashed| &mut trashed.snip_item)) {
+            if snip_item.make_image_paths_relative() {
+                mark_changed(&mut snip_store, snip_item.id);
+            }
+        }
+
+        // add font
+        cc.egui_ctx.set_fonts(font_definitions(&typst_scan_data.extra_fonts));
+        hotkeys::normalize_bindings(&mut typst_scan_data.hotkeys);
+        typst_scan_data.migrate_paste_target();
+        if let (Some(shortcut), Some(hotkey)) = (typst_scan_data.shortcut.take(), typst_scan_data.hotkey.take()) {
+            if let Some(capture) = typst_scan_data.hotkeys.iter_mut().find(|b| b.action == HotkeyAction::Capture) {
+                capture.shortcut = shortcut;
+                capture.hotkey = Some(hotkey);
+            }
+        }
+
+        // the worker may start capturing before the first frame shares the settings, e.g. with --capture-on-start
+        if let Ok(mut global_app_data) = global_app_data.lock() {
+            *global_app_data = typst_scan_data.clone();
+        }
+
+        i18n::set_language(typst_scan_data.language.unwrap_or_else(Language::detect));
+
+        typst_scan_data.theme.apply(&cc.egui_ctx);
+
+        if typst_scan_data.always_on_top {
+            cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(egui::WindowLevel::AlwaysOnTop));
+        }
 
         // Create a new hotkey hook
-        let hook = Hook::new().expect("Failed to create hotkey hook");
-        // Define the hotkey
-        let hotkey = typst_scan_data.hotkey;
+        let (hotkey_action_sender, hotkey_action_receiver) = mpsc::channel();
+        let hotkey_dispatcher = HotkeyDispatcher::new(task_sender.clone(), hotkey_action_sender, cc.egui_ctx.clone());
+        let hook = GlobalHotkeys::new(hotkey_dispatcher.clone());
+        for binding in typst_scan_data.hotkeys.iter_mut() {
+            binding.register_saved(&hook);
+        }
+
+        worker::start_auto_capture(task_sender.clone(), global_app_data.clone());
+
+        let bridge = Bridge::new(typst_scan_data.snip_items.last().map(SnipItem::bridge_result));
+        bridge.set_enabled(typst_scan_data.editor_bridge, options.profile.as_deref(), &task_sender);
+
+        if options.minimized {
+            cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        }
+        let mut toasts = Toasts::default();
+        if options.capture_on_start {
+            send_task(&task_sender, &mut toasts, SnipTask::new());
+        }
+        let config_path = options.config.or_else(|| settings::config_path(options.profile.as_deref()));
 
-        let task_sender_clone = task_sender.clone();
-        hook.register(hotkey, move || {
-            println!("Hotkey pressed!");
-            task_sender_clone.send(SnipTask::new()).unwrap();
-        })
-        .expect("Failed to register hotkey");
+        let onboarding = (!typst_scan_data.onboarding_done && typst_scan_data.mathpix_api_key.is_empty()).then(|| Onboarding {
+            step: OnboardingStep::Backend,
+            key_check: None,
+            key_status: None,
+            test_capture_from: None,
+        });
+
+        let update_receiver = typst_scan_data
+            .check_for_updates
+            .then(|| update::check_for_update(cc.egui_ctx.clone(), typst_scan_data.tls.clone()));
+
+        let folders_receiver = (!typst_scan_data.mathpix_api_key.is_empty()).then(|| {
+            folders::fetch_folders(cc.egui_ctx.clone(), typst_scan_data.mathpix_api_key.clone(), typst_scan_data.tls.clone())
+        });
+
+        let locked = typst_scan_data.app_lock.is_enabled();
 
         Self {
             data: typst_scan_data,
             task_sender,
-            result_receiver,
+            event_receiver,
             global_app_data,
             hotkey_hook: hook,
-            toasts: Toasts::default(),
+            hotkey_dispatcher,
+            hotkey_action_receiver,
+            toasts,
+            running_windows: Vec::new(),
+            region_overlay: None,
+            displays: capture::displays(),
+            countdown: None,
+            quick_open: None,
+            scroll_to_snip: false,
+            quick_copy: None,
+            online: true,
+            queued_uploads: 0,
+            region_drag_start: None,
+            onboarding,
+            update_receiver,
+            available_update: None,
+            folders_receiver,
+            share_link_receivers: Vec::new(),
+            export_secrets: false,
+            locked,
+            passcode_input: String::new(),
+            was_minimized: false,
+            restore_scroll: true,
+            snip_windows: Vec::new(),
+            newest_copy: None,
+            backup_password: String::new(),
+            last_auto_backup: backup::backups_dir(options.profile.as_deref())
+                .and_then(|dir| backup::list_backups(&dir).into_iter().next())
+                .and_then(|path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()),
+            config_receiver: config_path.clone().map(|path| settings::watch_config(path, cc.egui_ctx.clone())),
+            config_path,
+            merge_selection: Vec::new(),
+            strokes: Vec::new(),
+            reconvert_preview: None,
+            image_viewer: None,
+            images: ImageCache::new(cc.egui_ctx.clone()),
+            perf: PerfStats::default(),
+            bridge,
+            result_file_writer: ResultFileWriter::start(),
+            renderer: graphics::load(options.profile.as_deref()).unwrap_or_default(),
+            active_renderer: options.renderer.unwrap_or_default(),
+            profile: options.profile,
+            deleted_snip: None,
+            snip_store,
         }
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
-enum MainView {
-    Snips,
-    ContinuousClipboard,
-    ReplaceRules,
-    Settings,
-}
+impl TypstScan {
+    /// Hotkey actions that act on the UI rather than queueing a capture.
+    fn handle_hotkey_action(&mut self, ctx: &egui::Context, action: HotkeyAction) {
+        match action {
+            HotkeyAction::ToggleAutoCapture => {
+                self.data.auto_capture = !self.data.auto_capture && self.data.last_region.is_some();
+            }
+            HotkeyAction::CopyLatest => {
+                let text = match self.data.clipboard_mode {
+                    ClipboardMode::Continuous => Some(self.data.continuous_clipboard.clone()),
+                    _ => self.latest_output(),
+                };
+                if let Some(text) = text {
+                    ctx.copy_text(text.clone());
+                    self.record_copy(&text);
+                    self.toasts.info(tr("Copied latest result")).duration(Some(Duration::from_secs(2)));
+                }
+            }
+            HotkeyAction::PasteLatest => {
+                let Some(text) = self.latest_output() else {
+                    return;
+                };
+                if let Err(e) = Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text.clone())) {
+                    eprintln!("Failed to copy the latest result: {:?}", e);
+                    self.toasts.error(format!("{} {}", tr("Failed to copy:"), e));
+                    return;
+                }
+                self.record_copy(&text);
+                let paste_target = self.data.paste_target.clone().unwrap_or_default();
+                // pasting waits for the target window, keep that off the UI thread
+                std::thread::spawn(move || platform::paste_into(&paste_target));
+            }
+            HotkeyAction::QuickCopy => {
+                self.quick_copy = match self.quick_copy {
+                    Some(_) => None,
+                    None => Some(0),
+                };
+            }
+            HotkeyAction::ToggleMiniOverlay => {
+                self.data.mini_overlay = !self.data.mini_overlay;
+            }
+            HotkeyAction::ToggleWindow => {
+                if ctx.input(|i| i.viewport().minimized.unwrap_or(false)) {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                } else {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+                }
+            }
+            action => {
+                // capture actions never reach the UI, but forward them in case one does
+                self.hotkey_dispatcher.dispatch(action);
+            }
+        }
+    }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
-pub enum ClipboardMode {
-    Continuous,
-    CopyTeX,
-    CopyTypst,
-}
+    /// Switch to settings loaded from elsewhere, re-registering hotkeys and re-applying the look.
+    fn apply_settings(&mut self, ctx: &egui::Context, mut data: TypstScanData) {
+        for binding in &self.data.hotkeys {
+            if let Some(hotkey) = binding.hotkey {
+                self.hotkey_hook.unregister(hotkey);
+            }
+        }
+        // state that lives only in this session, and the snips which are not part of the settings
+        data.auto_capture = self.data.auto_capture;
+        data.snip_items = std::mem::take(&mut self.data.snip_items);
+        data.trash = std::mem::take(&mut self.data.trash);
 
-impl Default for MainView {
-    fn default() -> Self {
-        Self::Snips
+        hotkeys::normalize_bindings(&mut data.hotkeys);
+        for binding in data.hotkeys.iter_mut() {
+            binding.register_saved(&self.hotkey_hook);
+        }
+        if data.extra_fonts != self.data.extra_fonts {
+            ctx.set_fonts(font_definitions(&data.extra_fonts));
+        }
+        data.theme.apply(ctx);
+        i18n::set_language(data.language.unwrap_or_else(Language::detect));
+        let level = if data.always_on_top {
+            egui::WindowLevel::AlwaysOnTop
+        } else {
+            egui::WindowLevel::Normal
+        };
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+        self.data = data;
     }
-}
 
-impl App for TypstScan {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // for showing images
-        egui_extras::install_image_loaders(ctx);
+    fn export_settings(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("typst_scan_settings.json")
+            .save_file()
+        else {
+            return;
+        };
+        match settings::export_settings(&self.data, self.export_secrets)
+            .and_then(|settings| std::fs::write(&path, settings).map_err(|e| e.to_string()))
+        {
+            Ok(()) => {
+                self.toasts.success(tr("Exported settings")).duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                eprintln!("Failed to export settings: {}", e);
+                self.toasts.error(format!("{} {}", tr("Failed to export settings:"), e));
+            }
+        }
+    }
 
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            // The top panel is often a good place for a menu bar:
+    fn import_settings(&mut self, ctx: &egui::Context) {
+        let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() else {
+            return;
+        };
+        let imported = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|settings| settings::parse_json(&settings))
+            .and_then(|settings| settings::import_settings(&self.data, settings));
+        match imported {
+            Ok(data) => {
+                self.apply_settings(ctx, data);
+                self.toasts.success(tr("Imported settings")).duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                eprintln!("Failed to import settings: {}", e);
+                self.toasts.error(format!("{} {}", tr("Failed to import settings:"), e));
+            }
+        }
+    }
 
-            egui::menu::bar(ui, |ui| {
-                ui.horizontal(|ui| {
-                    ui.selectable_value(&mut self.data.main_view, MainView::Snips, "Snips");
-                    ui.selectable_value(&mut self.data.main_view, MainView::ContinuousClipboard, "Continuous Clipboard");
-                    ui.selectable_value(&mut self.data.main_view, MainView::ReplaceRules, "Replace Rules");
-                    ui.selectable_value(&mut self.data.main_view, MainView::Settings, "Settings");
-                });
+    /// Save the snips and settings to one file, encrypted when a backup password is set.
+    fn export_backup(&mut self) {
+        let encrypt = !self.backup_password.is_empty();
+        let (file_name, extension) = if encrypt {
+            ("typst_scan_backup.json.age", "age")
+        } else {
+            ("typst_scan_backup.json", "json")
+        };
+        let Some(path) = rfd::FileDialog::new().add_filter("Backup", &[extension]).set_file_name(file_name).save_file() else {
+            return;
+        };
+        let password = Some(self.backup_password.as_str()).filter(|_| encrypt);
+        let written = settings::settings_value(&self.data, self.export_secrets)
+            .map(|settings| Backup {
+                settings,
+                snip_items: self.data.snip_items.clone(),
+                trash: self.data.trash.clone(),
+            })
+            .and_then(|backup| backup::write_backup(&backup, password))
+            .and_then(|bytes| std::fs::write(&path, bytes).map_err(|e| e.to_string()));
+        match written {
+            Ok(()) => {
+                self.toasts.success(tr("Exported backup")).duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                eprintln!("Failed to export backup: {}", e);
+                self.toasts.error(format!("{} {}", tr("Failed to export backup:"), e));
+            }
+        }
+    }
 
-                ui.add_space(16.0);
+    /// Apply the settings of a backup and add its snips that are not here yet.
+    fn restore_backup(&mut self, ctx: &egui::Context) {
+        let Some(path) = rfd::FileDialog::new().add_filter("Backup", &["json", "age"]).pick_file() else {
+            return;
+        };
+        self.restore_backup_file(ctx, &path);
+    }
 
-                egui::widgets::global_theme_preference_buttons(ui);
+    fn restore_backup_file(&mut self, ctx: &egui::Context, path: &std::path::Path) {
+        let restored = std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| backup::read_backup(&bytes, &self.backup_password))
+            .and_then(|backup| {
+                let data = settings::import_settings(&self.data, backup.settings)?;
+                Ok((data, backup.snip_items, backup.trash))
             });
-        });
+        match restored {
+            Ok((data, snip_items, trash)) => {
+                self.apply_settings(ctx, data);
+                let known: Vec<Uuid> = self.data.snip_items.iter().map(|item| item.id).collect();
+                let count = self.data.snip_items.len();
+                self.data.snip_items.extend(snip_items.into_iter().filter(|item| !known.contains(&item.id)).map(|mut item| {
+                    item.make_image_paths_relative();
+                    item
+                }));
+                let added = self.data.snip_items.len() - count;
+                let trashed: Vec<Uuid> = self.data.trash.iter().map(|trashed| trashed.snip_item.id).collect();
+                self.data.trash.extend(trash.into_iter().filter(|restored| !trashed.contains(&restored.snip_item.id)));
+                let message = tr("Restored backup with {} new snips").replace("{}", &added.to_string());
+                self.toasts.success(message).duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                eprintln!("Failed to restore backup: {}", e);
+                self.toasts.error(format!("{} {}", tr("Failed to restore backup:"), e));
+            }
+        }
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| match self.data.main_view {
-            MainView::Snips => {
-                const PANEL_WIDTH: f32 = 200.0;
-                egui::SidePanel::left("main_left")
-                    .resizable(false)
-                    .exact_width(PANEL_WIDTH)
-                    .show_inside(ui, |ui| {
-                        if ui.button("Capture").clicked() {
-                            self.task_sender.send(SnipTask::new()).unwrap();
-                        }
+    /// Write the current settings to `config.toml`, which is then watched for changes.
+    fn write_config(&mut self) {
+        let Some(path) = self.config_path.clone() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        match settings::export_config(&self.data, self.export_secrets)
+            .and_then(|config| std::fs::write(&path, config).map_err(|e| e.to_string()))
+        {
+            Ok(()) => {
+                self.toasts.success(tr("Wrote config.toml")).duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                eprintln!("Failed to write config {:?}: {}", path, e);
+                self.toasts.error(format!("{} {}", tr("Failed to export settings:"), e));
+            }
+        }
+    }
 
-                        ui.separator();
+    fn reload_config(&mut self, ctx: &egui::Context, config: &str) {
+        match settings::parse_config(config).and_then(|settings| settings::import_settings(&self.data, settings)) {
+            Ok(data) => self.apply_settings(ctx, data),
+            Err(e) => {
+                eprintln!("Failed to load config.toml: {}", e);
+                self.toasts.error(format!("{} {}", tr("Failed to load config.toml:"), e));
+            }
+        }
+    }
 
-                        const ROW_HEIGHT: f32 = 30.0;
-                        egui_extras::TableBuilder::new(ui)
-                            .striped(true)
-                            .resizable(false)
-                            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-                            .column(Column::remainder().at_most(PANEL_WIDTH).clip(true).resizable(true))
-                            .sense(egui::Sense::click())
-                            .header(0.0, |_| {})
-                            .body(|mut body| {
-                                for snip_item in self.data.snip_items.iter().rev() {
-                                    body.row(ROW_HEIGHT, |mut row| {
-                                        row.set_selected(self.data.selected_snip_item.as_ref() == Some(&snip_item.id));
-                                        row.col(|ui| {
-                                            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-                                            let label = ui.label(&snip_item.title).on_hover_text(&snip_item.title);
-                                            if label.clicked() {
-                                                self.data.selected_snip_item = Some(snip_item.id);
-                                            }
-                                        });
-                                        if row.response().clicked() {
-                                            self.data.selected_snip_item = Some(snip_item.id);
-                                        }
-                                    });
-                                }
-                            });
-                    });
+    /// Ctrl+P launcher that fuzzy searches the snips and jumps to the chosen one.
+    fn show_quick_open(&mut self, ctx: &egui::Context) {
+        const QUICK_OPEN_RESULTS: usize = 20;
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::P)) {
+            self.quick_open = match self.quick_open {
+                Some(_) => None,
+                None => Some(QuickOpen::default()),
+            };
+        }
+        let Some(quick_open) = &mut self.quick_open else {
+            return;
+        };
 
-                egui::CentralPanel::default().show_inside(ui, |ui| {
-                    // display the image of the selected snip item
-                    if let Some(selected_snip_item) = self.data.selected_snip_item {
-                        if let Some(snip_item) = self.data.snip_items.iter_mut().find(|item| item.id == selected_snip_item) {
-                            egui::ScrollArea::vertical().show(ui, |ui| {
-                                ui.add_space(10.0);
-                                ui.vertical_centered(|ui| {
-                                    ui.add(egui::Image::from_uri(&snip_item.local_image).max_height(250.0).corner_radius(10.0));
-                                });
+        let mut matches: Vec<(u32, &SnipItem)> = self
+            .data
+            .snip_items
+            .iter()
+            .rev()
+            .filter_map(|snip_item| {
+                // a title match counts more than a match somewhere in the content
+                let title = search::fuzzy_score(&quick_open.query, &snip_item.title).map(|score| score * 2);
+                let content = [&snip_item.tex, &snip_item.typst, &snip_item.tags]
+                    .into_iter()
+                    .filter_map(|text| search::fuzzy_score(&quick_open.query, text))
+                    .max();
+                title.max(content).map(|score| (score, snip_item))
+            })
+            .collect();
+        // stable, so equal scores keep the newest first
+        matches.sort_by(|a, b| b.0.cmp(&a.0));
+        matches.truncate(QUICK_OPEN_RESULTS);
 
-                                ui.add_space(32.0);
-                                ui.heading("Tex");
-                                ui.add(
-                                    egui::TextEdit::multiline(&mut snip_item.tex)
-                                        .code_editor()
-                                        .desired_width(f32::INFINITY)
-                                        .desired_rows(5),
-                                );
+        let (up, down, enter) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            )
+        });
+        if down {
+            quick_open.selected = (quick_open.selected + 1).min(matches.len().saturating_sub(1));
+        }
+        if up {
+            quick_open.selected = quick_open.selected.saturating_sub(1);
+        }
+        let mut open = enter.then(|| matches.get(quick_open.selected).map(|(_, snip_item)| snip_item.id)).flatten();
 
-                                ui.add_space(16.0);
-                                ui.horizontal(|ui| {
-                                    ui.heading("Typst");
-                                    if ui.button("regenerate").clicked() {
-                                        snip_item.typst = text_and_tex2typst(&snip_item.tex)
-                                            .map_err(|e| eprintln!("Error: {:?}", e))
-                                            .unwrap_or_default();
-                                    }
-                                });
-                                ui.add(
-                                    egui::TextEdit::multiline(&mut snip_item.typst)
-                                        .code_editor()
-                                        .desired_width(f32::INFINITY)
-                                        .desired_rows(5),
-                                );
-                            });
-                        }
-                    }
-                });
+        let modal = egui::Modal::new(egui::Id::new("quick_open")).show(ctx, |ui| {
+            ui.set_width(480.0);
+            let edit = ui.add(
+                egui::TextEdit::singleline(&mut quick_open.query)
+                    .hint_text(tr("Search snips…"))
+                    .desired_width(f32::INFINITY),
+            );
+            edit.request_focus();
+            if edit.changed() {
+                quick_open.selected = 0;
             }
-            MainView::ContinuousClipboard => {
-                ui.heading("Clipboard Mode");
-                ui.horizontal(|ui| {
-                    ui.radio_value(&mut self.data.clipboard_mode, ClipboardMode::Continuous, "Continuous");
-                    ui.radio_value(&mut self.data.clipboard_mode, ClipboardMode::CopyTeX, "Copy TeX");
-                    ui.radio_value(&mut self.data.clipboard_mode, ClipboardMode::CopyTypst, "Copy Typst");
-                });
-                ui.add_space(2.0);
-                ui.separator();
-                ui.add_space(8.0);
-                ui.heading("Continuous Clipboard");
-                ui.horizontal(|ui| {
-                    if ui.button("copy all").clicked() {
-                        ctx.copy_text(self.data.continuous_clipboard.clone());
-                    }
-                    if ui.button("take all").clicked() {
-                        ctx.copy_text(self.data.continuous_clipboard.clone());
-                        self.data.continuous_clipboard.clear();
-                    }
-                });
-                ui.add_space(8.0);
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui.add(egui::TextEdit::multiline(&mut self.data.continuous_clipboard).desired_width(f32::INFINITY));
-                });
+            ui.separator();
+            if matches.is_empty() {
+                ui.weak(tr("No matching snips"));
             }
-            MainView::ReplaceRules => {
-                if ui.button("load rules").clicked() {
-                    let rules_str: String = serde_json::to_string_pretty(&self.data.replace_rules).unwrap();
-                    self.data.replace_rules_json = rules_str;
-                }
-                if ui.button("register rules").clicked() {
-                    if let Ok(rules) = serde_json::from_str(&self.data.replace_rules_json) {
-                        eprintln!("Parsed rules: {:?}", &rules);
-                        self.data.replace_rules = rules;
-                        self.toasts.success("Registered rules").duration(Some(Duration::from_secs(5)));
-                    } else {
-                        self.toasts.info("Failed to parse rules").duration(Some(Duration::from_secs(5)));
+            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Truncate);
+            egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                for (i, (_, snip_item)) in matches.iter().enumerate() {
+                    let label = ui.selectable_label(i == quick_open.selected, &snip_item.title);
+                    if label.clicked() {
+                        open = Some(snip_item.id);
+                    }
+                    if i == quick_open.selected {
+                        label.scroll_to_me(None);
                     }
                 }
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui.add(egui::TextEdit::multiline(&mut self.data.replace_rules_json).desired_width(f32::INFINITY));
-                });
-            }
-            MainView::Settings => {
-                ui.scope_builder(egui::UiBuilder::new(), |ui| {
-                    egui::Grid::new("settings_grid")
-                        .num_columns(2)
-                        .spacing([60.0, 16.0])
-                        .striped(true)
-                        .show(ui, |ui| {
-                            ui.label("Mathpix API Key");
-                            ui.add(egui::TextEdit::singleline(&mut self.data.mathpix_api_key).password(true));
-                            ui.end_row();
-
-                            ui.label("Global Hotkey");
-                            ui.horizontal(|ui| {
-                                ui.add(Keybind::new(&mut self.data.shortcut, "keybind_setter"));
-                                if ui.button("register").clicked() {
-                                    self.hotkey_hook.unregister(self.data.hotkey).unwrap();
-                                    let logged_key = self.data.shortcut.keyboard().unwrap();
-                                    let key_code: &str = logged_key.logical_key.name();
-                                    let modifiers = logged_key.modifiers;
-                                    let mut mods = Modifiers::empty();
-
-                                    if modifiers.contains(egui::Modifiers::CTRL) {
-                                        mods.insert(Modifiers::CONTROL);
-                                    }
-                                    if modifiers.contains(egui::Modifiers::ALT) {
-                                        mods.insert(Modifiers::ALT);
-                                    }
-                                    if modifiers.contains(egui::Modifiers::SHIFT) {
-                                        mods.insert(Modifiers::SHIFT);
-                                    }
+            });
+        });
 
-                                    self.data.hotkey = Hotkey {
-                                        key_code: KeyCode::from_str(key_code).unwrap(),
-                                        modifiers: mods,
-                                    };
-                                    dbg!(self.data.hotkey);
-                                    let task_sender_clone = self.task_sender.clone();
-                                    self.hotkey_hook
-                                        .register(self.data.hotkey, move || {
-                                            println!("Hotkey pressed!");
-                                            task_sender_clone.send(SnipTask::new()).unwrap();
-                                        })
-                                        .expect("Failed to register hotkey");
-                                    self.toasts.success("Registered hotkey").duration(Some(Duration::from_secs(5)));
-                                }
-                            });
-                            ui.end_row();
+        if let Some(id) = open {
+            self.data.selected_snip_item = Some(id);
+            self.data.main_view = MainView::Snips;
+            self.merge_selection.clear();
+            self.scroll_to_snip = true;
+            self.quick_open = None;
+        } else if modal.should_close() {
+            self.quick_open = None;
+        }
+    }
 
-                            ui.label("Delete All Snips");
-                            if ui.button("delete!!!").clicked() {
-                                self.data.snip_items.clear();
-                                self.data.selected_snip_item = None;
-                            }
-                            ui.end_row();
+    /// Zoom with Ctrl+scroll, pinch or the buttons and pan by dragging or scrolling.
+    fn show_image_viewer(&mut self, ctx: &egui::Context) {
+        let Some(viewer) = &mut self.image_viewer else {
+            return;
+        };
+        // full resolution here, the previews are too small to zoom into
+        for image in viewer.images.iter().chain([&viewer.rendered_image]) {
+            self.images.shown(image);
+        }
 
-                            ui.label("API usage");
-                            ui.add(egui::ProgressBar::new(self.data.api_used as f32 / self.data.api_limit as f32).show_percentage());
-                            ui.end_row();
+        let screen = ctx.screen_rect();
+        let modal = egui::Modal::new(egui::Id::new("image_viewer")).show(ctx, |ui| {
+            ui.set_width(screen.width() * 0.85);
+            ui.horizontal(|ui| {
+                if ui.button("−").clicked() {
+                    viewer.zoom /= 1.25;
+                }
+                ui.label(format!("{:.0} %", viewer.zoom * 100.0));
+                if ui.button("+").clicked() {
+                    viewer.zoom *= 1.25;
+                }
+                if ui.button(tr("Actual size")).clicked() {
+                    viewer.zoom = 1.0;
+                }
+                if !viewer.rendered_image.is_empty() {
+                    ui.toggle_value(&mut viewer.compare, tr("compare"));
+                }
+                ui.weak(tr("Ctrl+scroll to zoom, drag to pan"));
+            });
+            ui.separator();
 
-                            ui.checkbox(&mut self.data.bring_forward, "Bring Forward Window");
-                            ui.vertical(|ui| {
-                                ui.horizontal(|ui| {
-                                    ui.label("Process name:");
-                                    ui.text_edit_singleline(&mut self.data.target_process_name);
-                                });
-                                ui.horizontal(|ui| {
-                                    ui.label("Window Title:");
-                                    ui.text_edit_singleline(&mut self.data.target_window_title);
-                                });
+            let mut hovered = false;
+            if viewer.compare {
+                // both sides at the same height so the formulas line up, scrolled together
+                let height = 200.0 * viewer.zoom;
+                let width = (ui.available_width() - ui.spacing().item_spacing.x) / 2.0;
+                let mut offset = viewer.compare_offset;
+                ui.horizontal_top(|ui| {
+                    for (i, image) in [&viewer.images[0], &viewer.rendered_image].into_iter().enumerate() {
+                        let scroll = egui::ScrollArea::both()
+                            .id_salt(("compare", i))
+                            .max_width(width)
+                            .min_scrolled_width(width)
+                            .max_height(screen.height() * 0.75)
+                            .scroll_offset(viewer.compare_offset)
+                            .show(ui, |ui| {
+                                ui.add(egui::Image::from_uri(image).fit_to_original_size(100.0).max_height(height));
                             });
-                        });
+                        if scroll.state.offset != viewer.compare_offset {
+                            offset = scroll.state.offset;
+                        }
+                        hovered |= ui.rect_contains_pointer(scroll.inner_rect);
+                    }
                 });
+                viewer.compare_offset = offset;
+            } else {
+                let scroll = egui::ScrollArea::both()
+                    .max_height(screen.height() * 0.75)
+                    .drag_to_scroll(true)
+                    .show(ui, |ui| {
+                        ui.horizontal_top(|ui| {
+                            let scale = viewer.zoom / (viewer.pixel_scale * ui.ctx().zoom_factor());
+                            for image in viewer.images.iter() {
+                                ui.add(egui::Image::from_uri(image).fit_to_original_size(scale));
+                            }
+                        });
+                    });
+                hovered = ui.rect_contains_pointer(scroll.inner_rect);
             }
-        });
-
-        // check the results in the channel
-        if let Ok(result) = self.result_receiver.try_recv() {
-            match self.data.clipboard_mode {
-                ClipboardMode::Continuous => {
-                    self.data.continuous_clipboard.push_str(&result.typst);
-                    self.data.continuous_clipboard.push_str("\n");
+            if hovered {
+                let zoom_delta = ui.input(|i| i.zoom_delta());
+                if zoom_delta != 1.0 {
+                    viewer.zoom *= zoom_delta;
                 }
-                _ => {}
-            }
-
-            let mut typst_replaced = result.typst.clone();
-            for rule in self.data.replace_rules.iter() {
-                typst_replaced = typst_replaced.replace(&rule.pattern, &rule.replacement);
             }
+            viewer.zoom = viewer.zoom.clamp(0.1, 10.0);
+        });
 
-            self.data.snip_items.push(SnipItem {
-                id: result.id,
-                title: result.title,
-                local_image: format!("file://{}", result.local_image),
-                original_image: result.original_image,
-                rendered_image: result.rendered_image,
-                tex: result.text,
-                typst: typst_replaced,
-            });
-            self.data.selected_snip_item = Some(result.id);
-            self.data.api_used = result.snip_count;
-            self.data.api_limit = result.snip_limit;
+        if modal.should_close() {
+            self.image_viewer = None;
         }
+    }
 
-        self.toasts.show(ctx);
+    /// Summary of what a bulk reconversion changes, applied only when confirmed.
+    fn show_reconvert_preview(&mut self, ctx: &egui::Context) {
+        let Some(changes) = &self.reconvert_preview else {
+            return;
+        };
 
-        if let Ok(mut global_app_data) = self.global_app_data.lock() {
+        let mut close = false;
+        let mut apply = false;
+        egui::Modal::new(egui::Id::new("reconvert_preview")).show(ctx, |ui| {
+            ui.set_width(560.0);
+            ui.heading(tr("Reconvert All Snips"));
+            ui.label(
+                tr("{} of {} snips change")
+                    .replacen("{}", &changes.len().to_string(), 1)
+                    .replacen("{}", &self.data.snip_items.len().to_string(), 1),
+            );
+            ui.add_space(8.0);
+            egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                for (id, typst) in changes {
+                    let Some(item) = self.data.snip_items.iter().find(|item| item.id == *id) else {
+                        continue;
+                    };
+                    ui.label(egui::RichText::new(&item.title).strong());
+                    ui.label(egui::RichText::new(format!("- {}", item.typst)).monospace().color(ui.visuals().error_fg_color));
+                    ui.label(egui::RichText::new(format!("+ {}", typst)).monospace().color(ui.visuals().hyperlink_color));
+                    ui.separator();
+                }
+            });
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!changes.is_empty(), egui::Button::new(tr("Apply"))).clicked() {
+                    apply = true;
+                }
+                if ui.button(tr("Cancel")).clicked() {
+                    close = true;
+                }
+            });
+        });
+
+        if apply {
+            for (id, typst) in self.reconvert_preview.take().unwrap_or_default() {
+                if let Some(item) = self.data.snip_items.iter_mut().find(|item| item.id == id) {
+                    item.typst = typst;
+                    mark_changed(&mut self.snip_store, id);
+                }
+            }
+        } else if close {
+            self.reconvert_preview = None;
+        }
+    }
+
+    /// Guided setup on first launch: backend, API key, hotkey, clipboard mode and a test capture.
+    fn show_onboarding(&mut self, ctx: &egui::Context) {
+        let Some(onboarding) = &mut self.onboarding else {
+            return;
+        };
+        if let Some(status) = onboarding.key_check.as_ref().and_then(|receiver| receiver.try_recv().ok()) {
+            onboarding.key_status = Some(status);
+            onboarding.key_check = None;
+        }
+
+        let mut finished = false;
+        egui::Modal::new(egui::Id::new("onboarding")).show(ctx, |ui| {
+            ui.set_width(420.0);
+            ui.heading(tr("Welcome to Typst Scan"));
+            ui.add_space(8.0);
+
+            match onboarding.step {
+                OnboardingStep::Backend => {
+                    ui.label(tr("Choose the OCR service to use."));
+                    ui.radio(true, "Mathpix").on_hover_text(tr("The only supported service for now"));
+                }
+                OnboardingStep::ApiKey => {
+                    ui.label(tr("Enter your Mathpix API key."));
+                    ui.horizontal(|ui| {
+                        let edit = ui.add(egui::TextEdit::singleline(&mut self.data.mathpix_api_key).password(true));
+                        if edit.changed() {
+                            onboarding.key_status = None;
+                        }
+                        let checking = onboarding.key_check.is_some();
+                        if ui.add_enabled(!checking && !self.data.mathpix_api_key.is_empty(), egui::Button::new(tr("validate"))).clicked() {
+                            let (sender, receiver) = mpsc::channel();
+                            let api_key = self.data.mathpix_api_key.clone();
+                            let tls = self.data.tls.clone();
+                            let ctx = ctx.clone();
+                            std::thread::spawn(move || {
+                                let _ = sender.send(worker::validate_api_key(&api_key, &tls));
+                                ctx.request_repaint();
+                            });
+                            onboarding.key_check = Some(receiver);
+                        }
+                        if checking {
+                            ui.spinner();
+                        }
+                    });
+                    match &onboarding.key_status {
+                        Some(Ok(())) => {
+                            ui.colored_label(egui::Color32::from_rgb(0x3c, 0xb3, 0x71), tr("The key works"));
+                        }
+                        Some(Err(error)) => {
+                            ui.colored_label(ui.visuals().error_fg_color, error);
+                        }
+                        None => {}
+                    }
+                }
+                OnboardingStep::Hotkey => {
+                    ui.label(tr("Pick the global hotkey that starts a capture."));
+                    let in_use: Vec<(HotkeyAction, Hotkey)> = self
+                        .data
+                        .hotkeys
+                        .iter()
+                        .filter_map(|binding| binding.hotkey.map(|hotkey| (binding.action, hotkey)))
+                        .collect();
+                    if let Some(binding) = self.data.hotkeys.iter_mut().find(|binding| binding.action == HotkeyAction::Capture) {
+                        ui.horizontal(|ui| {
+                            ui.add(Keybind::new(&mut binding.shortcut, "onboarding_keybind_setter"));
+                            if ui.button(tr("register")).clicked() && binding.register(&self.hotkey_hook, &in_use) {
+                                self.toasts.success(tr("Registered hotkey")).duration(Some(Duration::from_secs(5)));
+                            }
+                        });
+                        if let Some(error) = &binding.error {
+                            ui.colored_label(ui.visuals().error_fg_color, error);
+                        }
+                    }
+                }
+                OnboardingStep::ClipboardMode => {
+                    ui.label(tr("What should be copied after a capture?"));
+                    ui.radio_value(&mut self.data.clipboard_mode, ClipboardMode::CopyTypst, tr("Copy Typst"));
+                    ui.radio_value(&mut self.data.clipboard_mode, ClipboardMode::CopyTeX, tr("Copy TeX"));
+                    ui.radio_value(&mut self.data.clipboard_mode, ClipboardMode::Continuous, tr("Continuous"));
+                }
+                OnboardingStep::TestCapture => {
+                    ui.label(tr("Select some math on screen to check that everything works."));
+                    if ui.button(tr("Capture")).clicked() {
+                        onboarding.test_capture_from = Some(self.data.snip_items.len());
+                        send_task(&self.task_sender, &mut self.toasts, SnipTask::new());
+                    }
+                    if let Some(from) = onboarding.test_capture_from {
+                        match self.data.snip_items.get(from) {
+                            Some(snip_item) => {
+                                ui.colored_label(egui::Color32::from_rgb(0x3c, 0xb3, 0x71), tr("It works:"));
+                                ui.monospace(&snip_item.typst);
+                            }
+                            None => {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label(tr("Waiting for the result…"));
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+
+            ui.add_space(12.0);
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button(tr("skip setup")).clicked() {
+                    finished = true;
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let next = match onboarding.step {
+                        OnboardingStep::Backend => Some(OnboardingStep::ApiKey),
+                        OnboardingStep::ApiKey => Some(OnboardingStep::Hotkey),
+                        OnboardingStep::Hotkey => Some(OnboardingStep::ClipboardMode),
+                        OnboardingStep::ClipboardMode => Some(OnboardingStep::TestCapture),
+                        OnboardingStep::TestCapture => None,
+                    };
+                    match next {
+                        Some(next) => {
+                            if ui.button(tr("Next")).clicked() {
+                                onboarding.step = next;
+                            }
+                        }
+                        None => {
+                            if ui.button(tr("Done")).clicked() {
+                                finished = true;
+                            }
+                        }
+                    }
+                });
+            });
+        });
+
+        if finished {
+            self.data.onboarding_done = true;
+            self.onboarding = None;
+        }
+    }
+
+    /// Keyboard navigation of the snip list while no text field has focus, returns true
+    /// when the selection moved and the list should scroll to it.
+    fn handle_snip_keys(&mut self, ctx: &egui::Context) -> bool {
+        if ctx.wants_keyboard_input() {
+            return false;
+        }
+        if self.deleted_snip.is_some() && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z)) {
+            self.undo_delete();
+            return true;
+        }
+        if self.data.snip_items.is_empty() {
+            return false;
+        }
+
+        let (up, down, enter, delete, copy) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Delete),
+                // Ctrl+C arrives as a copy event rather than a key press
+                i.events.iter().any(|event| matches!(event, egui::Event::Copy)),
+            )
+        });
+        let selected = self
+            .data
+            .selected_snip_item
+            .and_then(|id| self.data.snip_items.iter().position(|item| item.id == id));
+
+        // move through the rows in the order the list shows them
+        let order = self.list_order();
+        // the folder filter can hide every snip
+        if order.is_empty() {
+            return false;
+        }
+        let row = selected.and_then(|index| order.iter().position(|&i| i == index));
+        let moved_to = match row {
+            _ if !up && !down => None,
+            None => Some(0),
+            Some(row) if up => Some(row.saturating_sub(1)),
+            Some(row) => Some((row + 1).min(order.len() - 1)),
+        };
+        if let Some(row) = moved_to {
+            self.data.selected_snip_item = Some(self.data.snip_items[order[row]].id);
+            return true;
+        }
+
+        let Some(index) = selected else {
+            return false;
+        };
+        if enter {
+            ctx.memory_mut(|memory| memory.request_focus(egui::Id::new(TEX_EDITOR_ID)));
+        } else if delete {
+            self.delete_snip(self.data.snip_items[index].id);
+            return true;
+        } else if copy {
+            let typst = self.data.snip_items[index].typst.clone();
+            ctx.copy_text(typst.clone());
+            self.record_copy(&typst);
+        }
+        false
+    }
+
+    /// The capture buttons and the list of snips.
+    fn show_snip_list(&mut self, ui: &mut egui::Ui, restore_scroll: bool, scroll_to_selected: bool) {
+        let ctx = ui.ctx().clone();
+        ui.horizontal(|ui| {
+            if ui.button(tr("Capture")).clicked() {
+                send_task(&self.task_sender, &mut self.toasts, SnipTask::new());
+            }
+            let region = ui
+                .button(tr("Region"))
+                .on_hover_text(tr("Select a region to capture and remember, right click to pick a display"));
+            if region.clicked() {
+                self.displays = capture::displays();
+                self.region_overlay = self.overlay_bounds(self.data.capture_display.as_deref());
+            }
+            region.context_menu(|ui| {
+                if ui.button(tr("All displays")).clicked() {
+                    self.region_overlay = self.overlay_bounds(None);
+                    ui.close_menu();
+                }
+                for display in &self.displays {
+                    if ui.button(&display.name).clicked() {
+                        self.region_overlay = Some(display.bounds);
+                        ui.close_menu();
+                    }
+                }
+            });
+            let repeat = ui
+                .add_enabled(self.data.last_region.is_some(), egui::Button::new(tr("Repeat")))
+                .on_hover_text(tr("Capture the last selected region again"));
+            if repeat.clicked() {
+                send_task(&self.task_sender, &mut self.toasts, SnipTask::with_kind(TaskKind::RepeatRegion));
+            }
+            if ui.button(tr("Clipboard")).on_hover_text(tr("OCR the image on the clipboard")).clicked() {
+                send_task(&self.task_sender, &mut self.toasts, SnipTask::with_kind(TaskKind::ClipboardImage));
+            }
+            if self.deleted_snip.is_some() && ui.button(tr("Undo delete")).clicked() {
+                self.undo_delete();
+            }
+        });
+
+        if self.merge_selection.len() > 1 {
+            ui.horizontal(|ui| {
+                let label = tr("Merge {} snips").replace("{}", &self.merge_selection.len().to_string());
+                if ui.button(label).clicked() {
+                    self.merge_snips();
+                }
+                if ui.button(tr("Export screenshots…")).clicked() {
+                    let selection = self.merge_selection.clone();
+                    self.export_screenshots(&selection);
+                }
+                if ui.button(tr("Export Markdown…")).clicked() {
+                    let selection = self.merge_selection.clone();
+                    self.export_markdown(&selection);
+                }
+                if ui.button(tr("Export .tex…")).clicked() {
+                    let selection = self.merge_selection.clone();
+                    self.export_latex(&selection);
+                }
+                if ui.button(tr("Print / Save as PDF…")).clicked() {
+                    let selection = self.merge_selection.clone();
+                    self.print_report(&ctx, &selection);
+                }
+                if ui.button(tr("Cancel")).clicked() {
+                    self.merge_selection.clear();
+                }
+            });
+        }
+
+        let mut snip_folders: Vec<&str> = Vec::new();
+        for snip_item in &self.data.snip_items {
+            let folder_id = snip_item.folder_id();
+            if !folder_id.is_empty() && !snip_folders.contains(&folder_id) {
+                snip_folders.push(folder_id);
+            }
+        }
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("snip_sort")
+                .selected_text(self.data.layout.snip_sort.label())
+                .show_ui(ui, |ui| {
+                    for sort in SnipSort::ALL {
+                        ui.selectable_value(&mut self.data.layout.snip_sort, sort, sort.label());
+                    }
+                });
+            if !snip_folders.is_empty() {
+                let folder_filter = &mut self.data.layout.folder_filter;
+                let selected = match folder_filter {
+                    Some(id) => folders::folder_name(&self.data.mathpix_folders, id).to_string(),
+                    None => tr("All folders").to_string(),
+                };
+                egui::ComboBox::from_id_salt("snip_folder_filter").selected_text(selected).show_ui(ui, |ui| {
+                    ui.selectable_value(folder_filter, None, tr("All folders"));
+                    for id in snip_folders {
+                        let name = folders::folder_name(&self.data.mathpix_folders, id);
+                        ui.selectable_value(folder_filter, Some(id.to_string()), name);
+                    }
+                });
+            }
+        });
+
+        ui.separator();
+
+        const ROW_HEIGHT: f32 = 30.0;
+        let order = self.list_order();
+        let mut table = egui_extras::TableBuilder::new(ui);
+        if restore_scroll {
+            table = table.vertical_scroll_offset(self.data.layout.list_scroll);
+        }
+        if scroll_to_selected {
+            let selected_row =
+                order.iter().position(|&index| Some(self.data.snip_items[index].id) == self.data.selected_snip_item);
+            if let Some(row) = selected_row {
+                table = table.scroll_to_row(row, None);
+            }
+        }
+        let mut delete = None;
+        let mut export = None;
+        let mut open_window = None;
+        let snip_menu = |ui: &mut egui::Ui,
+                         delete: &mut Option<Uuid>,
+                         export: &mut Option<(Uuid, SnipExport)>,
+                         open_window: &mut Option<Uuid>,
+                         id: Uuid| {
+            if ui.button(tr("Open in a new window")).clicked() {
+                *open_window = Some(id);
+                ui.close_menu();
+            }
+            for (kind, label) in [
+                (SnipExport::Screenshots, tr("Export screenshots…")),
+                (SnipExport::Markdown, tr("Export Markdown…")),
+                (SnipExport::Latex, tr("Export .tex…")),
+                (SnipExport::Org, tr("Export org-mode…")),
+                (SnipExport::Report, tr("Print / Save as PDF…")),
+            ] {
+                if ui.button(label).clicked() {
+                    *export = Some((id, kind));
+                    ui.close_menu();
+                }
+            }
+            if ui.button(tr("Delete")).clicked() {
+                *delete = Some(id);
+                ui.close_menu();
+            }
+        };
+        let list_scroll = table
+            .striped(true)
+            .resizable(false)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::remainder().clip(true))
+            .sense(egui::Sense::click())
+            .header(0.0, |_| {})
+            .body(|body| {
+                // only the visible rows are built, so images further down are not loaded until scrolled to
+                body.rows(ROW_HEIGHT, order.len(), |mut row| {
+                    let snip_item = &self.data.snip_items[order[row.index()]];
+                    row.set_selected(
+                        self.data.selected_snip_item.as_ref() == Some(&snip_item.id)
+                            || self.merge_selection.contains(&snip_item.id),
+                    );
+                    let mut clicked = false;
+                    row.col(|ui| {
+                        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                        let thumbnail_size = egui::vec2(ROW_HEIGHT * 2.0, ROW_HEIGHT - 4.0);
+                        // the thumbnail made from the screenshot, the one Mathpix hosts until it is ready
+                        let thumbnail = match self.images.thumbnail(&snip_item.local_image) {
+                            Some((thumbnail, _)) => Some(thumbnail),
+                            None if !snip_item.thumbnail.is_empty() => {
+                                self.images.shown(&snip_item.thumbnail);
+                                Some(snip_item.thumbnail.clone())
+                            }
+                            None => None,
+                        };
+                        match thumbnail {
+                            Some(uri) => {
+                                ui.add(egui::Image::from_uri(uri).max_size(thumbnail_size).corner_radius(2.0));
+                            }
+                            None if !snip_item.local_image.is_empty() => {
+                                ui.add_sized(thumbnail_size, egui::Spinner::new());
+                            }
+                            None => {}
+                        }
+                        let label = if snip_item.error.is_empty() {
+                            ui.label(&snip_item.title).on_hover_text(&snip_item.title)
+                        } else {
+                            ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {}", snip_item.title))
+                                .on_hover_text(&snip_item.error)
+                        };
+                        clicked |= label.clicked();
+                        label.context_menu(|ui| snip_menu(ui, &mut delete, &mut export, &mut open_window, snip_item.id));
+                    });
+                    let response = row.response();
+                    response.context_menu(|ui| snip_menu(ui, &mut delete, &mut export, &mut open_window, snip_item.id));
+                    if clicked || response.clicked() {
+                        if response.ctx.input(|i| i.modifiers.command) {
+                            if self.merge_selection.is_empty() {
+                                self.merge_selection.extend(self.data.selected_snip_item);
+                            }
+                            if let Some(index) = self.merge_selection.iter().position(|id| *id == snip_item.id) {
+                                self.merge_selection.remove(index);
+                            } else {
+                                self.merge_selection.push(snip_item.id);
+                            }
+                        } else {
+                            self.merge_selection.clear();
+                        }
+                        self.data.selected_snip_item = Some(snip_item.id);
+                    }
+                });
+            });
+        self.data.layout.list_scroll = list_scroll.state.offset.y;
+        if let Some((id, kind)) = export {
+            // export the whole selection when the menu was opened on one of its snips
+            let ids = if self.merge_selection.contains(&id) { self.merge_selection.clone() } else { vec![id] };
+            match kind {
+                SnipExport::Screenshots => self.export_screenshots(&ids),
+                SnipExport::Markdown => self.export_markdown(&ids),
+                SnipExport::Latex => self.export_latex(&ids),
+                SnipExport::Org => self.export_org(&ids),
+                SnipExport::Report => self.print_report(&ctx, &ids),
+            }
+        }
+        if let Some(id) = open_window {
+            self.open_snip_window(id);
+        }
+        if let Some(id) = delete {
+            self.delete_snip(id);
+        }
+    }
+
+    /// The screenshots of the selected snip with its title, copy actions, details and tags.
+    fn show_snip_preview(&mut self, ui: &mut egui::Ui, restore_scroll: bool) {
+        let ctx = ui.ctx().clone();
+        let mut delete = None;
+        let mut open_viewer = None;
+        let mut copied = None;
+        let mut copy_as = None;
+        let mut share = None;
+        let mut share_link = None;
+        let mut open_window = None;
+        let send_to_editor = self.data.send_to_editor.command.trim().to_string();
+        let selected = self.data.selected_snip_item;
+        let Some(snip_item) = self.data.snip_items.iter_mut().find(|item| Some(item.id) == selected) else {
+            ui.weak(tr("No snip selected"));
+            return;
+        };
+        // edited in place by the widgets below
+        mark_changed(&mut self.snip_store, snip_item.id);
+        let mut scroll_area = egui::ScrollArea::vertical();
+        if restore_scroll {
+            scroll_area = scroll_area.vertical_scroll_offset(self.data.layout.detail_scroll);
+        }
+        let scroll = scroll_area.show(ui, |ui| {
+            ui.add_space(10.0);
+            ui.vertical_centered(|ui| {
+                // show physical pixels at the size they had on screen
+                let scale = 1.0 / (snip_item.pixel_scale.unwrap_or(1.0) * ui.ctx().zoom_factor());
+                for image in std::iter::once(&snip_item.local_image).chain(snip_item.extra_images.iter()) {
+                    let Some((preview, factor)) = self.images.preview(image) else {
+                        ui.add_sized([250.0, 100.0], egui::Spinner::new());
+                        continue;
+                    };
+                    let response = ui.add(
+                        egui::Image::from_uri(preview)
+                            .fit_to_original_size(scale * factor)
+                            .max_height(250.0)
+                            .corner_radius(10.0)
+                            .sense(egui::Sense::click()),
+                    );
+                    if response.on_hover_text(tr("Click to zoom")).clicked() {
+                        open_viewer = Some(ImageViewer {
+                            images: std::iter::once(&snip_item.local_image)
+                                .chain(snip_item.extra_images.iter())
+                                .map(|image| capture::image_uri(image))
+                                .collect(),
+                            pixel_scale: snip_item.pixel_scale.unwrap_or(1.0),
+                            zoom: 1.0,
+                            rendered_image: snip_item.rendered_image.clone(),
+                            compare: false,
+                            compare_offset: egui::Vec2::ZERO,
+                        });
+                    }
+                }
+                if !snip_item.rendered_image.is_empty()
+                    && ui.button(tr("compare")).on_hover_text(tr("Show the capture next to the rendered result")).clicked()
+                {
+                    open_viewer = Some(ImageViewer {
+                        images: vec![capture::image_uri(&snip_item.local_image)],
+                        pixel_scale: snip_item.pixel_scale.unwrap_or(1.0),
+                        zoom: 1.0,
+                        rendered_image: snip_item.rendered_image.clone(),
+                        compare: true,
+                        compare_offset: egui::Vec2::ZERO,
+                    });
+                }
+            });
+
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(&snip_item.title).strong());
+                if copy_button(ui, "copy_title", &snip_item.title) {
+                    copied = Some(snip_item.title.clone());
+                }
+                if ui.small_button("⧉").on_hover_text(tr("Open in a new window")).clicked() {
+                    open_window = Some(snip_item.id);
+                }
+                if ui.small_button("🗑").on_hover_text(tr("Delete")).clicked() {
+                    delete = Some(snip_item.id);
+                }
+            });
+            if !snip_item.error.is_empty() {
+                ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {}", snip_item.error));
+            }
+            ui.horizontal(|ui| {
+                ui.label(tr("Copy as:"));
+                for (copy, label) in [
+                    (SnipCopy::Typst, "Typst"),
+                    (SnipCopy::TeX, "TeX"),
+                    (SnipCopy::Unicode, "Unicode"),
+                    (SnipCopy::TypstBlock, "```typst"),
+                    (SnipCopy::TeXBlock, "```latex"),
+                    (SnipCopy::Org, "org"),
+                    (SnipCopy::Continuous, tr("continuous")),
+                    (SnipCopy::Image, tr("image")),
+                ] {
+                    if ui.small_button(label).clicked() {
+                        copy_as = Some((snip_item.id, copy));
+                    }
+                }
+                if !send_to_editor.is_empty() && ui.small_button(tr("editor")).on_hover_text(send_to_editor.as_str()).clicked() {
+                    copy_as = Some((snip_item.id, SnipCopy::Editor));
+                }
+                let has_mathpix_id = snip_item.metadata.as_ref().is_some_and(|metadata| !metadata.mathpix_id.is_empty());
+                if has_mathpix_id
+                    && ui.small_button(tr("share link")).on_hover_text(tr("Copy a public link to the snip on Mathpix")).clicked()
+                {
+                    share_link = Some(snip_item.id);
+                }
+                if !platform::SHARE_SERVICES.is_empty() {
+                    ui.menu_button(tr("Share…"), |ui| {
+                        for &(label, service) in platform::SHARE_SERVICES {
+                            if ui.button(tr(label)).clicked() {
+                                share = Some((snip_item.id, service));
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                }
+            });
+
+            ui.add_space(16.0);
+            egui::CollapsingHeader::new(tr("Details")).id_salt("snip_details").show(ui, |ui| {
+                show_snip_details(ui, snip_item);
+            });
+
+            ui.add_space(16.0);
+            ui.horizontal(|ui| {
+                ui.label(tr("Tags"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut snip_item.tags)
+                        .hint_text(tr("comma separated"))
+                        .desired_width(f32::INFINITY),
+                );
+            });
+        });
+        self.data.layout.detail_scroll = scroll.state.offset.y;
+
+        if let Some(text) = copied {
+            self.record_copy(&text);
+        }
+        if let Some((id, copy)) = copy_as {
+            self.copy_snip(&ctx, id, copy);
+        }
+        if let Some((id, service)) = share {
+            self.share_snip(id, service);
+        }
+        if let Some(id) = share_link {
+            self.copy_share_link(&ctx, id);
+        }
+        if open_viewer.is_some() {
+            self.image_viewer = open_viewer;
+        }
+        if let Some(id) = open_window {
+            self.open_snip_window(id);
+        }
+        if let Some(id) = delete {
+            self.delete_snip(id);
+        }
+    }
+
+    fn open_snip_window(&mut self, id: Uuid) {
+        if !self.snip_windows.contains(&id) {
+            self.snip_windows.push(id);
+        }
+    }
+
+    /// Snips opened in windows of their own, to compare or edit two of them side by side.
+    fn show_snip_windows(&mut self, ctx: &egui::Context) {
+        let code_text_color = self.data.theme.code_text_color();
+        let settings = ConversionSettings {
+            normalizations: &self.data.normalizations,
+            replace_rules: &self.data.replace_rules,
+            typst_format: &self.data.typst_format,
+            cleanup: &self.data.output_cleanup,
+        };
+        let snip_items = &mut self.data.snip_items;
+        let images = &mut self.images;
+        let snip_store = &mut self.snip_store;
+        self.snip_windows.retain(|id| {
+            let Some(snip_item) = snip_items.iter_mut().find(|item| item.id == *id) else {
+                return false;
+            };
+            // edited in place by the widgets below
+            mark_changed(snip_store, snip_item.id);
+            let preview = images.preview(&snip_item.local_image);
+            let mut open = true;
+            ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of(("snip_window", id)),
+                egui::ViewportBuilder::default()
+                    .with_title(format!("Typst Scan — {}", snip_item.title))
+                    .with_inner_size([520.0, 640.0]),
+                |ctx, _class| {
+                    open = !ctx.input(|i| i.viewport().close_requested());
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        egui::ScrollArea::vertical().show(ui, |ui| {
+                            ui.vertical_centered(|ui| {
+                                match &preview {
+                                    Some((preview, _)) => {
+                                        ui.add(egui::Image::from_uri(preview).max_height(200.0).corner_radius(10.0));
+                                    }
+                                    None => {
+                                        ui.spinner();
+                                    }
+                                }
+                            });
+                            ui.add_space(10.0);
+                            ui.label(egui::RichText::new(&snip_item.title).strong());
+                            ui.add_space(10.0);
+                            ui.label("TeX");
+                            ui.add(
+                                egui::TextEdit::multiline(&mut snip_item.tex)
+                                    .code_editor()
+                                    .text_color_opt(code_text_color)
+                                    .desired_width(f32::INFINITY)
+                                    .desired_rows(5),
+                            );
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.label("Typst");
+                                if ui.button(tr("regenerate")).clicked() {
+                                    let source = snip_item.typst_source.unwrap_or(TexSource::Text);
+                                    let (typst, source) = snip_item.convert(&settings, source);
+                                    snip_item.typst = typst;
+                                    snip_item.typst_source = Some(source);
+                                }
+                            });
+                            ui.add(
+                                egui::TextEdit::multiline(&mut snip_item.typst)
+                                    .code_editor()
+                                    .text_color_opt(code_text_color)
+                                    .desired_width(f32::INFINITY)
+                                    .desired_rows(5),
+                            );
+                        });
+                    });
+                },
+            );
+            open
+        });
+    }
+
+    /// Editor for the TeX of the selected snip, with its validation warnings.
+    fn show_snip_tex(&mut self, ui: &mut egui::Ui) {
+        let code_text_color = self.data.theme.code_text_color();
+        let mut split = None;
+        let mut copied = None;
+        let selected = self.data.selected_snip_item;
+        let Some(snip_item) = self.data.snip_items.iter_mut().find(|item| Some(item.id) == selected) else {
+            return;
+        };
+        // edited in place by the widgets below
+        mark_changed(&mut self.snip_store, snip_item.id);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if copy_button(ui, "copy_tex", &snip_item.tex) {
+                    copied = Some(snip_item.tex.clone());
+                }
+                if convert::split_display_math(&snip_item.tex).len() > 1
+                    && ui.button(tr("split")).on_hover_text(tr("Make a snip of every equation")).clicked()
+                {
+                    split = Some(snip_item.id);
+                }
+            });
+            ui.add(
+                egui::TextEdit::multiline(&mut snip_item.tex)
+                    .id(egui::Id::new(TEX_EDITOR_ID))
+                    .code_editor()
+                    .text_color_opt(code_text_color)
+                    .desired_width(f32::INFINITY)
+                    .desired_rows(5),
+            );
+            let warn_color = ui.visuals().warn_fg_color;
+            for warning in convert::validate(&snip_item.tex) {
+                ui.colored_label(warn_color, format!("⚠ {}", warning.message()));
+            }
+            if snip_item.typst.starts_with("Error:") {
+                ui.colored_label(warn_color, format!("⚠ {}", tr("Conversion to Typst failed")));
+            }
+            for (label, id, text) in [
+                (TexSource::LatexStyled.label(), "copy_latex_styled", &snip_item.latex_styled),
+                ("Markdown (MMD)", "copy_mmd", &snip_item.mmd),
+                ("HTML", "copy_html", &snip_item.html),
+            ] {
+                if text.is_empty() {
+                    continue;
+                }
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.label(label);
+                    if copy_button(ui, id, text) {
+                        copied = Some(text.clone());
+                    }
+                });
+                ui.add(
+                    egui::TextEdit::multiline(&mut text.as_str())
+                        .id_salt(id)
+                        .code_editor()
+                        .text_color_opt(code_text_color)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(2),
+                );
+            }
+        });
+        if let Some(id) = split {
+            self.split_snip(id);
+        }
+        if let Some(text) = copied {
+            self.record_copy(&text);
+        }
+    }
+
+    /// Editor for the Typst of the selected snip.
+    fn show_snip_typst(&mut self, ui: &mut egui::Ui) {
+        let code_text_color = self.data.theme.code_text_color();
+        let settings = ConversionSettings {
+            normalizations: &self.data.normalizations,
+            replace_rules: &self.data.replace_rules,
+            typst_format: &self.data.typst_format,
+            cleanup: &self.data.output_cleanup,
+        };
+        let mut copied = None;
+        let selected = self.data.selected_snip_item;
+        let Some(snip_item) = self.data.snip_items.iter_mut().find(|item| Some(item.id) == selected) else {
+            return;
+        };
+        // edited in place by the widgets below
+        mark_changed(&mut self.snip_store, snip_item.id);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if copy_button(ui, "copy_typst", &snip_item.typst) {
+                    copied = Some(snip_item.typst.clone());
+                }
+                let mut source = snip_item.typst_source.unwrap_or(TexSource::Text);
+                let mut regenerate = ui.button(tr("regenerate")).clicked();
+                if !snip_item.latex_styled.is_empty() {
+                    egui::ComboBox::from_id_salt("typst_source_picker")
+                        .selected_text(source.label())
+                        .show_ui(ui, |ui| {
+                            for option in TexSource::ALL {
+                                regenerate |= ui.selectable_value(&mut source, option, option.label()).clicked();
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr("Output of Mathpix the Typst is converted from"));
+                }
+                if regenerate {
+                    let (typst, source) = snip_item.convert(&settings, source);
+                    snip_item.typst = typst;
+                    snip_item.typst_source = Some(source);
+                }
+            });
+            ui.add(
+                egui::TextEdit::multiline(&mut snip_item.typst)
+                    .code_editor()
+                    .text_color_opt(code_text_color)
+                    .desired_width(f32::INFINITY)
+                    .desired_rows(5),
+            );
+        });
+        if let Some(text) = copied {
+            self.record_copy(&text);
+        }
+    }
+
+    /// The result as Mathpix rendered it, to check the recognition at a glance.
+    fn show_snip_render(&mut self, ui: &mut egui::Ui) {
+        let selected = self.data.selected_snip_item;
+        let Some(snip_item) = self.data.snip_items.iter().find(|item| Some(item.id) == selected) else {
+            return;
+        };
+        if snip_item.rendered_image.is_empty() {
+            ui.weak(tr("No rendered image for this snip"));
+            return;
+        }
+        self.images.shown(&snip_item.rendered_image);
+        egui::ScrollArea::both().show(ui, |ui| {
+            ui.add(egui::Image::from_uri(&snip_item.rendered_image).max_width(ui.available_width()));
+        });
+    }
+
+    /// Indices into `snip_items` in the order the snip list shows them.
+    fn list_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.data.snip_items.len()).collect();
+        if let Some(folder_id) = &self.data.layout.folder_filter {
+            order.retain(|&index| self.data.snip_items[index].folder_id() == folder_id);
+        }
+        match self.data.layout.snip_sort {
+            SnipSort::NewestFirst => order.reverse(),
+            SnipSort::OldestFirst => {}
+            SnipSort::Title => order.sort_by_cached_key(|&index| self.data.snip_items[index].title.to_lowercase()),
+        }
+        order
+    }
+
+    /// Move a snip to the trash, Ctrl+Z brings it back for a few seconds.
+    fn delete_snip(&mut self, id: Uuid) {
+        let Some(index) = self.data.snip_items.iter().position(|item| item.id == id) else {
+            return;
+        };
+        let snip_item = self.data.snip_items.remove(index);
+        // select the snip shown below the deleted one, or the one above at the end of the list
+        let next = index.checked_sub(1).or((index < self.data.snip_items.len()).then_some(index));
+        self.data.selected_snip_item = next.map(|next| self.data.snip_items[next].id);
+        self.merge_selection.retain(|selected| *selected != id);
+
+        self.data.trash.push(TrashedSnip {
+            snip_item,
+            deleted_at: chrono::Local::now().timestamp(),
+        });
+        self.deleted_snip = Some(DeletedSnip {
+            id,
+            index,
+            deleted_at: Instant::now(),
+        });
+        self.toasts
+            .info(tr("Snip moved to the trash, press Ctrl+Z to undo"))
+            .duration(Some(Duration::from_secs(UNDO_DELETE_SECS)));
+    }
+
+    fn undo_delete(&mut self) {
+        let Some(deleted) = self.deleted_snip.take() else {
+            return;
+        };
+        if let Some(position) = self.data.trash.iter().position(|trashed| trashed.snip_item.id == deleted.id) {
+            let trashed = self.data.trash.remove(position);
+            self.data.selected_snip_item = Some(deleted.id);
+            let index = deleted.index.min(self.data.snip_items.len());
+            self.data.snip_items.insert(index, trashed.snip_item);
+        }
+    }
+
+    /// Forget the undo once its time is over and purge snips that were in the trash for too long.
+    fn expire_deleted_snips(&mut self, ctx: &egui::Context) {
+        if let Some(deleted) = &self.deleted_snip {
+            let remaining = Duration::from_secs(UNDO_DELETE_SECS).saturating_sub(deleted.deleted_at.elapsed());
+            if remaining.is_zero() {
+                self.deleted_snip = None;
+            } else {
+                ctx.request_repaint_after(remaining);
+            }
+        }
+
+        let cutoff = chrono::Local::now().timestamp() - self.data.trash_retention_days as i64 * 24 * 60 * 60;
+        if self.data.trash.iter().any(|trashed| trashed.deleted_at < cutoff) {
+            let (expired, kept) = std::mem::take(&mut self.data.trash)
+                .into_iter()
+                .partition::<Vec<_>, _>(|trashed| trashed.deleted_at < cutoff);
+            self.data.trash = kept;
+            for trashed in expired {
+                remove_snip_files(ctx, &trashed.snip_item, &self.data);
+            }
+        }
+    }
+
+    /// Put a snip from the trash back into the list, where it was by capture time.
+    fn restore_snip(&mut self, id: Uuid) {
+        let Some(position) = self.data.trash.iter().position(|trashed| trashed.snip_item.id == id) else {
+            return;
+        };
+        let snip_item = self.data.trash.remove(position).snip_item;
+        let index = self
+            .data
+            .snip_items
+            .iter()
+            .position(|item| snip_item.created_at.is_some() && item.created_at > snip_item.created_at)
+            .unwrap_or(self.data.snip_items.len());
+        self.data.selected_snip_item = Some(id);
+        self.data.snip_items.insert(index, snip_item);
+    }
+
+    /// Remember text put on the clipboard, moving a repeated copy to the top.
+    fn record_copy(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.data.copy_history.retain(|copied| copied != text);
+        self.data.copy_history.insert(0, text.to_string());
+        self.data.copy_history.truncate(self.data.copy_history_size.max(1));
+    }
+
+    fn show_copy_history(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("Keep the last"));
+            ui.add(egui::DragValue::new(&mut self.data.copy_history_size).range(1..=200));
+            ui.label(tr("copied texts"));
+            if ui.add_enabled(!self.data.copy_history.is_empty(), egui::Button::new(tr("Clear"))).clicked() {
+                self.data.copy_history.clear();
+            }
+        });
+        ui.separator();
+
+        if self.data.copy_history.is_empty() {
+            ui.weak(tr("Nothing was copied yet"));
+            return;
+        }
+        let mut copy = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("copy_history_grid").num_columns(2).striped(true).spacing([16.0, 6.0]).show(ui, |ui| {
+                for text in self.data.copy_history.iter() {
+                    if ui.small_button("📋").on_hover_text(tr("Copy again")).clicked() {
+                        copy = Some(text.clone());
+                    }
+                    let first_line = text.lines().next().unwrap_or_default();
+                    let preview = if text.lines().nth(1).is_some() { format!("{first_line} …") } else { first_line.to_string() };
+                    ui.add(egui::Label::new(egui::RichText::new(preview).monospace()).truncate()).on_hover_text(text);
+                    ui.end_row();
+                }
+            });
+        });
+        if let Some(text) = copy {
+            ui.ctx().copy_text(text.clone());
+            self.record_copy(&text);
+        }
+    }
+
+    fn show_trash(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("Snips in the trash are removed for good after"));
+            ui.add(egui::DragValue::new(&mut self.data.trash_retention_days).range(1..=365));
+            ui.label(tr("days"));
+            if ui.add_enabled(!self.data.trash.is_empty(), egui::Button::new(tr("Empty trash"))).clicked() {
+                for trashed in std::mem::take(&mut self.data.trash) {
+                    remove_snip_files(ui.ctx(), &trashed.snip_item, &self.data);
+                }
+                self.deleted_snip = None;
+            }
+        });
+        ui.separator();
+
+        if self.data.trash.is_empty() {
+            ui.weak(tr("The trash is empty"));
+            return;
+        }
+        let mut restore = None;
+        let mut purge = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("trash_grid").num_columns(3).striped(true).spacing([16.0, 6.0]).show(ui, |ui| {
+                for trashed in self.data.trash.iter().rev() {
+                    ui.label(&trashed.snip_item.title);
+                    let deleted_at = chrono::DateTime::from_timestamp(trashed.deleted_at, 0)
+                        .map(|deleted_at| deleted_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_default();
+                    ui.label(deleted_at);
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("Restore")).clicked() {
+                            restore = Some(trashed.snip_item.id);
+                        }
+                        if ui.button(tr("Delete permanently")).clicked() {
+                            purge = Some(trashed.snip_item.id);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        });
+        if let Some(id) = restore {
+            self.restore_snip(id);
+        }
+        if let Some(id) = purge {
+            if let Some(position) = self.data.trash.iter().position(|trashed| trashed.snip_item.id == id) {
+                let trashed = self.data.trash.remove(position);
+                remove_snip_files(ui.ctx(), &trashed.snip_item, &self.data);
+            }
+        }
+    }
+
+    /// Output of the most recent snip in the format of the current clipboard mode.
+    fn latest_output(&self) -> Option<String> {
+        self.data.snip_items.last().map(|snip_item| self.output_of(snip_item))
+    }
+
+    /// A snip in the format of the current clipboard mode.
+    fn output_of(&self, snip_item: &SnipItem) -> String {
+        let template = match self.data.clipboard_mode {
+            ClipboardMode::CopyTeX => &self.data.output_templates.tex,
+            ClipboardMode::CopyTypst => &self.data.output_templates.typst,
+            ClipboardMode::Continuous => &self.data.output_templates.continuous,
+            ClipboardMode::CopyUnicode => &self.data.output_templates.unicode,
+        };
+        self.fill_template(template, snip_item)
+    }
+
+    fn fill_template(&self, template: &str, snip_item: &SnipItem) -> String {
+        let date = snip_item
+            .created_at
+            .and_then(|timestamp| chrono::DateTime::from_timestamp(timestamp, 0))
+            .map(|created_at| created_at.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        let fields = TemplateFields {
+            typst: &snip_item.typst,
+            tex: &convert::copy_tex(&snip_item.tex, &self.data.output_cleanup, self.data.tex_delimiters),
+            unicode: &convert::tex_to_unicode(&snip_item.tex),
+            title: &snip_item.title,
+            date: &date,
+            image_path: &capture::resolve_image_path(&snip_item.local_image).to_string_lossy(),
+        };
+        convert::fill_template(template, &fields)
+    }
+
+    fn show_lock_screen(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 3.0);
+                ui.heading(tr("🔒 Typst Scan is locked"));
+                ui.add_space(8.0);
+                let input = ui.add(
+                    egui::TextEdit::singleline(&mut self.passcode_input)
+                        .password(true)
+                        .hint_text(tr("passcode"))
+                        .desired_width(160.0),
+                );
+                input.request_focus();
+                let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if (ui.button(tr("Unlock")).clicked() || submitted) && !self.passcode_input.is_empty() {
+                    if self.data.app_lock.unlocks(&self.passcode_input) {
+                        self.locked = false;
+                    } else {
+                        self.toasts.error(tr("Wrong passcode")).duration(Some(Duration::from_secs(3)));
+                    }
+                    self.passcode_input.clear();
+                }
+            });
+        });
+        self.toasts.show(ctx);
+    }
+
+    /// Frameless popup with the latest results, arrow keys pick one and Enter copies it.
+    fn show_quick_copy(&mut self, ctx: &egui::Context) {
+        const QUICK_COPY_ITEMS: usize = 9;
+        let Some(selected) = self.quick_copy else {
+            return;
+        };
+        let snip_items: Vec<&SnipItem> =
+            self.data.snip_items.iter().rev().filter(|snip_item| snip_item.error.is_empty()).take(QUICK_COPY_ITEMS).collect();
+        let outputs: Vec<String> = snip_items.iter().map(|snip_item| self.output_of(snip_item)).collect();
+        let images = &mut self.images;
+        let thumbnails: Vec<Option<String>> = snip_items
+            .iter()
+            .map(|snip_item| images.thumbnail(&snip_item.local_image).map(|(thumbnail, _)| thumbnail))
+            .collect();
+
+        let mut selected = selected.min(outputs.len().saturating_sub(1));
+        let mut copy = None;
+        let mut close = false;
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("quick_copy"),
+            egui::ViewportBuilder::default()
+                .with_title("Typst Scan Quick Copy")
+                .with_decorations(false)
+                .with_always_on_top()
+                .with_active(true)
+                .with_inner_size([420.0, 280.0]),
+            |ctx, _class| {
+                ctx.input(|i| {
+                    if i.key_pressed(egui::Key::ArrowDown) {
+                        selected = (selected + 1).min(outputs.len().saturating_sub(1));
+                    }
+                    if i.key_pressed(egui::Key::ArrowUp) {
+                        selected = selected.saturating_sub(1);
+                    }
+                    if i.key_pressed(egui::Key::Enter) {
+                        copy = Some(selected);
+                    }
+                    close = i.key_pressed(egui::Key::Escape) || i.viewport().close_requested();
+                });
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    if outputs.is_empty() {
+                        ui.weak(tr("No snips yet"));
+                        return;
+                    }
+                    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Truncate);
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (i, output) in outputs.iter().enumerate() {
+                            let first_line = output.lines().next().unwrap_or_default();
+                            let label = ui
+                                .horizontal(|ui| {
+                                    let size = egui::vec2(40.0, 20.0);
+                                    match &thumbnails[i] {
+                                        Some(thumbnail) => ui.add(egui::Image::from_uri(thumbnail).max_size(size)),
+                                        None => ui.add_sized(size, egui::Spinner::new()),
+                                    };
+                                    ui.selectable_label(i == selected, egui::RichText::new(first_line).monospace())
+                                })
+                                .inner;
+                            if label.clicked() {
+                                copy = Some(i);
+                            }
+                            if i == selected {
+                                label.scroll_to_me(None);
+                            }
+                        }
+                    });
+                });
+            },
+        );
+
+        if let Some(output) = copy.and_then(|i| outputs.get(i)) {
+            ctx.copy_text(output.clone());
+            self.record_copy(output);
+            close = true;
+        }
+        self.quick_copy = (!close).then_some(selected);
+    }
+
+    /// Add an entry to the continuous clipboard, labelled when equation numbering is on.
+    fn append_continuous(&mut self, entry: &str) {
+        self.data.continuous_clipboard.push_str(entry);
+        if self.data.equation_numbering {
+            let label = self.data.equation_label_format.replace("{n}", &self.data.next_equation_number.to_string());
+            self.data.continuous_clipboard.push(' ');
+            self.data.continuous_clipboard.push_str(&label);
+            self.data.next_equation_number += 1;
+        }
+        self.data.continuous_clipboard.push('\n');
+    }
+
+    /// Copy one snip in the given way, whatever the clipboard mode is.
+    fn copy_snip(&mut self, ctx: &egui::Context, id: Uuid, copy: SnipCopy) {
+        let Some(snip_item) = self.data.snip_items.iter().find(|item| item.id == id) else {
+            return;
+        };
+        match copy {
+            SnipCopy::Typst | SnipCopy::TeX | SnipCopy::Unicode | SnipCopy::TypstBlock | SnipCopy::TeXBlock => {
+                let templates = &self.data.output_templates;
+                let (template, language) = match copy {
+                    SnipCopy::Typst => (&templates.typst, None),
+                    SnipCopy::TypstBlock => (&templates.typst, Some("typst")),
+                    SnipCopy::TeX => (&templates.tex, None),
+                    SnipCopy::TeXBlock => (&templates.tex, Some("latex")),
+                    _ => (&templates.unicode, None),
+                };
+                let text = self.fill_template(template, snip_item);
+                let text = match language {
+                    Some(language) => convert::fenced_code(&text, language),
+                    None => text,
+                };
+                ctx.copy_text(text.clone());
+                self.record_copy(&text);
+            }
+            SnipCopy::Org => {
+                let text = export::org_block(&snip_item.tex);
+                ctx.copy_text(text.clone());
+                self.record_copy(&text);
+            }
+            SnipCopy::Continuous => {
+                let entry = self.fill_template(&self.data.output_templates.continuous, snip_item);
+                self.append_continuous(&entry);
+                self.toasts.info(tr("Added to the continuous clipboard")).duration(Some(Duration::from_secs(2)));
+            }
+            SnipCopy::Editor => {
+                let text = self.fill_template(&self.data.output_templates.typst, snip_item);
+                let command = self.data.send_to_editor.command.clone();
+                let sent = platform::send_to_editor(&command, &text);
+                if let Err(e) = sent {
+                    eprintln!("Failed to send to the editor: {}", e);
+                    self.toasts.error(format!("{} {}", tr("Failed to send to the editor:"), e));
+                }
+            }
+            SnipCopy::Image => {
+                let path = capture::resolve_image_path(&snip_item.local_image);
+                let copied = image::open(path).map_err(|e| e.to_string()).and_then(|image| {
+                    let image = image.to_rgba8();
+                    let image_data = arboard::ImageData {
+                        width: image.width() as usize,
+                        height: image.height() as usize,
+                        bytes: image.into_raw().into(),
+                    };
+                    Clipboard::new().and_then(|mut clipboard| clipboard.set_image(image_data)).map_err(|e| e.to_string())
+                });
+                if let Err(e) = copied {
+                    eprintln!("Failed to copy the image: {}", e);
+                    self.toasts.error(format!("{} {}", tr("Failed to copy the image:"), e));
+                }
+            }
+        }
+    }
+
+    /// Send the screenshot and the output of a snip to a share service of the system.
+    fn share_snip(&mut self, id: Uuid, service: &str) {
+        let Some(snip_item) = self.data.snip_items.iter().find(|item| item.id == id) else {
+            return;
+        };
+        let text = self.output_of(snip_item);
+        let image_path = capture::resolve_image_path(&snip_item.local_image);
+        if let Err(e) = platform::share(service, &snip_item.title, &text, &image_path.to_string_lossy()) {
+            eprintln!("Failed to share: {}", e);
+            self.toasts.error(format!("{} {}", tr("Failed to share:"), e));
+        }
+    }
+
+    /// Copy the public link of a snip, asking Mathpix for one the first time.
+    fn copy_share_link(&mut self, ctx: &egui::Context, id: Uuid) {
+        let snip_item = self.data.snip_items.iter().find(|item| item.id == id);
+        let Some(metadata) = snip_item.and_then(|item| item.metadata.as_ref()) else {
+            return;
+        };
+        if !metadata.share_url.is_empty() {
+            let link = metadata.share_url.clone();
+            self.apply_share_link(ctx, id, Ok(link));
+            return;
+        }
+        if self.share_link_receivers.iter().any(|(pending, _)| *pending == id) {
+            return;
+        }
+        let receiver = worker::request_share_link(
+            ctx.clone(),
+            self.data.mathpix_api_key.clone(),
+            self.data.tls.clone(),
+            metadata.mathpix_id.clone(),
+        );
+        self.share_link_receivers.push((id, receiver));
+    }
+
+    fn apply_share_link(&mut self, ctx: &egui::Context, id: Uuid, link: Result<String, String>) {
+        match link {
+            Ok(link) => {
+                let snip_item = self.data.snip_items.iter_mut().find(|item| item.id == id);
+                if let Some(metadata) = snip_item.and_then(|item| item.metadata.as_mut()) {
+                    metadata.share_url = link.clone();
+                    mark_changed(&mut self.snip_store, id);
+                }
+                ctx.copy_text(link.clone());
+                self.record_copy(&link);
+                self.toasts.info(tr("Share link copied")).duration(Some(Duration::from_secs(2)));
+            }
+            Err(e) => {
+                eprintln!("Failed to get a share link: {}", e);
+                self.toasts.error(format!("{} {}", tr("Failed to get a share link:"), e));
+            }
+        }
+    }
+
+    /// Apply the results that arrived since the last frame in the order they were captured.
+    fn apply_results(&mut self, mut results: Vec<TaskResult>) {
+        results.sort_by_key(|result| result.sequence);
+        for result in results {
+            self.apply_result(result);
+        }
+    }
+
+    fn apply_result(&mut self, result: TaskResult) {
+        // the worker does not copy a result older than one it already copied
+        if let Some(copied) = &result.copied {
+            let newer = match self.newest_copy {
+                Some(newest) => result.sequence > newest,
+                None => true,
+            };
+            if newer {
+                self.newest_copy = Some(result.sequence);
+                self.record_copy(copied);
+            }
+        }
+        match result.clipboard_mode {
+            ClipboardMode::Continuous => {
+                let fields = TemplateFields {
+                    typst: &result.typst,
+                    tex: &result.text,
+                    unicode: &convert::tex_to_unicode(&result.text),
+                    title: &result.title,
+                    date: &chrono::Local::now().format("%Y-%m-%d").to_string(),
+                    image_path: &result.local_image,
+                };
+                let entry = convert::fill_template(&self.data.output_templates.continuous, &fields);
+                self.append_continuous(&entry);
+            }
+            _ => {}
+        }
+
+        let cost = self.data.cost_tracking.enabled.then(|| {
+            if result.overage {
+                self.data.cost_tracking.overage_price
+            } else {
+                0.0
+            }
+        });
+        if let Some(cost) = cost {
+            self.data.cost_tracking.record(cost);
+        }
+        self.data.snip_items.push(SnipItem {
+            id: result.id,
+            title: result.title,
+            local_image: capture::stored_image_path(std::path::Path::new(&result.local_image)),
+            original_image: result.original_image,
+            rendered_image: result.rendered_image,
+            thumbnail: result.thumbnail,
+            tex: result.text,
+            typst: result.typst,
+            created_at: Some(chrono::Local::now().timestamp()),
+            confidence: Some(result.confidence),
+            ocr_time_ms: Some(result.ocr_time_ms),
+            clipboard_mode: Some(result.clipboard_mode),
+            tags: String::new(),
+            metadata: Some(result.metadata),
+            extra_images: Vec::new(),
+            pixel_scale: Some(result.pixel_scale),
+            image_hash: result.image_hash,
+            timings: Some(result.timings),
+            error: String::new(),
+            latex_styled: result.latex.unwrap_or_default(),
+            typst_source: Some(result.typst_source),
+            preset: Some(result.preset),
+            html: result.html,
+            mmd: result.mmd,
+            cost,
+        });
+        self.data.selected_snip_item = Some(result.id);
+        if let Some(snip_item) = self.data.snip_items.last() {
+            self.bridge.publish(snip_item.bridge_result());
+            if let Some(path) = self.data.result_file.path().filter(|_| self.data.result_file.enabled) {
+                let typst = self.fill_template(&self.data.output_templates.typst, snip_item);
+                self.result_file_writer.write(path, typst);
+            }
+        }
+        if result.region.is_some() {
+            self.data.last_region = result.region;
+        }
+        // reused results do not report the usage
+        if result.snip_limit > 0 {
+            self.data.api_used = result.snip_count;
+            self.data.api_limit = result.snip_limit;
+        }
+        let month = cost::current_month();
+        let spent = self.data.cost_tracking.month_spent();
+        if self.data.cost_tracking.over_budget(spent) && self.data.cost_tracking.warned_month != month {
+            self.toasts.warning(
+                tr("Snips this month cost an estimated ${}, more than the budget")
+                    .replace("{}", &format!("{:.2}", spent)),
+            );
+            self.data.cost_tracking.warned_month = month;
+        }
+    }
+
+    /// Keep a capture Mathpix could not recognize in the list, with the reason it failed.
+    fn add_failed_snip(&mut self, id: Uuid, local_image: String, error: String) {
+        self.data.snip_items.push(SnipItem {
+            id,
+            title: tr("Recognition failed").to_string(),
+            local_image: capture::stored_image_path(std::path::Path::new(&local_image)),
+            original_image: String::new(),
+            rendered_image: String::new(),
+            tex: String::new(),
+            typst: String::new(),
+            created_at: Some(chrono::Local::now().timestamp()),
+            confidence: None,
+            ocr_time_ms: None,
+            clipboard_mode: None,
+            tags: String::new(),
+            metadata: None,
+            extra_images: Vec::new(),
+            pixel_scale: None,
+            thumbnail: String::new(),
+            image_hash: String::new(),
+            timings: None,
+            error,
+            latex_styled: String::new(),
+            typst_source: None,
+            preset: None,
+            html: String::new(),
+            mmd: String::new(),
+            cost: None,
+        });
+        self.data.selected_snip_item = Some(id);
+    }
+
+    /// Typst for TeX typed or split in the UI, converted the same way the worker converts OCR results.
+    fn tex_to_typst(&self, tex: &str) -> String {
+        worker::convert_tex(&self.data.conversion_settings(), OcrPreset::Equation, tex, "", TexSource::Text).0
+    }
+
+    /// Replace a snip holding several equations with a snip per equation and copy each of them.
+    fn split_snip(&mut self, id: Uuid) {
+        let Some(index) = self.data.snip_items.iter().position(|item| item.id == id) else {
+            return;
+        };
+        let snip_item = self.data.snip_items.remove(index);
+        let parts: Vec<SnipItem> = convert::split_display_math(&snip_item.tex)
+            .into_iter()
+            .enumerate()
+            .map(|(i, tex)| SnipItem {
+                id: Uuid::new_v4(),
+                title: format!("{} ({})", snip_item.title, i + 1),
+                typst: self.tex_to_typst(&tex),
+                tex,
+                metadata: None,
+                latex_styled: String::new(),
+                typst_source: Some(TexSource::Text),
+                html: String::new(),
+                mmd: String::new(),
+                cost: None,
+                ..snip_item.clone()
+            })
+            .collect();
+
+        let mut clipboard = Clipboard::new().map_err(|e| eprintln!("Failed to open the clipboard: {:?}", e)).ok();
+        for part in parts.iter() {
+            let text = self.output_of(part);
+            self.record_copy(&text);
+            if let Some(Err(e)) = clipboard.as_mut().map(|clipboard| clipboard.set_text(text)) {
+                eprintln!("Failed to copy the split snip: {:?}", e);
+            }
+        }
+
+        self.data.selected_snip_item = parts.first().map(|part| part.id);
+        self.data.snip_items.splice(index..index, parts);
+        // kept in the trash, where it can be restored from if the split was not wanted
+        self.data.trash.push(TrashedSnip {
+            snip_item,
+            deleted_at: chrono::Local::now().timestamp(),
+        });
+    }
+
+    /// Combine the snips picked for merging, in the order they were taken, into one snip in place of the last.
+    fn merge_snips(&mut self) {
+        let merge_selection = std::mem::take(&mut self.merge_selection);
+        let Some(last_index) = self.data.snip_items.iter().rposition(|item| merge_selection.contains(&item.id)) else {
+            return;
+        };
+        let (merged, kept): (Vec<SnipItem>, Vec<SnipItem>) = std::mem::take(&mut self.data.snip_items)
+            .into_iter()
+            .partition(|item| merge_selection.contains(&item.id));
+        self.data.snip_items = kept;
+        let first = &merged[0];
+
+        let joiner = self.data.merge_joiner.replace("\\n", "\n");
+        let join = |field: fn(&SnipItem) -> &str, separator: &str| {
+            merged.iter().map(field).filter(|text| !text.is_empty()).collect::<Vec<_>>().join(separator)
+        };
+        let mut extra_images = Vec::new();
+        for item in merged.iter() {
+            for image in std::iter::once(&item.local_image).chain(item.extra_images.iter()) {
+                if *image != first.local_image && !extra_images.contains(image) {
+                    extra_images.push(image.clone());
+                }
+            }
+        }
+        let merged_item = SnipItem {
+            id: Uuid::new_v4(),
+            title: join(|item| &item.title, " + "),
+            tex: join(|item| &item.tex, "\n"),
+            typst: join(|item| &item.typst, &joiner),
+            created_at: Some(chrono::Local::now().timestamp()),
+            confidence: None,
+            ocr_time_ms: None,
+            tags: join(|item| &item.tags, ", "),
+            metadata: None,
+            extra_images,
+            image_hash: String::new(),
+            timings: None,
+            error: String::new(),
+            latex_styled: String::new(),
+            typst_source: Some(TexSource::Text),
+            html: String::new(),
+            mmd: String::new(),
+            cost: None,
+            ..first.clone()
+        };
+
+        self.data.selected_snip_item = Some(merged_item.id);
+        self.data.snip_items.insert(last_index + 1 - merged.len(), merged_item);
+        // kept in the trash, where they can be restored from if the merge was not wanted
+        let deleted_at = chrono::Local::now().timestamp();
+        self.data.trash.extend(merged.into_iter().map(|snip_item| TrashedSnip { snip_item, deleted_at }));
+    }
+
+    /// Copy the screenshots of the given snips to a folder picked by the user, named after their titles.
+    fn export_screenshots(&mut self, ids: &[Uuid]) {
+        let screenshots: Vec<(String, String)> = self
+            .data
+            .snip_items
+            .iter()
+            .filter(|item| ids.contains(&item.id))
+            .flat_map(|item| {
+                let name = capture::sanitize_file_name(&item.title);
+                std::iter::once(&item.local_image).chain(item.extra_images.iter()).map(move |image| (name.clone(), image.clone()))
+            })
+            .collect();
+        if screenshots.is_empty() {
+            return;
+        }
+        let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        match capture::export_screenshots(&dir, &screenshots) {
+            Ok(count) => {
+                let message = tr("Exported {} screenshots").replace("{}", &count.to_string());
+                self.toasts.success(message).duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                eprintln!("Failed to export screenshots: {}", e);
+                self.toasts.error(format!("{} {}", tr("Failed to export screenshots:"), e));
+            }
+        }
+    }
+
+    /// The given snips in the order of the list, for the document exports.
+    fn export_snips(&self, ids: &[Uuid]) -> Vec<ExportSnip<'_>> {
+        self.data
+            .snip_items
+            .iter()
+            .filter(|item| ids.contains(&item.id))
+            .map(|item| ExportSnip {
+                title: &item.title,
+                tex: &item.tex,
+                typst: &item.typst,
+                image_path: capture::resolve_image_path(&item.local_image).to_string_lossy().to_string(),
+            })
+            .collect()
+    }
+
+    /// Save the given snips as Pandoc Markdown, for Quarto and Pandoc pipelines.
+    fn export_markdown(&mut self, ids: &[Uuid]) {
+        let markdown = export::markdown(&self.export_snips(ids));
+        self.save_export(&markdown, "Markdown", "md");
+    }
+
+    fn export_org(&mut self, ids: &[Uuid]) {
+        let org = export::org(&self.export_snips(ids));
+        self.save_export(&org, "Org", "org");
+    }
+
+    /// Save the given snips as a LaTeX document built from the template in the settings.
+    fn export_latex(&mut self, ids: &[Uuid]) {
+        if ids.is_empty() {
+            return;
+        }
+        let latex = export::latex(&self.export_snips(ids), &self.data.latex_template);
+        self.save_export(&latex, "LaTeX", "tex");
+    }
+
+    /// Open a report of the given snips in the browser, which prints it or saves it as PDF.
+    fn print_report(&mut self, ctx: &egui::Context, ids: &[Uuid]) {
+        if ids.is_empty() {
+            return;
+        }
+        let html = export::report_html(&self.export_snips(ids));
+        let path = std::env::temp_dir().join("typst_scan_report.html");
+        match std::fs::write(&path, html) {
+            Ok(()) => ctx.open_url(egui::OpenUrl::new_tab(format!("file://{}", path.display()))),
+            Err(e) => {
+                eprintln!("Failed to write the report {:?}: {:?}", path, e);
+                self.toasts.error(format!("{} {}", tr("Failed to export:"), e));
+            }
+        }
+    }
+
+    /// Write an export to a file picked by the user.
+    fn save_export(&mut self, contents: &str, filter: &str, extension: &str) {
+        if contents.is_empty() {
+            return;
+        }
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(filter, &[extension])
+            .set_file_name(format!("snips.{}", extension))
+            .save_file()
+        else {
+            return;
+        };
+        match std::fs::write(&path, contents) {
+            Ok(()) => {
+                let message = tr("Exported to {}").replace("{}", &path.display().to_string());
+                self.toasts.success(message).duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                eprintln!("Failed to export {:?}: {:?}", path, e);
+                self.toasts.error(format!("{} {}", tr("Failed to export:"), e));
+            }
+        }
+    }
+
+    /// A white canvas to draw a formula on with the mouse or a stylus, recognized like a screenshot.
+    fn show_canvas(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!self.strokes.is_empty(), egui::Button::new(tr("Recognize"))).clicked() {
+                let strokes =
+                    self.strokes.drain(..).map(|stroke| stroke.iter().map(|(p, pressure)| [p.x, p.y, *pressure]).collect()).collect();
+                send_task(&self.task_sender, &mut self.toasts, SnipTask::with_kind(TaskKind::Drawing(strokes)));
+            }
+            if ui.add_enabled(!self.strokes.is_empty(), egui::Button::new(tr("Undo"))).clicked() {
+                self.strokes.pop();
+            }
+            if ui.add_enabled(!self.strokes.is_empty(), egui::Button::new(tr("Clear"))).clicked() {
+                self.strokes.clear();
+            }
+        });
+        ui.add_space(8.0);
+
+        let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::drag());
+        let rect = response.rect;
+        painter.rect_filled(rect, 4.0, egui::Color32::WHITE);
+        if response.drag_started() {
+            self.strokes.push(Vec::new());
+        }
+        if let (true, Some(pos)) = (response.dragged(), response.interact_pointer_pos()) {
+            let point = (pos - rect.min).to_pos2();
+            // tablets report the pen pressure as touch force, a mouse draws at full pressure
+            let pressure = ui.input(|i| {
+                i.events.iter().rev().find_map(|event| match event {
+                    egui::Event::Touch { force: Some(force), .. } => Some(*force),
+                    _ => None,
+                })
+            });
+            if let Some(stroke) = self.strokes.last_mut() {
+                if stroke.last().map(|(last, _)| *last) != Some(point) {
+                    stroke.push((point, pressure.unwrap_or(1.0)));
+                }
+            }
+        }
+        for stroke in self.strokes.iter() {
+            let points: Vec<(egui::Pos2, f32)> = stroke.iter().map(|(p, pressure)| (rect.min + p.to_vec2(), *pressure)).collect();
+            for (i, (point, pressure)) in points.iter().enumerate() {
+                let width = 5.0 * pressure.clamp(0.25, 1.5);
+                match i.checked_sub(1).map(|previous| points[previous].0) {
+                    Some(previous) => painter.line_segment([previous, *point], egui::Stroke::new(width, egui::Color32::BLACK)),
+                    None => painter.circle_filled(*point, width / 2.0, egui::Color32::BLACK),
+                };
+            }
+        }
+    }
+
+    /// Show the remaining seconds of a delayed capture in a small window above everything else.
+    fn show_countdown(&self, ctx: &egui::Context) {
+        let Some(remaining) = self.countdown else {
+            return;
+        };
+
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("capture_countdown"),
+            egui::ViewportBuilder::default()
+                .with_title(tr("Capture Countdown"))
+                .with_decorations(false)
+                .with_always_on_top()
+                .with_mouse_passthrough(true)
+                .with_inner_size([160.0, 80.0]),
+            |ctx, _class| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.centered_and_justified(|ui| {
+                        ui.heading(tr("Capturing in {}…").replace("{}", &remaining.to_string()));
+                    });
+                });
+            },
+        );
+    }
+
+    /// Small always-on-top window with just a capture button and the latest result,
+    /// for working in another application without the full window on screen.
+    fn show_mini_overlay(&mut self, ctx: &egui::Context) {
+        if !self.data.mini_overlay {
+            return;
+        }
+
+        let latest_typst = self.data.snip_items.last().map(|snip_item| snip_item.typst.clone());
+        let mut capture = false;
+        let mut copied = false;
+        let mut close = false;
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("mini_overlay"),
+            egui::ViewportBuilder::default()
+                .with_title("Typst Scan Mini")
+                .with_always_on_top()
+                .with_inner_size([300.0, 140.0]),
+            |ctx, _class| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        capture = ui.button(tr("Capture")).clicked();
+                        if ui.add_enabled(latest_typst.is_some(), egui::Button::new(tr("Copy"))).clicked() {
+                            ctx.copy_text(latest_typst.clone().unwrap_or_default());
+                            copied = true;
+                        }
+                    });
+                    ui.separator();
+                    egui::ScrollArea::vertical().show(ui, |ui| match &latest_typst {
+                        Some(typst) => {
+                            ui.monospace(typst);
+                        }
+                        None => {
+                            ui.weak(tr("No snips yet"));
+                        }
+                    });
+                });
+                close = ctx.input(|i| i.viewport().close_requested());
+            },
+        );
+
+        if capture {
+            send_task(&self.task_sender, &mut self.toasts, SnipTask::new());
+        }
+        if let Some(typst) = latest_typst.filter(|_| copied) {
+            self.record_copy(&typst);
+        }
+        if close {
+            self.data.mini_overlay = false;
+        }
+    }
+
+    /// Bounds of the named display for the region overlay, spanning all displays when `None`
+    /// or when the display is no longer connected.
+    fn overlay_bounds(&self, display_name: Option<&str>) -> Option<CaptureRegion> {
+        display_name
+            .and_then(|name| self.displays.iter().find(|display| display.name == name))
+            .map(|display| display.bounds)
+            .or_else(|| capture::virtual_desktop(&self.displays))
+    }
+
+    /// Show a translucent overlay on a display where the user drags out the region to capture.
+    fn show_region_overlay(&mut self, ctx: &egui::Context) {
+        let Some(bounds) = self.region_overlay else {
+            return;
+        };
+
+        let mut selected_region = None;
+        let mut cancelled = false;
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("region_overlay"),
+            egui::ViewportBuilder::default()
+                .with_title(tr("Select Region"))
+                .with_decorations(false)
+                .with_transparent(true)
+                .with_always_on_top()
+                .with_position([bounds.x, bounds.y])
+                .with_inner_size([bounds.width, bounds.height]),
+            |ctx, _class| {
+                egui::CentralPanel::default()
+                    .frame(egui::Frame::NONE.fill(egui::Color32::from_black_alpha(80)))
+                    .show(ctx, |ui| {
+                        ui.ctx().set_cursor_icon(egui::CursorIcon::Crosshair);
+                        let response = ui.allocate_rect(ui.max_rect(), egui::Sense::drag());
+                        if response.drag_started() {
+                            self.region_drag_start = response.interact_pointer_pos();
+                        }
+                        let (Some(start), Some(current)) = (self.region_drag_start, ctx.pointer_latest_pos()) else {
+                            return;
+                        };
+                        let rect = egui::Rect::from_two_pos(start, current);
+                        ui.painter().rect_stroke(
+                            rect,
+                            0.0,
+                            egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE),
+                            egui::StrokeKind::Outside,
+                        );
+                        if response.drag_stopped() {
+                            self.region_drag_start = None;
+                            if rect.width() >= 4.0 && rect.height() >= 4.0 {
+                                // pointer positions are relative to the overlay, shift them onto the desktop.
+                                // egui points are logical pixels divided by the UI zoom.
+                                let zoom = ctx.zoom_factor();
+                                let origin = ctx
+                                    .input(|i| i.viewport().inner_rect)
+                                    .map(|inner| (inner.min.to_vec2() * zoom).to_pos2())
+                                    .unwrap_or(egui::pos2(bounds.x, bounds.y));
+                                selected_region = Some(CaptureRegion {
+                                    x: origin.x + rect.min.x * zoom,
+                                    y: origin.y + rect.min.y * zoom,
+                                    width: rect.width() * zoom,
+                                    height: rect.height() * zoom,
+                                });
+                            }
+                        }
+                    });
+
+                if ctx.input(|i| i.key_pressed(egui::Key::Escape) || i.viewport().close_requested()) {
+                    cancelled = true;
+                }
+            },
+        );
+
+        if let Some(region) = selected_region {
+            self.data.last_region = Some(region);
+            send_task(&self.task_sender, &mut self.toasts, SnipTask::with_kind(TaskKind::Region(region)));
+        }
+        if selected_region.is_some() || cancelled {
+            self.region_overlay = None;
+            self.region_drag_start = None;
+        }
+    }
+}
+
+/// Have the snip store write a snip edited in place again on the next save.
+fn mark_changed(snip_store: &mut Option<SnipStore>, id: Uuid) {
+    if let Some(snip_store) = snip_store {
+        snip_store.mark_changed(id);
+    }
+}
+
+/// Hand a capture to the worker, telling the user when it is no longer running.
+fn send_task(task_sender: &Sender<SnipTask>, toasts: &mut Toasts, task: SnipTask) {
+    if task_sender.send(task).is_err() {
+        eprintln!("The capture worker stopped");
+        toasts.error(tr("The capture worker stopped, restart Typst Scan to capture again"));
+    }
+}
+
+/// Write an automatic backup of the data, on a thread of its own when `in_background`.
+fn write_auto_backup(data: &TypstScanData, profile: Option<&str>, reason: &'static str, in_background: bool) {
+    let Some(dir) = backup::backups_dir(profile) else {
+        return;
+    };
+    let keep = data.auto_backup.keep;
+    // written unattended and unencrypted, so the API key and passcode stay out, restoring keeps the current ones
+    let snapshot = settings::settings_value(data, false).map(|settings| Backup {
+        settings,
+        snip_items: data.snip_items.clone(),
+        trash: data.trash.clone(),
+    });
+    let write = move || match snapshot.and_then(|backup| backup::write_rotating(&dir, &backup, reason, keep)) {
+        Ok(path) => println!("Backup written to {:?}", path),
+        Err(e) => eprintln!("Failed to write the automatic backup: {}", e),
+    };
+    if in_background {
+        std::thread::spawn(write);
+    } else {
+        write();
+    }
+}
+
+/// Delete the screenshots of a snip that was removed for good and drop them from the image cache,
+/// except the ones other snips still show, as the parts of a split snip share one.
+fn remove_snip_files(ctx: &egui::Context, snip_item: &SnipItem, data: &TypstScanData) {
+    for image in std::iter::once(&snip_item.local_image).chain(snip_item.extra_images.iter()) {
+        let path = capture::resolve_image_path(image);
+        if data.references_image(&path) {
+            continue;
+        }
+        if let Err(e) = std::fs::remove_file(&path) {
+            eprintln!("Failed to remove {:?}: {:?}", path, e);
+        }
+        ctx.forget_image(&capture::image_uri(image));
+        for scaled in Scaled::ALL {
+            let scaled_path = capture::scaled_path(&path, scaled.suffix());
+            if scaled_path.exists() {
+                if let Err(e) = std::fs::remove_file(&scaled_path) {
+                    eprintln!("Failed to remove {:?}: {:?}", scaled_path, e);
+                }
+                ctx.forget_image(&format!("file://{}", scaled_path.display()));
+            }
+        }
+    }
+    for url in [&snip_item.thumbnail, &snip_item.original_image, &snip_item.rendered_image] {
+        if !url.is_empty() {
+            ctx.forget_image(url);
+        }
+    }
+}
+
+fn font_definitions(extra_fonts: &[PathBuf]) -> egui::FontDefinitions {
+    let mut fonts = egui::FontDefinitions::default();
+    fonts.font_data.insert(
+        "JB".to_owned(),
+        Arc::new(FontData::from_static(include_bytes!("../assets/fonts/JetBrainsMono-Regular.ttf"))),
+    );
+    fonts.font_data.insert(
+        "SC".to_owned(),
+        Arc::new(FontData::from_static(include_bytes!("../assets/fonts/NotoSansSC-Regular.ttf"))),
+    );
+    fonts.families.get_mut(&FontFamily::Monospace).unwrap().insert(0, "JB".to_owned());
+    fonts.families.get_mut(&FontFamily::Monospace).unwrap().insert(1, "SC".to_owned());
+    fonts
+        .families
+        .get_mut(&FontFamily::Proportional)
+        .unwrap()
+        .insert(1, "SC".to_owned());
+
+    // user fonts are fallbacks for the glyphs the bundled fonts don't cover
+    for path in extra_fonts {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Failed to load font {:?}: {:?}", path, e);
+                continue;
+            }
+        };
+        let name = path.to_string_lossy().to_string();
+        fonts.font_data.insert(name.clone(), Arc::new(FontData::from_owned(data)));
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            fonts.families.get_mut(&family).unwrap().push(name.clone());
+        }
+    }
+    fonts
+}
+
+/// Process name and window title inputs with a picker of the running windows,
+/// returns true when the list of running windows should be refreshed.
+fn window_target_setting(
+    ui: &mut egui::Ui,
+    id: &str,
+    running_windows: &[TargetWindow],
+    process_name: &mut String,
+    window_title: &mut String,
+) -> bool {
+    let mut refresh = false;
+    ui.vertical(|ui| {
+        ui.horizontal(|ui| {
+            ui.label(tr("Process name:"));
+            ui.text_edit_singleline(process_name);
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Window Title:"));
+            ui.text_edit_singleline(window_title);
+        });
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt(id).selected_text(tr("Pick running window")).width(240.0).show_ui(ui, |ui| {
+                if running_windows.is_empty() {
+                    ui.label(tr("No windows found, try refreshing"));
+                }
+                for window in running_windows {
+                    let label = format!("{} — {}", window.process_name, window.window_title);
+                    let selected = *process_name == window.process_name && *window_title == window.window_title;
+                    if ui.selectable_label(selected, label).clicked() {
+                        *process_name = window.process_name.clone();
+                        *window_title = window.window_title.clone();
+                    }
+                }
+            });
+            refresh = ui.button(tr("refresh")).clicked();
+        });
+    });
+    refresh
+}
+
+/// Small copy button that turns into a check mark for a moment after copying.
+fn copy_button(ui: &mut egui::Ui, id_salt: &str, text: &str) -> bool {
+    const FEEDBACK_SECS: f64 = 1.5;
+    let id = ui.id().with(id_salt);
+    let now = ui.input(|i| i.time);
+    let copied = ui.data(|data| data.get_temp::<f64>(id)).is_some_and(|copied_at| now - copied_at < FEEDBACK_SECS);
+
+    let (icon, hover_text) = if copied { ("✔", tr("Copied")) } else { ("📋", tr("Copy")) };
+    let clicked = ui.small_button(icon).on_hover_text(hover_text).clicked();
+    if clicked {
+        ui.ctx().copy_text(text.to_owned());
+        ui.data_mut(|data| data.insert_temp(id, now));
+    }
+    if copied {
+        ui.ctx().request_repaint_after(Duration::from_secs_f64(FEEDBACK_SECS));
+    }
+    clicked
+}
+
+fn show_snip_details(ui: &mut egui::Ui, snip_item: &SnipItem) {
+    fn row(ui: &mut egui::Ui, label: &str, value: String) {
+        ui.label(label);
+        ui.label(value);
+        ui.end_row();
+    }
+
+    egui::Grid::new("snip_details_grid").num_columns(2).spacing([24.0, 4.0]).show(ui, |ui| {
+        if let Some(created_at) = snip_item.created_at.and_then(|timestamp| chrono::DateTime::from_timestamp(timestamp, 0)) {
+            let created_at = created_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+            row(ui, tr("Captured"), created_at.to_string());
+        }
+        if let Some(confidence) = snip_item.confidence {
+            row(ui, tr("Confidence"), format!("{:.1} %", confidence * 100.0));
+        }
+        if let Some(ocr_time_ms) = snip_item.ocr_time_ms {
+            row(ui, tr("OCR time"), format!("{} ms", ocr_time_ms));
+        }
+        if let Some(timings) = snip_item.timings {
+            ui.label(tr("Latency"));
+            ui.label(format!("{} ms", timings.total_ms())).on_hover_text(format!(
+                "{} {} ms\n{} {} ms\n{} {} ms\n{} {} ms",
+                tr("Capture"),
+                timings.capture_ms,
+                tr("Upload"),
+                timings.upload_ms,
+                tr("OCR"),
+                timings.ocr_ms,
+                tr("Conversion"),
+                timings.conversion_ms
+            ));
+            ui.end_row();
+        }
+        if let Some(pixel_scale) = snip_item.pixel_scale {
+            row(ui, tr("Pixel ratio"), format!("{:.2}×", pixel_scale));
+        }
+        if let Some(source) = snip_item.typst_source {
+            row(ui, tr("Typst converted from"), source.label().to_string());
+        }
+        for (label, url) in [(tr("Original image"), &snip_item.original_image), (tr("Rendered image"), &snip_item.rendered_image)] {
+            if url.is_empty() {
+                continue;
+            }
+            ui.label(label);
+            ui.horizontal(|ui| {
+                ui.hyperlink_to(tr("open"), url).on_hover_text(url);
+                copy_button(ui, label, url);
+            });
+            ui.end_row();
+        }
+        if let Some(metadata) = &snip_item.metadata {
+            row(ui, tr("Mathpix id"), metadata.mathpix_id.clone());
+            row(ui, tr("Processed at"), metadata.created_at.clone());
+            row(ui, tr("Upload time"), format!("{} ms", metadata.read_request_body_ms));
+            row(
+                ui,
+                tr("Auto rotation"),
+                format!("{}° ({:.1} %)", metadata.auto_rotate_degrees, metadata.auto_rotate_confidence * 100.0),
+            );
+            row(ui, tr("Font size"), format!("{:.1}", metadata.font_size));
+            row(ui, tr("OCR version"), metadata.ocr_version.to_string());
+            if !metadata.folder_id.is_empty() {
+                row(ui, tr("Mathpix folder"), metadata.folder_id.clone());
+            }
+        }
+    });
+    if snip_item.created_at.is_none() && snip_item.metadata.is_none() {
+        ui.weak(tr("No details were saved for this snip"));
+    }
+}
+
+fn show_stats(ui: &mut egui::Ui, snip_items: &[SnipItem], cost_tracking: &CostTracking, month_cost: f64) {
+    fn average(values: impl Iterator<Item = f64>) -> Option<f64> {
+        let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+        (count > 0).then(|| sum / count as f64)
+    }
+
+    ui.heading(tr("Overview"));
+    egui::Grid::new("stats_overview").num_columns(2).spacing([40.0, 8.0]).show(ui, |ui| {
+        ui.label(tr("Snips"));
+        ui.label(snip_items.len().to_string());
+        ui.end_row();
+
+        ui.label(tr("Average confidence"));
+        match average(snip_items.iter().filter_map(|item| item.confidence)) {
+            Some(confidence) => ui.label(format!("{:.1} %", confidence * 100.0)),
+            None => ui.weak("–"),
+        };
+        ui.end_row();
+
+        ui.label(tr("Average OCR latency"));
+        match average(snip_items.iter().filter_map(|item| item.ocr_time_ms.map(|ms| ms as f64))) {
+            Some(latency) => ui.label(format!("{:.0} ms", latency)),
+            None => ui.weak("–"),
+        };
+        ui.end_row();
+
+        let timings: Vec<SnipTimings> = snip_items.iter().filter_map(|item| item.timings).collect();
+        let steps: [(&str, fn(&SnipTimings) -> u64); 5] = [
+            (tr("Average end-to-end latency"), SnipTimings::total_ms),
+            (tr("Capture"), |timings| timings.capture_ms),
+            (tr("Upload"), |timings| timings.upload_ms),
+            (tr("OCR"), |timings| timings.ocr_ms),
+            (tr("Conversion"), |timings| timings.conversion_ms),
+        ];
+        for (label, step) in steps {
+            ui.label(label);
+            match average(timings.iter().map(|timings| step(timings) as f64)) {
+                Some(latency) => ui.label(format!("{:.0} ms", latency)),
+                None => ui.weak("–"),
+            };
+            ui.end_row();
+        }
+    });
+
+    if cost_tracking.enabled {
+        ui.add_space(16.0);
+        ui.heading(tr("Cost"));
+        egui::Grid::new("stats_cost").num_columns(2).spacing([40.0, 8.0]).show(ui, |ui| {
+            ui.label(tr("This month"));
+            if cost_tracking.over_budget(month_cost) {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ ${:.2}", month_cost));
+            } else {
+                ui.label(format!("${:.2}", month_cost));
+            }
+            ui.end_row();
+
+            if cost_tracking.monthly_budget > 0.0 {
+                ui.label(tr("Monthly budget"));
+                ui.add(
+                    egui::ProgressBar::new((month_cost / cost_tracking.monthly_budget) as f32)
+                        .text(format!("${:.2}", cost_tracking.monthly_budget))
+                        .desired_width(240.0),
+                );
+                ui.end_row();
+            }
+        });
+    }
+
+    ui.add_space(16.0);
+    ui.heading(tr("Snips per day"));
+    let mut per_day: Vec<(chrono::NaiveDate, usize)> = Vec::new();
+    for item in snip_items {
+        let Some(date) = item
+            .created_at
+            .and_then(|timestamp| chrono::DateTime::from_timestamp(timestamp, 0))
+            .map(|time| time.with_timezone(&chrono::Local).date_naive())
+        else {
+            continue;
+        };
+        match per_day.iter_mut().find(|(day, _)| *day == date) {
+            Some((_, count)) => *count += 1,
+            None => per_day.push((date, 1)),
+        }
+    }
+    per_day.sort_by(|a, b| b.0.cmp(&a.0));
+    per_day.truncate(14);
+    let most = per_day.iter().map(|(_, count)| *count).max().unwrap_or(1);
+    if per_day.is_empty() {
+        ui.weak(tr("No dated snips yet"));
+    }
+    egui::Grid::new("stats_per_day").num_columns(2).show(ui, |ui| {
+        for (day, count) in &per_day {
+            ui.label(day.format("%Y-%m-%d").to_string());
+            ui.add(egui::ProgressBar::new(*count as f32 / most as f32).text(count.to_string()).desired_width(240.0));
+            ui.end_row();
+        }
+    });
+
+    ui.add_space(16.0);
+    ui.heading(tr("Most used tags"));
+    let mut tags: Vec<(&str, usize)> = Vec::new();
+    for tag in snip_items.iter().flat_map(|item| item.tags()) {
+        match tags.iter_mut().find(|(other, _)| *other == tag) {
+            Some((_, count)) => *count += 1,
+            None => tags.push((tag, 1)),
+        }
+    }
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    if tags.is_empty() {
+        ui.weak(tr("No tags yet"));
+    }
+    for (tag, count) in tags.iter().take(10) {
+        ui.label(format!("{tag} ({count})"));
+    }
+
+    ui.add_space(16.0);
+    ui.heading(tr("Clipboard Mode"));
+    egui::Grid::new("stats_clipboard_modes").num_columns(2).show(ui, |ui| {
+        for (mode, label) in [
+            (ClipboardMode::CopyTypst, tr("Copy Typst")),
+            (ClipboardMode::CopyTeX, tr("Copy TeX")),
+            (ClipboardMode::Continuous, tr("Continuous")),
+            (ClipboardMode::CopyUnicode, tr("Copy Unicode")),
+        ] {
+            ui.label(label);
+            ui.label(snip_items.iter().filter(|item| item.clipboard_mode == Some(mode)).count().to_string());
+            ui.end_row();
+        }
+    });
+}
+
+const TEX_EDITOR_ID: &str = "tex_editor";
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+enum MainView {
+    Snips,
+    Draw,
+    ContinuousClipboard,
+    ReplaceRules,
+    Stats,
+    CopyHistory,
+    Trash,
+    Settings,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum ClipboardMode {
+    Continuous,
+    CopyTeX,
+    CopyTypst,
+    CopyUnicode,
+}
+
+impl Default for MainView {
+    fn default() -> Self {
+        Self::Snips
+    }
+}
+
+impl App for TypstScan {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.perf.begin_frame();
+        // for showing images
+        egui_extras::install_image_loaders(ctx);
+
+        let minimized = ctx.input(|i| i.viewport().minimized.unwrap_or(false));
+        if std::mem::replace(&mut self.was_minimized, minimized) && !minimized && self.data.app_lock.on_restore {
+            self.locked = self.data.app_lock.is_enabled();
+        }
+        if self.locked {
+            // nothing else is drawn, results and hotkey actions wait in their channels
+            self.show_lock_screen(ctx);
+            return;
+        }
+
+        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+            // The top panel is often a good place for a menu bar:
+
+            egui::menu::bar(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.data.main_view, MainView::Snips, tr("Snips"));
+                    ui.selectable_value(&mut self.data.main_view, MainView::Draw, tr("Draw"));
+                    ui.selectable_value(&mut self.data.main_view, MainView::ContinuousClipboard, tr("Continuous Clipboard"));
+                    ui.selectable_value(&mut self.data.main_view, MainView::ReplaceRules, tr("Replace Rules"));
+                    ui.selectable_value(&mut self.data.main_view, MainView::Stats, tr("Stats"));
+                    ui.selectable_value(&mut self.data.main_view, MainView::CopyHistory, tr("History"));
+                    ui.selectable_value(&mut self.data.main_view, MainView::Trash, tr("Trash"));
+                    ui.selectable_value(&mut self.data.main_view, MainView::Settings, tr("Settings"));
+                });
+
+                ui.add_space(16.0);
+
+                egui::widgets::global_theme_preference_buttons(ui);
+
+                ui.add_space(16.0);
+
+                egui::ComboBox::from_id_salt("ocr_preset_picker")
+                    .selected_text(self.data.ocr_preset.label())
+                    .show_ui(ui, |ui| {
+                        for preset in OcrPreset::ALL {
+                            ui.selectable_value(&mut self.data.ocr_preset, preset, preset.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text(tr("What the captures contain"));
+
+                let folder_text = if self.data.upload_folder.is_empty() {
+                    tr("No folder").to_string()
+                } else {
+                    folders::folder_name(&self.data.mathpix_folders, &self.data.upload_folder).to_string()
+                };
+                let mut refresh_folders = false;
+                egui::ComboBox::from_id_salt("upload_folder_picker")
+                    .selected_text(folder_text)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.data.upload_folder, String::new(), tr("No folder"));
+                        for folder in &self.data.mathpix_folders {
+                            ui.selectable_value(&mut self.data.upload_folder, folder.id.clone(), &folder.name);
+                        }
+                        ui.separator();
+                        refresh_folders = ui.button(tr("Refresh folders")).clicked();
+                    })
+                    .response
+                    .on_hover_text(tr("Mathpix folder the captures are uploaded into"));
+                if refresh_folders {
+                    self.folders_receiver = Some(folders::fetch_folders(
+                        ctx.clone(),
+                        self.data.mathpix_api_key.clone(),
+                        self.data.tls.clone(),
+                    ));
+                }
+
+                if self.online {
+                    ui.label("🌐").on_hover_text(tr("Online"));
+                } else {
+                    let status = if self.queued_uploads > 0 {
+                        tr("Offline, {} captures will be sent when the network is back")
+                            .replace("{}", &self.queued_uploads.to_string())
+                    } else {
+                        tr("Offline").to_string()
+                    };
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", self.queued_uploads))
+                        .on_hover_text(status);
+                }
+
+                if self.data.auto_paste {
+                    let paste_target = self.data.paste_target.get_or_insert_with(PasteTarget::default);
+                    let picker = egui::ComboBox::from_id_salt("paste_target_quick_picker")
+                        .selected_text(format!("⎘ {}", paste_target.label()))
+                        .width(160.0)
+                        .show_ui(ui, |ui| {
+                            if ui.button(tr("refresh")).clicked() {
+                                self.running_windows = platform::list_windows();
+                            }
+                            if ui.selectable_label(paste_target.is_frontmost(), tr("Frontmost window")).clicked() {
+                                *paste_target = PasteTarget { delay_ms: paste_target.delay_ms, ..Default::default() };
+                            }
+                            for window in &self.running_windows {
+                                let selected = paste_target.process_name == window.process_name
+                                    && paste_target.window_title == window.window_title;
+                                let label = format!("{} — {}", window.process_name, window.window_title);
+                                if ui.selectable_label(selected, label).clicked() {
+                                    paste_target.process_name = window.process_name.clone();
+                                    paste_target.window_title = window.window_title.clone();
+                                }
+                            }
+                        });
+                    picker.response.on_hover_text(tr("Where results are pasted"));
+                }
+
+                ui.toggle_value(&mut self.data.mini_overlay, tr("Mini"))
+                    .on_hover_text(tr("Show a small always-on-top window with the latest result"));
+
+                let pin = ui
+                    .toggle_value(&mut self.data.always_on_top, "📌")
+                    .on_hover_text(tr("Keep this window above other windows"));
+                if pin.changed() {
+                    let level = if self.data.always_on_top {
+                        egui::WindowLevel::AlwaysOnTop
+                    } else {
+                        egui::WindowLevel::Normal
+                    };
+                    ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+                }
+            });
+        });
+
+        if let Some(fetched) = self.folders_receiver.as_ref().and_then(|receiver| receiver.try_recv().ok()) {
+            self.folders_receiver = None;
+            match fetched {
+                Ok(fetched) => self.data.mathpix_folders = fetched,
+                Err(e) => {
+                    self.toasts.error(format!("{} {}", tr("Failed to fetch the Mathpix folders:"), e));
+                }
+            }
+        }
+        let mut share_links = Vec::new();
+        self.share_link_receivers.retain(|(id, receiver)| match receiver.try_recv() {
+            Ok(link) => {
+                share_links.push((*id, link));
+                false
+            }
+            Err(error) => error == mpsc::TryRecvError::Empty,
+        });
+        for (id, link) in share_links {
+            self.apply_share_link(ctx, id, link);
+        }
+        if let Some(release) = self.update_receiver.as_ref().and_then(|receiver| receiver.try_recv().ok()) {
+            if self.data.dismissed_update.as_deref() != Some(release.tag_name.as_str()) {
+                self.available_update = Some(release);
+            }
+        }
+        if let Some(release) = &self.available_update {
+            let mut dismissed = false;
+            egui::TopBottomPanel::top("update_banner").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Typst Scan {} is available").replace("{}", release.version()));
+                    ui.hyperlink_to(tr("download"), &release.html_url);
+                    dismissed = ui.small_button("🗙").clicked();
+                });
+                if !release.body.is_empty() {
+                    egui::CollapsingHeader::new(tr("What's new")).show(ui, |ui| {
+                        egui::ScrollArea::vertical().max_height(160.0).show(ui, |ui| {
+                            ui.label(&release.body);
+                        });
+                    });
+                }
+            });
+            if dismissed {
+                self.data.dismissed_update = Some(release.tag_name.clone());
+                self.available_update = None;
+            }
+        }
+
+        if self.data.quota_low() {
+            egui::TopBottomPanel::top("quota_banner").show(ctx, |ui| {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    tr("Only {} Mathpix snips left, periodic capture is paused until the quota is reset. Captures started by hand, from a hotkey, a button or the bridge, are still sent.")
+                        .replace("{}", &self.data.remaining_snips().to_string()),
+                );
+            });
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| match self.data.main_view {
+            MainView::Snips => {
+                let scroll_to_selected = self.handle_snip_keys(ctx) | std::mem::take(&mut self.scroll_to_snip);
+                let restore_scroll = std::mem::take(&mut self.restore_scroll);
+                // the dock is moved out while its panes borrow the app
+                let mut dock = std::mem::replace(&mut self.data.layout.dock, DockState::new(Vec::new()));
+                DockArea::new(&mut dock)
+                    .id(egui::Id::new("snips_dock"))
+                    .style(egui_dock::Style::from_egui(ui.style()))
+                    .show_inside(ui, &mut SnipPanes { app: self, restore_scroll, scroll_to_selected });
+                self.data.layout.dock = dock;
+            }
+            MainView::Draw => self.show_canvas(ui),
+            MainView::ContinuousClipboard => {
+                ui.heading(tr("Clipboard Mode"));
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.data.clipboard_mode, ClipboardMode::Continuous, tr("Continuous"));
+                    ui.radio_value(&mut self.data.clipboard_mode, ClipboardMode::CopyTeX, tr("Copy TeX"));
+                    ui.radio_value(&mut self.data.clipboard_mode, ClipboardMode::CopyTypst, tr("Copy Typst"));
+                    ui.radio_value(&mut self.data.clipboard_mode, ClipboardMode::CopyUnicode, tr("Copy Unicode"))
+                        .on_hover_text(tr("Plain text math for chat apps and emails"));
+                });
+                ui.add_space(2.0);
+                ui.separator();
+                ui.add_space(8.0);
+                ui.heading(tr("Continuous Clipboard"));
+                ui.horizontal(|ui| {
+                    if ui.button(tr("copy all")).clicked() {
+                        ctx.copy_text(self.data.continuous_clipboard.clone());
+                        self.record_copy(&self.data.continuous_clipboard.clone());
+                    }
+                    if ui.button(tr("take all")).clicked() {
+                        ctx.copy_text(self.data.continuous_clipboard.clone());
+                        self.record_copy(&std::mem::take(&mut self.data.continuous_clipboard));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.add_enabled(
+                        self.data.last_region.is_some(),
+                        egui::Checkbox::new(&mut self.data.auto_capture, tr("Auto capture last region")),
+                    )
+                    .on_disabled_hover_text(tr("Capture a region first"));
+                    ui.label(tr("every"));
+                    ui.add(egui::DragValue::new(&mut self.data.auto_capture_interval_secs).range(1..=3600).suffix(" s"));
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.data.equation_numbering, tr("Number equations"));
+                    ui.add_enabled(
+                        self.data.equation_numbering,
+                        egui::TextEdit::singleline(&mut self.data.equation_label_format).desired_width(100.0),
+                    )
+                    .on_hover_text(tr("Typst label added after each entry, {n} is the number"));
+                    ui.label(tr("next: {}").replace("{}", &self.data.next_equation_number.to_string()));
+                    if ui.button(tr("reset")).clicked() {
+                        self.data.next_equation_number = 1;
+                    }
+                });
+                ui.add_space(8.0);
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.add(egui::TextEdit::multiline(&mut self.data.continuous_clipboard).desired_width(f32::INFINITY));
+                });
+            }
+            MainView::ReplaceRules => {
+                if ui.button(tr("load rules")).clicked() {
+                    let rules_str: String = serde_json::to_string_pretty(&self.data.replace_rules).unwrap();
+                    self.data.replace_rules_json = rules_str;
+                }
+                if ui.button(tr("register rules")).clicked() {
+                    if let Ok(rules) = serde_json::from_str(&self.data.replace_rules_json) {
+                        eprintln!("Parsed rules: {:?}", &rules);
+                        self.data.replace_rules = rules;
+                        self.toasts.success(tr("Registered rules")).duration(Some(Duration::from_secs(5)));
+                    } else {
+                        self.toasts.info(tr("Failed to parse rules")).duration(Some(Duration::from_secs(5)));
+                    }
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.add(egui::TextEdit::multiline(&mut self.data.replace_rules_json).desired_width(f32::INFINITY));
+                });
+            }
+            MainView::Stats => {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    show_stats(ui, &self.data.snip_items, &self.data.cost_tracking, self.data.cost_tracking.month_spent())
+                });
+            }
+            MainView::Trash => self.show_trash(ui),
+            MainView::CopyHistory => self.show_copy_history(ui),
+            MainView::Settings => {
+                egui::ScrollArea::vertical().id_salt("settings_scroll").auto_shrink(false).show(ui, |ui| {
+                    egui::Grid::new("settings_grid")
+                        .num_columns(2)
+                        .spacing([60.0, 16.0])
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label(tr("Language"));
+                            let selected = self.data.language.map_or(tr("System"), |language| language.label());
+                            egui::ComboBox::from_id_salt("language_picker").selected_text(selected).show_ui(ui, |ui| {
+                                let mut changed = ui.selectable_value(&mut self.data.language, None, tr("System")).changed();
+                                for language in Language::ALL {
+                                    changed |= ui.selectable_value(&mut self.data.language, Some(language), language.label()).changed();
+                                }
+                                if changed {
+                                    i18n::set_language(self.data.language.unwrap_or_else(Language::detect));
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Theme"));
+                            ui.vertical(|ui| {
+                                let mut changed = false;
+                                egui::ComboBox::from_id_salt("theme_picker").selected_text(self.data.theme.preset.label()).show_ui(
+                                    ui,
+                                    |ui| {
+                                        for preset in ThemePreset::ALL {
+                                            if ui.selectable_label(self.data.theme.preset == preset, preset.label()).clicked() {
+                                                // start customizing from the colors of the current preset
+                                                self.data.theme = if preset == ThemePreset::Custom {
+                                                    ThemeSettings { preset, ..self.data.theme.clone() }
+                                                } else {
+                                                    ThemeSettings::from_preset(preset)
+                                                };
+                                                changed = true;
+                                            }
+                                        }
+                                    },
+                                );
+                                if self.data.theme.preset == ThemePreset::Custom {
+                                    let theme = &mut self.data.theme;
+                                    for (label, color) in [
+                                        (tr("Accent"), &mut theme.accent),
+                                        (tr("Panels"), &mut theme.panel_background),
+                                        (tr("Code background"), &mut theme.code_background),
+                                        (tr("Code text"), &mut theme.code_text),
+                                    ] {
+                                        ui.horizontal(|ui| {
+                                            changed |= ui.color_edit_button_srgba(color).changed();
+                                            ui.label(label);
+                                        });
+                                    }
+                                }
+                                if changed {
+                                    self.data.theme.apply(ctx);
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Extra Fonts"));
+                            ui.vertical(|ui| {
+                                let mut removed = None;
+                                for (i, path) in self.data.extra_fonts.iter().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        ui.label(path.to_string_lossy());
+                                        if ui.small_button("🗙").clicked() {
+                                            removed = Some(i);
+                                        }
+                                    });
+                                }
+                                let mut changed = false;
+                                if let Some(i) = removed {
+                                    self.data.extra_fonts.remove(i);
+                                    changed = true;
+                                }
+                                if ui.button(tr("add font…")).on_hover_text(tr("For scripts the bundled fonts don't cover")).clicked() {
+                                    if let Some(path) = rfd::FileDialog::new().add_filter("Fonts", &["ttf", "otf", "ttc"]).pick_file() {
+                                        if std::fs::metadata(&path).is_ok() && !self.data.extra_fonts.contains(&path) {
+                                            self.data.extra_fonts.push(path);
+                                            changed = true;
+                                        }
+                                    }
+                                }
+                                if changed {
+                                    ctx.set_fonts(font_definitions(&self.data.extra_fonts));
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Mathpix API Key"));
+                            ui.add(egui::TextEdit::singleline(&mut self.data.mathpix_api_key).password(true));
+                            ui.end_row();
+
+                            ui.label(tr("TLS"));
+                            ui.vertical(|ui| {
+                                let tls = &mut self.data.tls;
+                                ui.horizontal(|ui| {
+                                    ui.label(tr("Root certificates:"));
+                                    match &tls.ca_bundle {
+                                        Some(path) => {
+                                            ui.label(path.display().to_string());
+                                        }
+                                        None => {
+                                            ui.weak(tr("system only"));
+                                        }
+                                    }
+                                    if ui.button(tr("Choose…")).on_hover_text(tr("A PEM file with extra certificates to trust")).clicked() {
+                                        if let Some(path) = rfd::FileDialog::new().add_filter("PEM", &["pem", "crt", "cer"]).pick_file() {
+                                            tls.ca_bundle = Some(path);
+                                        }
+                                    }
+                                    if tls.ca_bundle.is_some() && ui.small_button("🗙").clicked() {
+                                        tls.ca_bundle = None;
+                                    }
+                                });
+                                ui.checkbox(&mut tls.accept_invalid_certs, tr("Accept invalid certificates"));
+                                if tls.accept_invalid_certs {
+                                    ui.colored_label(
+                                        ui.visuals().error_fg_color,
+                                        tr("⚠ Insecure: anyone on the network can read your API key and captures"),
+                                    );
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Global Hotkeys"));
+                            let in_use: Vec<(HotkeyAction, Hotkey)> = self
+                                .data
+                                .hotkeys
+                                .iter()
+                                .filter_map(|binding| binding.hotkey.map(|hotkey| (binding.action, hotkey)))
+                                .collect();
+                            egui::Grid::new("hotkeys_grid").num_columns(2).spacing([16.0, 4.0]).show(ui, |ui| {
+                                for binding in self.data.hotkeys.iter_mut() {
+                                    ui.label(tr(binding.action.label()));
+                                    ui.vertical(|ui| {
+                                        ui.horizontal(|ui| {
+                                            let id = format!("keybind_setter_{:?}", binding.action);
+                                            ui.add(Keybind::new(&mut binding.shortcut, id));
+                                            if ui.button(tr("register")).clicked() && binding.register(&self.hotkey_hook, &in_use) {
+                                                self.toasts.success(tr("Registered hotkey")).duration(Some(Duration::from_secs(5)));
+                                            }
+                                            if ui.add_enabled(binding.hotkey.is_some(), egui::Button::new(tr("clear"))).clicked() {
+                                                binding.clear(&self.hotkey_hook);
+                                            }
+                                        });
+                                        if let Some(error) = &binding.error {
+                                            ui.colored_label(ui.visuals().error_fg_color, error);
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("scrcpy Window"));
+                            ui.vertical(|ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label(tr("Window Title:"));
+                                    ui.text_edit_singleline(&mut self.data.scrcpy_window_title)
+                                        .on_hover_text(tr("Leave empty to use the first scrcpy window"));
+                                    if ui.button(tr("detect")).clicked() {
+                                        self.running_windows = platform::list_windows();
+                                        let scrcpy = self
+                                            .running_windows
+                                            .iter()
+                                            .find(|window| window.process_name.eq_ignore_ascii_case(SCRCPY_PROCESS_NAME));
+                                        match scrcpy {
+                                            Some(window) => self.data.scrcpy_window_title = window.window_title.clone(),
+                                            None => {
+                                                self.toasts.info(tr("No scrcpy window found")).duration(Some(Duration::from_secs(5)));
+                                            }
+                                        }
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label(tr("Crop:"));
+                                    let crop = &mut self.data.scrcpy_crop;
+                                    ui.add(egui::DragValue::new(&mut crop.top).prefix(tr("top ")));
+                                    ui.add(egui::DragValue::new(&mut crop.right).prefix(tr("right ")));
+                                    ui.add(egui::DragValue::new(&mut crop.bottom).prefix(tr("bottom ")));
+                                    ui.add(egui::DragValue::new(&mut crop.left).prefix(tr("left ")));
+                                });
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Capture Delay"));
+                            ui.add(egui::Slider::new(&mut self.data.capture_delay_secs, 0..=10).suffix(" s"))
+                                .on_hover_text(tr("Count down before capturing, to open menus or hover over content first"));
+                            ui.end_row();
+
+                            ui.label(tr("Default Display"));
+                            ui.horizontal(|ui| {
+                                let selected = self.data.capture_display.clone().unwrap_or_else(|| tr("All displays").to_owned());
+                                egui::ComboBox::from_id_salt("display_picker").selected_text(selected).show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.data.capture_display, None, tr("All displays"));
+                                    for display in &self.displays {
+                                        let label = if display.is_primary {
+                                            tr("{} (primary)").replace("{}", &display.name)
+                                        } else {
+                                            display.name.clone()
+                                        };
+                                        let value = Some(display.name.clone());
+                                        ui.selectable_value(&mut self.data.capture_display, value, label);
+                                    }
+                                });
+                                if ui.button(tr("refresh")).clicked() {
+                                    self.displays = capture::displays();
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Settings File"));
+                            ui.horizontal(|ui| {
+                                if ui.button(tr("export…")).clicked() {
+                                    self.export_settings();
+                                }
+                                if ui.button(tr("import…")).clicked() {
+                                    self.import_settings(ctx);
+                                }
+                                if ui
+                                    .button(tr("write config.toml"))
+                                    .on_hover_text(tr("Changes to config.toml in the storage folder are loaded automatically"))
+                                    .clicked()
+                                {
+                                    self.write_config();
+                                }
+                                ui.checkbox(&mut self.export_secrets, tr("include API key"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Layout"));
+                            if ui.button(tr("reset")).on_hover_text(tr("Put the panes of the snips view back in place")).clicked() {
+                                self.data.layout.dock = default_dock();
+                            }
+                            ui.end_row();
+
+                            ui.label(tr("Renderer"));
+                            ui.horizontal(|ui| {
+                                let previous = self.renderer;
+                                egui::ComboBox::from_id_salt("renderer_picker").selected_text(self.renderer.label()).show_ui(ui, |ui| {
+                                    for backend in GraphicsBackend::ALL {
+                                        ui.selectable_value(&mut self.renderer, backend, backend.label());
+                                    }
+                                });
+                                if self.renderer != previous {
+                                    graphics::save(self.profile.as_deref(), self.renderer);
+                                }
+                                ui.weak(format!("{} {}", tr("in use:"), self.active_renderer.label()));
+                            })
+                            .response
+                            .on_hover_text(tr("Used from the next launch. When it fails to start, the others are tried in turn"));
+                            ui.end_row();
+
+                            ui.label(tr("Performance Overlay"));
+                            ui.checkbox(&mut self.data.perf_overlay, tr("show"))
+                                .on_hover_text(tr("Frame time, pending events, worker queues and memory used by images"));
+                            ui.end_row();
+
+                            ui.label(tr("App Lock"));
+                            ui.horizontal(|ui| {
+                                if self.data.app_lock.is_enabled() {
+                                    if ui.button(tr("lock now")).clicked() {
+                                        self.locked = true;
+                                    }
+                                    ui.checkbox(&mut self.data.app_lock.on_restore, tr("lock when restored"))
+                                        .on_hover_text(tr("Ask for the passcode again after the window was minimized"));
+                                    if ui.button(tr("remove")).clicked() {
+                                        self.data.app_lock.disable();
+                                    }
+                                } else {
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut self.passcode_input)
+                                            .password(true)
+                                            .hint_text(tr("passcode"))
+                                            .desired_width(120.0),
+                                    );
+                                    if ui.add_enabled(!self.passcode_input.is_empty(), egui::Button::new(tr("set"))).clicked() {
+                                        self.data.app_lock.set_passcode(&self.passcode_input);
+                                        self.passcode_input.clear();
+                                    }
+                                }
+                            })
+                            .response
+                            .on_hover_text(tr("Hide the snips behind a passcode on launch"));
+                            ui.end_row();
+
+                            ui.label(tr("Backup"));
+                            ui.horizontal(|ui| {
+                                if ui.button(tr("export…")).clicked() {
+                                    self.export_backup();
+                                }
+                                if ui.button(tr("restore…")).clicked() {
+                                    self.restore_backup(ctx);
+                                }
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.backup_password)
+                                        .password(true)
+                                        .hint_text(tr("password (optional)"))
+                                        .desired_width(140.0),
+                                )
+                                .on_hover_text(tr("Backups exported with a password are encrypted with age"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Automatic Backups"));
+                            ui.horizontal(|ui| {
+                                let auto_backup = &mut self.data.auto_backup;
+                                ui.checkbox(&mut auto_backup.enabled, tr("every"));
+                                ui.add(egui::DragValue::new(&mut auto_backup.interval_hours).range(1..=720).suffix(" h"));
+                                ui.label(tr("keep"));
+                                ui.add(egui::DragValue::new(&mut auto_backup.keep).range(1..=100));
+                                let mut restore = None;
+                                egui::ComboBox::from_id_salt("auto_backup_picker").selected_text(tr("restore from…")).show_ui(ui, |ui| {
+                                    let backups = backup::backups_dir(self.profile.as_deref())
+                                        .map(|dir| backup::list_backups(&dir))
+                                        .unwrap_or_default();
+                                    if backups.is_empty() {
+                                        ui.weak(tr("No backups yet"));
+                                    }
+                                    for path in backups {
+                                        let name = path.file_stem().and_then(|name| name.to_str()).unwrap_or_default();
+                                        if ui.selectable_label(false, name.trim_start_matches("backup_")).clicked() {
+                                            restore = Some(path);
+                                        }
+                                    }
+                                });
+                                if let Some(path) = restore {
+                                    self.restore_backup_file(ctx, &path);
+                                }
+                            })
+                            .response
+                            .on_hover_text(tr("Written to the storage directory before data from an older version is migrated and then regularly"));
+                            ui.end_row();
+
+                            ui.label(tr("Reconvert All Snips"));
+                            if ui
+                                .button(tr("reconvert…"))
+                                .on_hover_text(tr("Convert the TeX of every snip again with the current rules and converter"))
+                                .clicked()
+                            {
+                                self.reconvert_preview = Some(self.data.reconvert_changes());
+                            }
+                            ui.end_row();
+
+                            ui.label(tr("Delete All Snips"));
+                            if ui.button(tr("delete!!!")).on_hover_text(tr("Move all snips to the trash")).clicked() {
+                                let deleted_at = chrono::Local::now().timestamp();
+                                for snip_item in self.data.snip_items.drain(..) {
+                                    self.data.trash.push(TrashedSnip { snip_item, deleted_at });
+                                }
+                                self.data.selected_snip_item = None;
+                                self.deleted_snip = None;
+                            }
+                            ui.end_row();
+
+                            ui.label(tr("API usage"));
+                            ui.add(egui::ProgressBar::new(self.data.api_used as f32 / self.data.api_limit as f32).show_percentage());
+                            ui.end_row();
+
+                            ui.label(tr("Cost Tracking"));
+                            ui.horizontal(|ui| {
+                                let costs = &mut self.data.cost_tracking;
+                                ui.checkbox(&mut costs.enabled, tr("Estimate the cost of each snip"));
+                                ui.label(tr("Overage price"));
+                                ui.add(egui::DragValue::new(&mut costs.overage_price).range(0.0..=1.0).speed(0.001).prefix("$"));
+                                ui.label(tr("Monthly budget"));
+                                ui.add(egui::DragValue::new(&mut costs.monthly_budget).range(0.0..=10000.0).prefix("$"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Low Quota"));
+                            ui.horizontal(|ui| {
+                                ui.label(tr("Pause periodic capture below"));
+                                ui.add(egui::DragValue::new(&mut self.data.low_quota_threshold).range(0..=10000))
+                                    .on_hover_text(tr("Only periodic capture is paused, captures started by hand are always sent"));
+                                ui.label(tr("snips left, 0 never pauses it"));
+                            });
+                            ui.end_row();
+
+                            ui.checkbox(&mut self.data.bring_forward, tr("Bring Forward Window"));
+                            if window_target_setting(
+                                ui,
+                                "running_window_picker",
+                                &self.running_windows,
+                                &mut self.data.target_process_name,
+                                &mut self.data.target_window_title,
+                            ) {
+                                self.running_windows = platform::list_windows();
+                            }
+                            ui.end_row();
+
+                            ui.label(tr("Updates"));
+                            ui.checkbox(&mut self.data.check_for_updates, tr("Check for new releases on GitHub at startup"));
+                            ui.end_row();
+
+                            ui.label(tr("Hide When Capturing"));
+                            ui.checkbox(&mut self.data.hide_when_capturing, tr("Minimize this window while taking a screenshot"));
+                            ui.end_row();
+
+                            ui.label(tr("Smart Crop"));
+                            ui.checkbox(&mut self.data.smart_crop, tr("Crop captures to the formula in their middle"));
+                            ui.end_row();
+
+                            ui.label(tr("Request Queue"));
+                            ui.vertical(|ui| {
+                                let limits = &mut self.data.worker_limits;
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::DragValue::new(&mut limits.max_concurrent).range(1..=8));
+                                    ui.label(tr("requests at once"));
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::DragValue::new(&mut limits.max_queued).range(1..=500));
+                                    ui.label(tr("captures may wait"));
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label(tr("When full:"));
+                                    ui.radio_value(&mut limits.when_full, QueueFullPolicy::DropOldest, tr("drop the oldest"));
+                                    ui.radio_value(&mut limits.when_full, QueueFullPolicy::Reject, tr("drop the new capture"));
+                                });
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Result Cache"));
+                            ui.checkbox(&mut self.data.reuse_results, tr("Reuse the result of an identical image instead of sending it again"));
+                            ui.end_row();
+
+                            ui.label(tr("Screenshot Format"));
+                            ui.vertical(|ui| {
+                                let screenshot = &mut self.data.screenshot;
+                                ui.horizontal(|ui| {
+                                    ui.radio_value(&mut screenshot.format, ScreenshotFormat::Png, "PNG");
+                                    ui.radio_value(&mut screenshot.format, ScreenshotFormat::Jpeg, "JPEG");
+                                    ui.add_enabled(
+                                        screenshot.format == ScreenshotFormat::Jpeg,
+                                        egui::Slider::new(&mut screenshot.jpeg_quality, 1..=100).text(tr("quality")),
+                                    );
+                                });
+                                ui.add_enabled(
+                                    screenshot.format == ScreenshotFormat::Jpeg,
+                                    egui::Checkbox::new(&mut screenshot.lossless_original, tr("Keep the lossless PNG, only upload the JPEG")),
+                                );
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Auto Paste"));
+                            ui.vertical(|ui| {
+                                ui.checkbox(&mut self.data.auto_paste, tr("Paste into the target window after copying"));
+                                let paste_target = self.data.paste_target.get_or_insert_with(PasteTarget::default);
+                                ui.add_enabled_ui(self.data.auto_paste, |ui| {
+                                    ui.label(tr("Leave empty to paste into the frontmost window"));
+                                    if window_target_setting(
+                                        ui,
+                                        "paste_target_picker",
+                                        &self.running_windows,
+                                        &mut paste_target.process_name,
+                                        &mut paste_target.window_title,
+                                    ) {
+                                        self.running_windows = platform::list_windows();
+                                    }
+                                    ui.horizontal(|ui| {
+                                        ui.label(tr("Wait before pasting:"));
+                                        ui.add(egui::DragValue::new(&mut paste_target.delay_ms).range(0..=5000).suffix(" ms"));
+                                    });
+                                });
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Editor Bridge"));
+                            if ui
+                                .checkbox(&mut self.data.editor_bridge, tr("Accept commands from editor plugins on a local socket"))
+                                .on_hover_text(tr("Plugins can start captures, read the latest result and subscribe to new ones"))
+                                .changed()
+                            {
+                                self.bridge.set_enabled(self.data.editor_bridge, self.profile.as_deref(), &self.task_sender);
+                            }
+                            ui.end_row();
+
+                            ui.label(tr("Result File"));
+                            ui.vertical(|ui| {
+                                let result_file = &mut self.data.result_file;
+                                ui.checkbox(&mut result_file.enabled, tr("Write the Typst of every result to a file"));
+                                let default_path = ResultFile::default().path().map(|path| path.display().to_string()).unwrap_or_default();
+                                ui.add(egui::TextEdit::singleline(&mut result_file.path).hint_text(default_path).desired_width(420.0));
+                                ui.weak(tr("Replaced with each result. A FIFO made with mkfifo gets the result once something reads from it"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Send to Editor"));
+                            ui.vertical(|ui| {
+                                let editor = &mut self.data.send_to_editor;
+                                ui.checkbox(&mut editor.enabled, tr("Send results to the editor instead of the clipboard"));
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut editor.command)
+                                        .code_editor()
+                                        .hint_text("nvim --server /tmp/nvim.sock --remote-tab {file}")
+                                        .desired_width(420.0),
+                                );
+                                ui.weak(tr("Run by the shell with the Typst on stdin, {file} is a file holding it"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("TeX Delimiters"));
+                            ui.horizontal(|ui| {
+                                egui::ComboBox::from_id_salt("tex_delimiters_picker")
+                                    .selected_text(self.data.tex_delimiters.label())
+                                    .show_ui(ui, |ui| {
+                                        for delimiters in TexDelimiters::ALL {
+                                            ui.selectable_value(&mut self.data.tex_delimiters, delimiters, delimiters.label());
+                                        }
+                                    });
+                                ui.label(tr("Wrapping of single formulas copied in Copy TeX mode"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Extra Formats"));
+                            ui.horizontal(|ui| {
+                                let formats = &mut self.data.extra_formats;
+                                ui.checkbox(&mut formats.latex_styled, TexSource::LatexStyled.label());
+                                ui.checkbox(&mut formats.mmd, "Markdown (MMD)");
+                                ui.checkbox(&mut formats.html, "HTML");
+                                ui.label(tr("Also requested from Mathpix and kept with each snip"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Preset Folders"));
+                            ui.vertical(|ui| {
+                                egui::Grid::new("preset_folders_grid").num_columns(2).show(ui, |ui| {
+                                    let mathpix_folders = &self.data.mathpix_folders;
+                                    for preset in OcrPreset::ALL {
+                                        ui.label(preset.label());
+                                        let folder_id = self.data.preset_folders.entry(preset).or_default();
+                                        let selected = if folder_id.is_empty() {
+                                            tr("Default folder").to_string()
+                                        } else {
+                                            folders::folder_name(mathpix_folders, folder_id).to_string()
+                                        };
+                                        egui::ComboBox::from_id_salt(("preset_folder", preset)).selected_text(selected).show_ui(ui, |ui| {
+                                            ui.selectable_value(folder_id, String::new(), tr("Default folder"));
+                                            for folder in mathpix_folders {
+                                                ui.selectable_value(folder_id, folder.id.clone(), &folder.name);
+                                            }
+                                        });
+                                        ui.end_row();
+                                    }
+                                });
+                                ui.weak(tr("Mathpix folder for the captures of each preset, the default is the one in the toolbar"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Typst Source"));
+                            ui.horizontal(|ui| {
+                                egui::ComboBox::from_id_salt("tex_source_picker")
+                                    .selected_text(self.data.tex_source.label())
+                                    .show_ui(ui, |ui| {
+                                        for source in TexSource::ALL {
+                                            ui.selectable_value(&mut self.data.tex_source, source, source.label());
+                                        }
+                                    });
+                                ui.label(tr("Converted to Typst in the Equation preset, Mathpix styles only single formulas"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Output Templates"));
+                            ui.vertical(|ui| {
+                                let templates = &mut self.data.output_templates;
+                                egui::Grid::new("output_templates_grid").num_columns(2).show(ui, |ui| {
+                                    for (label, template) in [
+                                        (tr("Copy Typst"), &mut templates.typst),
+                                        (tr("Copy TeX"), &mut templates.tex),
+                                        (tr("Continuous"), &mut templates.continuous),
+                                        (tr("Copy Unicode"), &mut templates.unicode),
+                                    ] {
+                                        ui.label(label);
+                                        ui.add(egui::TextEdit::multiline(template).code_editor().desired_rows(1).desired_width(320.0));
+                                        ui.end_row();
+                                    }
+                                });
+                                ui.weak(tr("Placeholders: {typst} {tex} {unicode} {title} {date} {image_path}"));
+                                if ui.button(tr("reset")).clicked() {
+                                    *templates = OutputTemplates::default();
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("LaTeX Export"));
+                            ui.vertical(|ui| {
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut self.data.latex_template)
+                                        .code_editor()
+                                        .desired_rows(6)
+                                        .desired_width(320.0),
+                                );
+                                ui.weak(tr("The snips go at {body}, one equation environment each"));
+                                if ui.button(tr("reset")).clicked() {
+                                    self.data.latex_template = export::DEFAULT_LATEX_TEMPLATE.to_string();
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Output Cleanup"));
+                            ui.vertical(|ui| {
+                                let cleanup = &mut self.data.output_cleanup;
+                                ui.checkbox(&mut cleanup.trim, tr("Trim leading and trailing whitespace"));
+                                ui.checkbox(&mut cleanup.collapse_blank_lines, tr("Collapse repeated blank lines"));
+                                ui.checkbox(
+                                    &mut cleanup.strip_display_delimiters,
+                                    tr("Strip the math delimiters from TeX copied as raw"),
+                                )
+                                .on_hover_text(tr("The stored TeX keeps them, the other delimiter styles replace them"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Typst Formatting"));
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut self.data.typst_format.enabled, tr("Format the Typst output"));
+                                ui.add_enabled(
+                                    self.data.typst_format.enabled,
+                                    egui::DragValue::new(&mut self.data.typst_format.line_width).range(20..=200),
+                                );
+                                ui.label(tr("characters per line"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Merge Joiner"));
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(&mut self.data.merge_joiner).desired_width(120.0));
+                                ui.label(tr("between the Typst of merged snips, \\n for a line break"));
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("TeX Normalization"));
+                            ui.vertical(|ui| {
+                                for normalization in Normalization::ALL {
+                                    let mut enabled = self.data.normalizations.contains(&normalization);
+                                    if ui.checkbox(&mut enabled, normalization.label()).changed() {
+                                        if enabled {
+                                            self.data.normalizations.push(normalization);
+                                        } else {
+                                            self.data.normalizations.retain(|n| *n != normalization);
+                                        }
+                                    }
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Capture Window"));
+                            if window_target_setting(
+                                ui,
+                                "capture_window_picker",
+                                &self.running_windows,
+                                &mut self.data.capture_process_name,
+                                &mut self.data.capture_window_title,
+                            ) {
+                                self.running_windows = platform::list_windows();
+                            }
+                            ui.end_row();
+                        });
+                });
+            }
+        });
+
+        // the worker requests a repaint with every event, handle all that arrived since the last frame
+        let mut results = Vec::new();
+        self.perf.events = 0;
+        while let Ok(event) = self.event_receiver.try_recv() {
+            self.perf.events += 1;
+            match event {
+                WorkerEvent::Countdown(remaining) => self.countdown = (remaining > 0).then_some(remaining),
+                WorkerEvent::HideWindow => ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true)),
+                // no focus, the bring forward target should keep it for auto paste
+                WorkerEvent::RestoreWindow => ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false)),
+                WorkerEvent::Failed(error) => {
+                    self.toasts.error(format!("{} {}", tr("Capture failed:"), error)).duration(Some(Duration::from_secs(8)));
+                }
+                WorkerEvent::Network { online, queued } => {
+                    self.online = online;
+                    self.queued_uploads = queued;
+                }
+                WorkerEvent::RecognitionFailed { id, local_image, error } => {
+                    self.toasts.error(format!("{} {}", tr("Capture failed:"), error)).duration(Some(Duration::from_secs(8)));
+                    self.add_failed_snip(id, local_image, error);
+                }
+                WorkerEvent::Result(result) => results.push(result),
+            }
+        }
+        self.apply_results(results);
+        self.images.generate_thumbnails(self.data.snip_items.iter().map(|snip_item| snip_item.local_image.as_str()));
+
+        let backup_interval = Duration::from_secs(self.data.auto_backup.interval_hours.max(1) as u64 * 3600);
+        let backup_due = match self.last_auto_backup {
+            Some(last) => last.elapsed().map_or(true, |elapsed| elapsed >= backup_interval),
+            None => true,
+        };
+        if self.data.auto_backup.enabled && backup_due {
+            self.last_auto_backup = Some(SystemTime::now());
+            write_auto_backup(&self.data, self.profile.as_deref(), "scheduled", true);
+        }
+
+        if let Some(config) = self.config_receiver.as_ref().and_then(|receiver| receiver.try_recv().ok()) {
+            self.reload_config(ctx, &config);
+        }
+
+        while let Ok(action) = self.hotkey_action_receiver.try_recv() {
+            self.handle_hotkey_action(ctx, action);
+        }
+        for (action, error) in self.hotkey_hook.take_failures() {
+            self.toasts.error(error.clone()).duration(Some(Duration::from_secs(8)));
+            if let Some(binding) = self.data.hotkeys.iter_mut().find(|binding| binding.action == action) {
+                binding.refused(error);
+            }
+        }
+
+        self.expire_deleted_snips(ctx);
+        self.show_countdown(ctx);
+        self.show_mini_overlay(ctx);
+        self.show_quick_copy(ctx);
+        self.show_quick_open(ctx);
+        self.show_snip_windows(ctx);
+        self.show_onboarding(ctx);
+        self.show_reconvert_preview(ctx);
+        self.show_image_viewer(ctx);
+        self.show_region_overlay(ctx);
+
+        self.toasts.show(ctx);
+        if self.data.perf_overlay {
+            let (waiting_tasks, pending_uploads) = worker::queue_depth();
+            self.perf.show(ctx, waiting_tasks, pending_uploads, self.images.loaded());
+        }
+        self.images.end_frame(ctx);
+        self.perf.end_frame();
+
+        if let Ok(mut global_app_data) = self.global_app_data.lock() {
             *global_app_data = self.data.clone();
         }
     }
@@ -411,18 +4205,264 @@ impl App for TypstScan {
     /// Called by the framework to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, "typst_scan_data", &self.data);
+        if let Some(snip_store) = &mut self.snip_store {
+            snip_store.save(&self.data.snip_items, &self.data.trash);
+        }
     }
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub struct SnipItem {
     id: Uuid,
-    title: String,
+    pub(crate) title: String,
     local_image: String,
-    original_image: String,
-    rendered_image: String,
-    tex: String,
+    pub(crate) original_image: String,
+    pub(crate) rendered_image: String,
+    pub(crate) tex: String,
     typst: String,
+    /// Unix timestamp, missing for snips from older versions.
+    #[serde(default)]
+    created_at: Option<i64>,
+    #[serde(default)]
+    pub(crate) confidence: Option<f64>,
+    #[serde(default)]
+    ocr_time_ms: Option<u64>,
+    #[serde(default)]
+    clipboard_mode: Option<ClipboardMode>,
+    /// Comma separated.
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    pub(crate) metadata: Option<SnipMetadata>,
+    /// Images of the snips merged into this one, after `local_image`.
+    #[serde(default)]
+    extra_images: Vec<String>,
+    /// Device pixel ratio of the capture, the image is shown at its size on screen.
+    #[serde(default)]
+    pixel_scale: Option<f32>,
+    /// URL of a small version of the capture, empty for snips without one.
+    #[serde(default)]
+    pub(crate) thumbnail: String,
+    /// Hash of the uploaded image, to reuse the result when the same image is captured again.
+    #[serde(default)]
+    image_hash: String,
+    #[serde(default)]
+    timings: Option<SnipTimings>,
+    /// Why recognition failed, empty for recognized snips.
+    #[serde(default)]
+    error: String,
+    /// `latex_styled` of the response, empty when Mathpix sent none.
+    #[serde(default)]
+    latex_styled: String,
+    /// What the Typst was converted from, `None` for snips from older versions.
+    #[serde(default)]
+    typst_source: Option<TexSource>,
+    /// Preset the snip was recognized with, `None` for snips from older versions, which were all equations.
+    #[serde(default)]
+    preset: Option<OcrPreset>,
+    /// Extra formats of the response, empty when they were not requested.
+    #[serde(default)]
+    html: String,
+    #[serde(default)]
+    mmd: String,
+    /// Estimated price of the request, `None` when costs were not tracked.
+    #[serde(default)]
+    cost: Option<f64>,
+}
+
+/// Arrangement of the snips view, restored on the next launch.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(default)]
+struct WorkspaceLayout {
+    dock: DockState<SnipPane>,
+    snip_sort: SnipSort,
+    /// Scroll offsets of the snip list and of the selected snip.
+    list_scroll: f32,
+    detail_scroll: f32,
+    /// Only list the snips uploaded into this Mathpix folder.
+    folder_filter: Option<String>,
+}
+
+impl Default for WorkspaceLayout {
+    fn default() -> Self {
+        Self {
+            dock: default_dock(),
+            snip_sort: SnipSort::NewestFirst,
+            list_scroll: 0.0,
+            detail_scroll: 0.0,
+            folder_filter: None,
+        }
+    }
+}
+
+/// The list on the left, the preview in the middle and the editors below it.
+fn default_dock() -> DockState<SnipPane> {
+    let mut dock = DockState::new(vec![SnipPane::Preview, SnipPane::Render]);
+    let surface = dock.main_surface_mut();
+    let [preview, _] = surface.split_left(NodeIndex::root(), 0.25, vec![SnipPane::List]);
+    surface.split_below(preview, 0.55, vec![SnipPane::TeX, SnipPane::Typst]);
+    dock
+}
+
+/// The parts of the snips view, arranged as tabs and panes of a dock.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+enum SnipPane {
+    List,
+    Preview,
+    TeX,
+    Typst,
+    Render,
+}
+
+impl SnipPane {
+    fn label(&self) -> &'static str {
+        match self {
+            SnipPane::List => tr("Snips"),
+            SnipPane::Preview => tr("Preview"),
+            SnipPane::TeX => "TeX",
+            SnipPane::Typst => "Typst",
+            SnipPane::Render => tr("Rendered"),
+        }
+    }
+}
+
+struct SnipPanes<'a> {
+    app: &'a mut TypstScan,
+    restore_scroll: bool,
+    scroll_to_selected: bool,
+}
+
+impl egui_dock::TabViewer for SnipPanes<'_> {
+    type Tab = SnipPane;
+
+    fn title(&mut self, pane: &mut SnipPane) -> egui::WidgetText {
+        pane.label().into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, pane: &mut SnipPane) {
+        match pane {
+            SnipPane::List => self.app.show_snip_list(ui, self.restore_scroll, self.scroll_to_selected),
+            SnipPane::Preview => self.app.show_snip_preview(ui, self.restore_scroll),
+            SnipPane::TeX => self.app.show_snip_tex(ui),
+            SnipPane::Typst => self.app.show_snip_typst(ui),
+            SnipPane::Render => self.app.show_snip_render(ui),
+        }
+    }
+
+    /// Panes can be moved around but not closed, there would be no way to get them back.
+    fn closeable(&mut self, _pane: &mut SnipPane) -> bool {
+        false
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+enum SnipSort {
+    NewestFirst,
+    OldestFirst,
+    Title,
+}
+
+impl SnipSort {
+    const ALL: [SnipSort; 3] = [SnipSort::NewestFirst, SnipSort::OldestFirst, SnipSort::Title];
+
+    fn label(&self) -> &'static str {
+        match self {
+            SnipSort::NewestFirst => tr("Newest first"),
+            SnipSort::OldestFirst => tr("Oldest first"),
+            SnipSort::Title => tr("By title"),
+        }
+    }
+}
+
+/// Exports offered in the context menu of a snip.
+#[derive(Clone, Copy, PartialEq)]
+enum SnipExport {
+    Screenshots,
+    Markdown,
+    Latex,
+    Org,
+    Report,
+}
+
+/// Ways to copy a single snip from the detail view.
+#[derive(Clone, Copy, PartialEq)]
+enum SnipCopy {
+    Typst,
+    TeX,
+    Unicode,
+    /// In a fenced Markdown code block, for GitHub issues and chat.
+    TypstBlock,
+    TeXBlock,
+    /// A LaTeX export block or fragment for org-mode.
+    Org,
+    Continuous,
+    Image,
+    /// With the editor command of the settings.
+    Editor,
+}
+
+#[derive(Default)]
+struct QuickOpen {
+    query: String,
+    selected: usize,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct TrashedSnip {
+    snip_item: SnipItem,
+    /// Unix timestamp.
+    deleted_at: i64,
+}
+
+impl TrashedSnip {
+    /// Tells trashed snips apart, the same snip can be deleted, restored and deleted again.
+    pub(crate) fn key(&self) -> (Uuid, i64) {
+        (self.snip_item.id, self.deleted_at)
+    }
+}
+
+impl SnipItem {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Mathpix folder the snip was uploaded into, empty for none.
+    fn folder_id(&self) -> &str {
+        self.metadata.as_ref().map_or("", |metadata| metadata.folder_id.as_str())
+    }
+
+    /// Typst converted again from the stored TeX, from `source`, the way a fresh capture with its preset is.
+    fn convert(&self, settings: &ConversionSettings, source: TexSource) -> (String, TexSource) {
+        let preset = self.preset.unwrap_or(OcrPreset::Equation);
+        worker::convert_tex(settings, preset, &self.tex, &self.latex_styled, source)
+    }
+
+    fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.split(',').map(str::trim).filter(|tag| !tag.is_empty())
+    }
+
+    fn bridge_result(&self) -> BridgeResult {
+        BridgeResult {
+            id: self.id.to_string(),
+            title: self.title.clone(),
+            tex: self.tex.clone(),
+            typst: self.typst.clone(),
+            image: capture::resolve_image_path(&self.local_image).to_string_lossy().to_string(),
+        }
+    }
+
+    /// Turn the absolute `file://` URIs of older snips into paths relative to the storage directory,
+    /// returns whether there were any.
+    fn make_image_paths_relative(&mut self) -> bool {
+        let mut changed = false;
+        for image in std::iter::once(&mut self.local_image).chain(self.extra_images.iter_mut()) {
+            if image.starts_with("file://") {
+                *image = capture::stored_image_path(&capture::resolve_image_path(image));
+                changed = true;
+            }
+        }
+        changed
+    }
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
@@ -0,0 +1,110 @@
+use crate::app::{SnipItem, TrashedSnip};
+use crate::capture;
+use age::secrecy::SecretString;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// What age writes at the start of an encrypted file.
+const AGE_HEADER: &[u8] = b"age-encryption.org/v1";
+
+/// The snips and settings in one file, to keep a copy or move them to another machine.
+/// Screenshots are referenced by path and not included.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Backup {
+    pub settings: serde_json::Value,
+    pub snip_items: Vec<SnipItem>,
+    #[serde(default)]
+    pub trash: Vec<TrashedSnip>,
+}
+
+/// The backup as JSON, encrypted with a passphrase by age when one is given.
+pub fn write_backup(backup: &Backup, password: Option<&str>) -> Result<Vec<u8>, String> {
+    let json = serde_json::to_vec_pretty(backup).map_err(|e| e.to_string())?;
+    let Some(password) = password else {
+        return Ok(json);
+    };
+    let encryptor = age::Encryptor::with_user_passphrase(SecretString::from(password.to_string()));
+    let mut encrypted = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut encrypted).map_err(|e| e.to_string())?;
+    writer.write_all(&json).map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(encrypted)
+}
+
+/// Read a backup written by [`write_backup`], the password is only needed for encrypted ones.
+pub fn read_backup(bytes: &[u8], password: &str) -> Result<Backup, String> {
+    if !bytes.starts_with(AGE_HEADER) {
+        return serde_json::from_slice(bytes).map_err(|e| e.to_string());
+    }
+    if password.is_empty() {
+        return Err("The backup is encrypted, enter its password".to_string());
+    }
+    let decryptor = age::Decryptor::new(bytes).map_err(|e| e.to_string())?;
+    let identity = age::scrypt::Identity::new(SecretString::from(password.to_string()));
+    let mut reader = decryptor
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .map_err(|e| e.to_string())?;
+    let mut json = Vec::new();
+    reader.read_to_end(&mut json).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&json).map_err(|e| e.to_string())
+}
+
+/// Backups written on their own into the storage directory, before data from an older version is
+/// migrated and then every `interval_hours`, to recover from corrupted storage.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AutoBackup {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    /// How many backups are kept, the oldest are deleted.
+    pub keep: usize,
+}
+
+impl Default for AutoBackup {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: 24,
+            keep: 10,
+        }
+    }
+}
+
+pub fn backups_dir(profile: Option<&str>) -> Option<PathBuf> {
+    let dir = capture::get_storage_dir()?;
+    Some(match profile {
+        Some(profile) => dir.join("profiles").join(profile).join("backups"),
+        None => dir.join("backups"),
+    })
+}
+
+/// Write a timestamped backup into `dir` and delete the oldest ones beyond `keep`.
+pub fn write_rotating(dir: &Path, backup: &Backup, reason: &str, keep: usize) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("backup_{}_{}.json", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S"), reason));
+    std::fs::write(&path, write_backup(backup, None)?).map_err(|e| e.to_string())?;
+    for old in list_backups(dir).into_iter().skip(keep.max(1)) {
+        if let Err(e) = std::fs::remove_file(&old) {
+            eprintln!("Failed to remove {:?}: {:?}", old, e);
+        }
+    }
+    Ok(path)
+}
+
+/// The automatic backups in `dir`, newest first.
+pub fn list_backups(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            name.starts_with("backup_") && name.ends_with(".json")
+        })
+        .collect();
+    // the timestamp in the name sorts by age
+    backups.sort();
+    backups.reverse();
+    backups
+}
@@ -0,0 +1,286 @@
+//! Local socket for editor plugins: a Unix socket named `typst_scan.sock` in the storage
+//! directory, or the named pipe `\\.\pipe\typst_scan` on Windows. With `--profile`, the profile
+//! name is added (`typst_scan_<profile>.sock`, `\\.\pipe\typst_scan_<profile>`). Only the user
+//! running the app can connect, and the pipe rejects clients on other machines.
+//!
+//! Clients write one command per line and get one JSON object per line back:
+//!
+//! - `ping` answers `{"ok":true,"version":"…"}`.
+//! - `capture` starts a capture like the capture hotkey and answers `{"ok":true}`. The result
+//!   follows on connections that subscribed.
+//! - `last_result` answers `{"ok":true,"result":{…}}` with the newest snip, or
+//!   `{"ok":false,"error":"…"}` when there is none.
+//! - `subscribe` answers `{"ok":true}`, then sends `{"event":"result","result":{…}}` for every
+//!   new snip until the client disconnects.
+//!
+//! A result has the fields `id`, `title`, `tex`, `typst` and `image` (path of the screenshot).
+//! Unknown commands answer `{"ok":false,"error":"…"}`.
+
+use crate::worker::SnipTask;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A snip as sent to the clients.
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct BridgeResult {
+    pub id: String,
+    pub title: String,
+    pub tex: String,
+    pub typst: String,
+    pub image: String,
+}
+
+#[derive(Default)]
+struct BridgeState {
+    last_result: Option<BridgeResult>,
+    /// Connections that sent `subscribe`, dropped once they are gone.
+    subscribers: Vec<Sender<String>>,
+}
+
+/// The listening side of the socket, shared with a thread per connection.
+#[derive(Clone)]
+pub struct Bridge {
+    state: Arc<Mutex<BridgeState>>,
+    /// Connections are closed right away while the bridge is turned off in the settings.
+    accepting: Arc<AtomicBool>,
+    started: Arc<AtomicBool>,
+}
+
+impl Bridge {
+    pub fn new(last_result: Option<BridgeResult>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BridgeState { last_result, subscribers: Vec::new() })),
+            accepting: Arc::new(AtomicBool::new(false)),
+            started: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Listen for clients, the socket is created the first time the bridge is enabled.
+    pub fn set_enabled(&self, enabled: bool, profile: Option<&str>, task_sender: &Sender<SnipTask>) {
+        self.accepting.store(enabled, Ordering::Relaxed);
+        if enabled && !self.started.swap(true, Ordering::Relaxed) {
+            let bridge = self.clone();
+            let task_sender = task_sender.clone();
+            let profile = profile.map(str::to_string);
+            thread::spawn(move || {
+                if let Err(e) = listen(&bridge, profile.as_deref(), task_sender) {
+                    eprintln!("Failed to start the editor bridge: {}", e);
+                    bridge.started.store(false, Ordering::Relaxed);
+                }
+            });
+        }
+    }
+
+    /// Remember a new snip and send it to the subscribed clients.
+    pub fn publish(&self, result: BridgeResult) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let line = json!({ "event": "result", "result": result }).to_string();
+        state.subscribers.retain(|subscriber| subscriber.send(line.clone()).is_ok());
+        state.last_result = Some(result);
+    }
+
+    fn handle(&self, command: &str, task_sender: &Sender<SnipTask>) -> Value {
+        match command {
+            "ping" => json!({ "ok": true, "version": env!("CARGO_PKG_VERSION") }),
+            "capture" => match task_sender.send(SnipTask::new()) {
+                Ok(()) => json!({ "ok": true }),
+                Err(_) => json!({ "ok": false, "error": "The worker has stopped" }),
+            },
+            "last_result" => match self.state.lock().ok().and_then(|state| state.last_result.clone()) {
+                Some(result) => json!({ "ok": true, "result": result }),
+                None => json!({ "ok": false, "error": "No snips yet" }),
+            },
+            _ => json!({ "ok": false, "error": format!("Unknown command: {}", command) }),
+        }
+    }
+
+    /// Answer the commands of one client until it disconnects.
+    fn serve<S: std::io::Read + Write + Send + 'static>(&self, stream: S, writer: S, task_sender: Sender<SnipTask>) {
+        let writer = Arc::new(Mutex::new(writer));
+        let write_line = |writer: &Mutex<S>, line: &str| {
+            writer.lock().is_ok_and(|mut writer| writeln!(writer, "{}", line).and_then(|()| writer.flush()).is_ok())
+        };
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let command = line.trim();
+            if command.is_empty() {
+                continue;
+            }
+            if command == "subscribe" {
+                let (sender, receiver) = mpsc::channel::<String>();
+                if let Ok(mut state) = self.state.lock() {
+                    state.subscribers.push(sender);
+                }
+                let events = writer.clone();
+                thread::spawn(move || {
+                    for event in receiver {
+                        if !write_line(&events, &event) {
+                            break;
+                        }
+                    }
+                });
+                if !write_line(&writer, &json!({ "ok": true }).to_string()) {
+                    break;
+                }
+                continue;
+            }
+            if !write_line(&writer, &self.handle(command, &task_sender).to_string()) {
+                break;
+            }
+        }
+    }
+}
+
+fn socket_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("typst_scan_{}", profile),
+        None => "typst_scan".to_string(),
+    }
+}
+
+#[cfg(unix)]
+fn listen(bridge: &Bridge, profile: Option<&str>, task_sender: Sender<SnipTask>) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixListener;
+
+    let dir = crate::capture::get_storage_dir().ok_or("No storage directory")?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.sock", socket_name(profile)));
+    // left behind when the app did not exit cleanly
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).map_err(|e| format!("{:?}: {}", path, e))?;
+    // other users must not start captures or read the results
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("{:?}: {}", path, e))?;
+    println!("Editor bridge listening on {:?}", path);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Editor bridge connection failed: {:?}", e);
+                continue;
+            }
+        };
+        if !bridge.accepting.load(Ordering::Relaxed) {
+            continue;
+        }
+        let Ok(writer) = stream.try_clone() else {
+            continue;
+        };
+        let bridge = bridge.clone();
+        let task_sender = task_sender.clone();
+        thread::spawn(move || bridge.serve(stream, writer, task_sender));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn listen(bridge: &Bridge, profile: Option<&str>, task_sender: Sender<SnipTask>) -> Result<(), String> {
+    use std::fs::File;
+    use std::os::windows::io::FromRawHandle;
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_PIPE_CONNECTED, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Storage::FileSystem::PIPE_ACCESS_DUPLEX;
+    use windows_sys::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE,
+        PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    let name = format!(r"\\.\pipe\{}", socket_name(profile));
+    let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    let security_attributes = current_user_only()?;
+    println!("Editor bridge listening on {}", name);
+    loop {
+        // a new instance of the pipe for every client
+        let handle = unsafe {
+            CreateNamedPipeW(
+                wide.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                &security_attributes,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(format!("Failed to create {}: error {}", name, unsafe { GetLastError() }));
+        }
+        let connected = unsafe { ConnectNamedPipe(handle, std::ptr::null_mut()) } != 0
+            || unsafe { GetLastError() } == ERROR_PIPE_CONNECTED;
+        // the file closes the handle when dropped
+        let stream = unsafe { File::from_raw_handle(handle) };
+        if !connected || !bridge.accepting.load(Ordering::Relaxed) {
+            continue;
+        }
+        let Ok(writer) = stream.try_clone() else {
+            continue;
+        };
+        let bridge = bridge.clone();
+        let task_sender = task_sender.clone();
+        thread::spawn(move || bridge.serve(stream, writer, task_sender));
+    }
+}
+
+/// Security attributes granting access to the pipe to the current user only, so other users of
+/// the machine cannot connect. The descriptor lives as long as the listener, for the whole run.
+#[cfg(windows)]
+fn current_user_only() -> Result<windows_sys::Win32::Security::SECURITY_ATTRIBUTES, String> {
+    use windows_sys::Win32::Foundation::{CloseHandle, GetLastError, LocalFree, HANDLE};
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows_sys::Win32::Security::{GetTokenInformation, TokenUser, SECURITY_ATTRIBUTES, TOKEN_QUERY, TOKEN_USER};
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    unsafe {
+        let mut token: HANDLE = std::ptr::null_mut();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == 0 {
+            return Err(format!("Failed to open the process token: error {}", GetLastError()));
+        }
+        let mut length = 0;
+        GetTokenInformation(token, TokenUser, std::ptr::null_mut(), 0, &mut length);
+        // u64 elements keep the TOKEN_USER aligned
+        let mut buffer = vec![0u64; (length as usize).div_ceil(8)];
+        let read = GetTokenInformation(token, TokenUser, buffer.as_mut_ptr().cast(), length, &mut length);
+        CloseHandle(token);
+        if read == 0 {
+            return Err(format!("Failed to read the current user: error {}", GetLastError()));
+        }
+        let user = &*(buffer.as_ptr() as *const TOKEN_USER);
+
+        let mut sid_string = std::ptr::null_mut();
+        if ConvertSidToStringSidW(user.User.Sid, &mut sid_string) == 0 {
+            return Err(format!("Failed to read the current user: error {}", GetLastError()));
+        }
+        let sid_length = (0..).take_while(|&i| *sid_string.add(i) != 0).count();
+        let sid = String::from_utf16_lossy(std::slice::from_raw_parts(sid_string, sid_length));
+        LocalFree(sid_string.cast());
+
+        // protected DACL with a single entry: full access for the current user
+        let sddl: Vec<u16> = format!("D:P(A;;GA;;;{})", sid).encode_utf16().chain(std::iter::once(0)).collect();
+        let mut descriptor = std::ptr::null_mut();
+        if ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            sddl.as_ptr(),
+            SDDL_REVISION_1,
+            &mut descriptor,
+            std::ptr::null_mut(),
+        ) == 0
+        {
+            return Err(format!("Failed to create the pipe security: error {}", GetLastError()));
+        }
+        Ok(SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: descriptor,
+            bInheritHandle: 0,
+        })
+    }
+}
@@ -0,0 +1,586 @@
+use arboard::Clipboard;
+use image::codecs::jpeg::JpegEncoder;
+use image::{imageops, Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+use xcap::{Monitor, Window};
+
+#[cfg(target_os = "windows")]
+use screen_snip;
+
+/// A rectangle on the virtual desktop in logical (point) coordinates.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct CaptureRegion {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl CaptureRegion {
+    pub fn intersect(&self, other: &CaptureRegion) -> Option<CaptureRegion> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        (right > left && bottom > top).then(|| CaptureRegion {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        })
+    }
+
+    pub fn union(&self, other: &CaptureRegion) -> CaptureRegion {
+        let left = self.x.min(other.x);
+        let top = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        CaptureRegion {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        }
+    }
+}
+
+/// A connected display and its bounds in logical coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Display {
+    pub name: String,
+    pub is_primary: bool,
+    pub bounds: CaptureRegion,
+}
+
+pub fn displays() -> Vec<Display> {
+    match Monitor::all() {
+        Ok(monitors) => monitors.iter().map(display_of).collect(),
+        Err(e) => {
+            eprintln!("Failed to list displays: {:?}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Bounds covering all displays, for selecting regions that span them.
+pub fn virtual_desktop(displays: &[Display]) -> Option<CaptureRegion> {
+    displays.iter().map(|display| display.bounds).reduce(|desktop, bounds| desktop.union(&bounds))
+}
+
+fn display_of(monitor: &Monitor) -> Display {
+    // xcap reports logical coordinates on macOS and physical pixels elsewhere
+    #[cfg(target_os = "macos")]
+    let scale = 1.0;
+    #[cfg(not(target_os = "macos"))]
+    let scale = monitor.scale_factor();
+
+    Display {
+        name: monitor.name().to_string(),
+        is_primary: monitor.is_primary(),
+        bounds: CaptureRegion {
+            x: monitor.x() as f32 / scale,
+            y: monitor.y() as f32 / scale,
+            width: monitor.width() as f32 / scale,
+            height: monitor.height() as f32 / scale,
+        },
+    }
+}
+
+/// Convert a rectangle in physical desktop pixels into logical coordinates.
+#[cfg(not(target_os = "macos"))]
+pub fn region_from_physical(x: i32, y: i32, width: u32, height: u32) -> CaptureRegion {
+    let scale = Monitor::from_point(x + width as i32 / 2, y + height as i32 / 2)
+        .map(|monitor| monitor.scale_factor())
+        .unwrap_or(1.0);
+    CaptureRegion {
+        x: x as f32 / scale,
+        y: y as f32 / scale,
+        width: width as f32 / scale,
+        height: height as f32 / scale,
+    }
+}
+
+/// Capture a region of the screen without interaction and save it as a new screenshot.
+/// Regions spanning several displays are stitched together.
+pub fn capture_region(region: &CaptureRegion) -> Option<PathBuf> {
+    let monitors = Monitor::all().map_err(|e| eprintln!("Failed to list displays: {:?}", e)).ok()?;
+
+    let mut parts = Vec::new();
+    for monitor in &monitors {
+        let bounds = display_of(monitor).bounds;
+        let Some(part) = region.intersect(&bounds) else {
+            continue;
+        };
+        let image = match monitor.capture_image() {
+            Ok(image) => image,
+            Err(e) => {
+                eprintln!("Failed to capture display {}: {:?}", monitor.name(), e);
+                continue;
+            }
+        };
+        // the captured image is in physical pixels
+        let pixel_scale = image.width() as f32 / bounds.width;
+        let left = (((part.x - bounds.x) * pixel_scale) as u32).min(image.width().saturating_sub(1));
+        let top = (((part.y - bounds.y) * pixel_scale) as u32).min(image.height().saturating_sub(1));
+        let width = ((part.width * pixel_scale) as u32).clamp(1, image.width() - left);
+        let height = ((part.height * pixel_scale) as u32).clamp(1, image.height() - top);
+        parts.push((part, pixel_scale, imageops::crop_imm(&image, left, top, width, height).to_image()));
+    }
+    if parts.is_empty() {
+        eprintln!("Region {:?} is not on any display", region);
+        return None;
+    }
+
+    // compose at the highest pixel density among the displays involved
+    let scale = parts.iter().map(|(_, pixel_scale, _)| *pixel_scale).fold(1.0, f32::max);
+    let mut canvas = RgbaImage::new(
+        (region.width * scale).round().max(1.0) as u32,
+        (region.height * scale).round().max(1.0) as u32,
+    );
+    for (part, pixel_scale, image) in parts {
+        let image = if pixel_scale == scale {
+            image
+        } else {
+            let width = (part.width * scale).round().max(1.0) as u32;
+            let height = (part.height * scale).round().max(1.0) as u32;
+            imageops::resize(&image, width, height, imageops::FilterType::Triangle)
+        };
+        let x = ((part.x - region.x) * scale).round() as i64;
+        let y = ((part.y - region.y) * scale).round() as i64;
+        imageops::replace(&mut canvas, &image, x, y);
+    }
+
+    save_screenshot(&canvas)
+}
+
+/// Physical pixels per logical pixel of a captured image, from the region it was taken of or
+/// else the primary display.
+pub fn pixel_scale(path: &Path, region: Option<&CaptureRegion>) -> f32 {
+    if let (Some(region), Ok((width, _))) = (region, image::image_dimensions(path)) {
+        if region.width > 0.0 {
+            return width as f32 / region.width;
+        }
+    }
+    Monitor::all()
+        .ok()
+        .and_then(|monitors| monitors.into_iter().find(|monitor| monitor.is_primary()))
+        .map(|monitor| monitor.scale_factor())
+        .unwrap_or(1.0)
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum ScreenshotFormat {
+    Png,
+    Jpeg,
+}
+
+/// How captures are stored and uploaded.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ScreenshotSettings {
+    pub format: ScreenshotFormat,
+    /// 1 to 100.
+    pub jpeg_quality: u8,
+    /// Keep the PNG on disk and only upload the JPEG.
+    pub lossless_original: bool,
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        Self {
+            format: ScreenshotFormat::Png,
+            jpeg_quality: 85,
+            lossless_original: false,
+        }
+    }
+}
+
+/// Encode a captured PNG for upload according to the settings. Returns the path the capture is
+/// kept at, which changes when it is converted to JPEG, the bytes to upload and their MIME type.
+pub fn prepare_upload(path: &Path, settings: &ScreenshotSettings) -> Result<(PathBuf, Vec<u8>, &'static str), String> {
+    if settings.format == ScreenshotFormat::Png {
+        let data = std::fs::read(path).map_err(|e| format!("Failed to read screenshot {:?}: {}", path, e))?;
+        return Ok((path.to_path_buf(), data, "image/png"));
+    }
+
+    // JPEG has no alpha channel
+    let image = image::open(path).map_err(|e| format!("Failed to read screenshot {:?}: {}", path, e))?.to_rgb8();
+    let mut data = Vec::new();
+    JpegEncoder::new_with_quality(&mut data, settings.jpeg_quality.clamp(1, 100))
+        .encode_image(&image)
+        .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+    if settings.lossless_original {
+        return Ok((path.to_path_buf(), data, "image/jpeg"));
+    }
+    let jpeg_path = path.with_extension("jpg");
+    std::fs::write(&jpeg_path, &data).map_err(|e| format!("Failed to save screenshot {:?}: {}", jpeg_path, e))?;
+    if let Err(e) = std::fs::remove_file(path) {
+        eprintln!("Failed to remove {:?}: {:?}", path, e);
+    }
+    Ok((jpeg_path, data, "image/jpeg"))
+}
+
+/// Pixels to cut off each side of a captured image.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Margins {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}
+
+/// Capture the content of a specific window, even when it is covered by other windows
+/// (where the OS allows it). An empty process name or title matches any.
+pub fn capture_window(process_name: &str, window_title: &str, crop: &Margins) -> Option<PathBuf> {
+    let windows = Window::all().map_err(|e| eprintln!("Failed to list windows: {:?}", e)).ok()?;
+    let Some(window) = windows.iter().find(|window| {
+        !window.is_minimized()
+            && (process_name.is_empty() || window.app_name().eq_ignore_ascii_case(process_name))
+            && (window_title.is_empty() || window.title() == window_title)
+    }) else {
+        eprintln!("No window found for process {process_name:?} / title {window_title:?}");
+        return None;
+    };
+
+    let image = window.capture_image().map_err(|e| eprintln!("Failed to capture window: {:?}", e)).ok()?;
+    if crop.left + crop.right >= image.width() || crop.top + crop.bottom >= image.height() {
+        eprintln!("Crop margins {:?} are larger than the window", crop);
+        return save_screenshot(&image);
+    }
+    let width = image.width() - crop.left - crop.right;
+    let height = image.height() - crop.top - crop.bottom;
+    let cropped = imageops::crop_imm(&image, crop.left, crop.top, width, height).to_image();
+    save_screenshot(&cropped)
+}
+
+/// Save the image currently on the clipboard as a new screenshot, e.g. a region copied in a PDF viewer.
+pub fn clipboard_image() -> Option<PathBuf> {
+    let image = match Clipboard::new().and_then(|mut clipboard| clipboard.get_image()) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("No image on the clipboard: {:?}", e);
+            return None;
+        }
+    };
+    let Some(image) = RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.into_owned()) else {
+        eprintln!("Clipboard image has an unexpected size");
+        return None;
+    };
+    save_screenshot(&image)
+}
+
+/// Crop a loose capture to the content around its center: the band of rows with ink closest to the
+/// middle, trimmed to its ink. Surrounding lines of text separated by blank space are cut off.
+pub fn smart_crop(path: &Path) {
+    const THRESHOLD: u32 = 60;
+    const MIN_GAP: u32 = 6;
+    const PADDING: u32 = 8;
+
+    let image = match image::open(path) {
+        Ok(image) => image.to_rgba8(),
+        Err(e) => {
+            eprintln!("Failed to open {:?} for cropping: {:?}", path, e);
+            return;
+        }
+    };
+    let (width, height) = image.dimensions();
+    // the background is whatever the corners have in common
+    let corners = [(0, 0), (width - 1, 0), (0, height - 1), (width - 1, height - 1)].map(|(x, y)| image.get_pixel(x, y).0);
+    let background: [u32; 3] = std::array::from_fn(|i| {
+        let mut values = corners.map(|pixel| pixel[i] as u32);
+        values.sort();
+        (values[1] + values[2]) / 2
+    });
+    let is_ink = |x: u32, y: u32| {
+        let pixel = image.get_pixel(x, y).0;
+        (0..3).map(|i| (pixel[i] as u32).abs_diff(background[i])).sum::<u32>() > THRESHOLD
+    };
+
+    // bands of rows with ink, separated by at least MIN_GAP blank rows
+    let mut bands: Vec<(u32, u32)> = Vec::new();
+    let mut blank_rows = MIN_GAP;
+    for y in 0..height {
+        if (0..width).any(|x| is_ink(x, y)) {
+            match bands.last_mut() {
+                Some(band) if blank_rows < MIN_GAP => band.1 = y,
+                _ => bands.push((y, y)),
+            }
+            blank_rows = 0;
+        } else {
+            blank_rows += 1;
+        }
+    }
+    let center = height / 2;
+    let Some(&(top, bottom)) = bands.iter().min_by_key(|(top, bottom)| {
+        if (*top..=*bottom).contains(&center) {
+            0
+        } else {
+            top.abs_diff(center).min(bottom.abs_diff(center))
+        }
+    }) else {
+        return;
+    };
+    let columns: Vec<u32> = (0..width).filter(|&x| (top..=bottom).any(|y| is_ink(x, y))).collect();
+    let (Some(&left), Some(&right)) = (columns.first(), columns.last()) else {
+        return;
+    };
+
+    let left = left.saturating_sub(PADDING);
+    let top = top.saturating_sub(PADDING);
+    let right = (right + PADDING).min(width - 1);
+    let bottom = (bottom + PADDING).min(height - 1);
+    if (left, top, right, bottom) == (0, 0, width - 1, height - 1) {
+        return;
+    }
+    let cropped = imageops::crop_imm(&image, left, top, right - left + 1, bottom - top + 1).to_image();
+    if let Err(e) = cropped.save(path) {
+        eprintln!("Failed to save the cropped screenshot: {:?}", e);
+    }
+}
+
+/// Where a downscaled copy of a screenshot is kept, `screenshot.png` becomes `screenshot.<suffix>.png`.
+pub fn scaled_path(path: &Path, suffix: &str) -> PathBuf {
+    path.with_extension(format!("{}.png", suffix))
+}
+
+/// A copy of the screenshot scaled down to `max_size` pixels on its longest side, saved next to it
+/// and reused while it is newer than the screenshot, with the factor it was scaled down by.
+/// `None` when the screenshot is already small.
+pub fn create_scaled(path: &Path, suffix: &str, max_size: u32) -> Option<(PathBuf, f32)> {
+    let (width, height) = image::image_dimensions(path).ok()?;
+    if width.max(height) <= max_size {
+        return None;
+    }
+    let factor = width.max(height) as f32 / max_size as f32;
+    let preview_path = scaled_path(path, suffix);
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    if let (Some(preview), Some(screenshot)) = (modified(&preview_path), modified(path)) {
+        if preview >= screenshot {
+            return Some((preview_path, factor));
+        }
+    }
+    let image = match image::open(path) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("Failed to open {:?} for scaling: {:?}", path, e);
+            return None;
+        }
+    };
+    match image.thumbnail(max_size, max_size).save(&preview_path) {
+        Ok(()) => Some((preview_path, factor)),
+        Err(e) => {
+            eprintln!("Failed to save the scaled screenshot {:?}: {:?}", preview_path, e);
+            None
+        }
+    }
+}
+
+/// Draw the strokes of the canvas in black on white, cropped to the strokes with some padding.
+/// Points are `[x, y, pressure]`, the pressure scales the line width.
+pub fn render_strokes(strokes: &[Vec<[f32; 3]>]) -> Option<PathBuf> {
+    const PADDING: f32 = 20.0;
+    const RADIUS: f32 = 2.5;
+
+    let points = strokes.iter().flatten();
+    let min_x = points.clone().map(|p| p[0]).fold(f32::INFINITY, f32::min);
+    let min_y = points.clone().map(|p| p[1]).fold(f32::INFINITY, f32::min);
+    let max_x = points.clone().map(|p| p[0]).fold(f32::NEG_INFINITY, f32::max);
+    let max_y = points.map(|p| p[1]).fold(f32::NEG_INFINITY, f32::max);
+    if !min_x.is_finite() {
+        eprintln!("Nothing was drawn");
+        return None;
+    }
+
+    let width = (max_x - min_x + 2.0 * PADDING).ceil() as u32;
+    let height = (max_y - min_y + 2.0 * PADDING).ceil() as u32;
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+    let mut dot = |x: f32, y: f32, pressure: f32| {
+        let (x, y) = (x - min_x + PADDING, y - min_y + PADDING);
+        let radius = RADIUS * pressure.clamp(0.25, 1.5);
+        for py in (y - radius).floor() as i64..=(y + radius).ceil() as i64 {
+            for px in (x - radius).floor() as i64..=(x + radius).ceil() as i64 {
+                let inside = (px as f32 - x).powi(2) + (py as f32 - y).powi(2) <= radius * radius;
+                if inside && px >= 0 && py >= 0 && (px as u32) < width && (py as u32) < height {
+                    canvas.put_pixel(px as u32, py as u32, Rgba([0, 0, 0, 255]));
+                }
+            }
+        }
+    };
+    for stroke in strokes {
+        for (i, point) in stroke.iter().enumerate() {
+            let previous = if i == 0 { point } else { &stroke[i - 1] };
+            let steps = (point[0] - previous[0]).hypot(point[1] - previous[1]).ceil().max(1.0);
+            for step in 0..=steps as u32 {
+                let t = step as f32 / steps;
+                let lerp = |i: usize| previous[i] + (point[i] - previous[i]) * t;
+                dot(lerp(0), lerp(1), lerp(2));
+            }
+        }
+    }
+    save_screenshot(&canvas)
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_screenshot() -> Option<PathBuf> {
+    let file_name = new_screenshot_path();
+    if let Err(e) = std::process::Command::new("screencapture").arg("-i").arg(&file_name).output() {
+        eprintln!("Failed to run screencapture: {:?}", e);
+        return None;
+    }
+
+    // check the path if teh file exists
+    if file_name.exists() {
+        println!("Screenshot saved to: {:?}", file_name);
+        Some(file_name)
+    } else {
+        println!("Screenshot cancelled.");
+        None
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn get_screenshot() -> Option<PathBuf> {
+    let file_name = new_screenshot_path();
+    screen_snip::get_screen_snip(file_name.clone().into());
+    Some(file_name)
+}
+
+/// Let the user select a region with the first screenshot tool that is installed.
+#[cfg(target_os = "linux")]
+pub fn get_screenshot() -> Option<PathBuf> {
+    use std::io::ErrorKind;
+    use std::process::Command;
+
+    let file_name = new_screenshot_path();
+
+    // slurp selects the region for grim on wlroots compositors
+    match Command::new("slurp").output() {
+        Ok(selection) if selection.status.success() => {
+            let geometry = String::from_utf8_lossy(&selection.stdout).trim().to_string();
+            Command::new("grim").arg("-g").arg(geometry).arg(&file_name).output().ok();
+            return screenshot_taken(file_name);
+        }
+        Ok(_) => {
+            println!("Screenshot cancelled.");
+            return None;
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => eprintln!("Failed to run slurp: {:?}", e),
+    }
+
+    let tools: [(&str, &[&str]); 3] = [
+        ("gnome-screenshot", &["-a", "-f"]),
+        ("spectacle", &["-r", "-b", "-n", "-o"]),
+        ("maim", &["-s"]),
+    ];
+    for (program, args) in tools {
+        match Command::new(program).args(args).arg(&file_name).output() {
+            Ok(_) => return screenshot_taken(file_name),
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => eprintln!("Failed to run {}: {:?}", program, e),
+        }
+    }
+    eprintln!("No screenshot tool found, install grim and slurp, gnome-screenshot, spectacle or maim");
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn screenshot_taken(file_name: PathBuf) -> Option<PathBuf> {
+    if file_name.exists() {
+        println!("Screenshot saved to: {:?}", file_name);
+        Some(file_name)
+    } else {
+        println!("Screenshot cancelled.");
+        None
+    }
+}
+
+fn save_screenshot(image: &RgbaImage) -> Option<PathBuf> {
+    let file_name = new_screenshot_path();
+    match image.save(&file_name) {
+        Ok(()) => {
+            println!("Screenshot saved to: {:?}", file_name);
+            Some(file_name)
+        }
+        Err(e) => {
+            eprintln!("Failed to save screenshot: {:?}", e);
+            None
+        }
+    }
+}
+
+fn new_screenshot_path() -> PathBuf {
+    let storage_path = get_storage_dir().unwrap_or_else(|| PathBuf::from("/tmp")); // Fallback to /tmp if no storage path
+    storage_path.join(format!("screenshot_{}.png", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S-%3f")))
+}
+
+pub fn get_storage_dir() -> Option<PathBuf> {
+    eframe::storage_dir("Typst Scan")
+}
+
+/// How a screenshot is kept in a snip: relative to the storage directory when it is in there, so
+/// snips still find their images after the directory moved or a backup was restored elsewhere.
+/// Other files keep their absolute `file://` URI.
+pub fn stored_image_path(path: &Path) -> String {
+    let relative = get_storage_dir().and_then(|dir| path.strip_prefix(dir).ok().map(Path::to_path_buf));
+    match relative {
+        Some(relative) => relative.to_string_lossy().replace('\\', "/"),
+        None => format!("file://{}", path.display()),
+    }
+}
+
+/// The file a stored screenshot refers to. Older snips have absolute `file://` URIs, those that
+/// no longer exist are looked up by name in the storage directory.
+pub fn resolve_image_path(stored: &str) -> PathBuf {
+    if stored.is_empty() {
+        return PathBuf::new();
+    }
+    let Some(dir) = get_storage_dir() else {
+        return PathBuf::from(stored.strip_prefix("file://").unwrap_or(stored));
+    };
+    match stored.strip_prefix("file://") {
+        Some(absolute) => {
+            let path = PathBuf::from(absolute);
+            match path.file_name() {
+                Some(file_name) if !path.exists() && dir.join(file_name).exists() => dir.join(file_name),
+                _ => path,
+            }
+        }
+        None => dir.join(stored),
+    }
+}
+
+/// The URI for showing a stored screenshot with the image loaders.
+pub fn image_uri(stored: &str) -> String {
+    format!("file://{}", resolve_image_path(stored).display())
+}
+
+/// A file name made from a snip title, keeping letters, digits, spaces, `-` and `_`.
+pub fn sanitize_file_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_') { c } else { '_' })
+        .take(80)
+        .collect();
+    let name = name.trim().trim_matches('_').trim();
+    if name.is_empty() { "snip".to_string() } else { name.to_string() }
+}
+
+/// Copy screenshots into `dir` under the given names, adding a number when a name is taken.
+/// Returns how many files were copied.
+pub fn export_screenshots(dir: &Path, screenshots: &[(String, String)]) -> Result<usize, String> {
+    let mut used = Vec::new();
+    for (name, image) in screenshots {
+        let source = &resolve_image_path(image);
+        let extension = source.extension().and_then(|e| e.to_str()).unwrap_or("png");
+        let mut file_name = format!("{}.{}", name, extension);
+        let mut counter = 2;
+        while used.contains(&file_name) || dir.join(&file_name).exists() {
+            file_name = format!("{} ({}).{}", name, counter, extension);
+            counter += 1;
+        }
+        std::fs::copy(source, dir.join(&file_name)).map_err(|e| format!("{:?}: {}", source, e))?;
+        used.push(file_name);
+    }
+    Ok(used.len())
+}
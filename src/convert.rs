@@ -0,0 +1,795 @@
+use crate::i18n::tr;
+
+/// How TeX is wrapped when it is copied in CopyTeX mode.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum TexDelimiters {
+    /// Copy the OCR output as it is.
+    Raw,
+    /// `$...$`
+    Dollars,
+    /// `\[...\]`
+    Display,
+    /// `\begin{equation}...\end{equation}`
+    Equation,
+}
+
+impl TexDelimiters {
+    pub const ALL: [TexDelimiters; 4] = [
+        TexDelimiters::Raw,
+        TexDelimiters::Dollars,
+        TexDelimiters::Display,
+        TexDelimiters::Equation,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TexDelimiters::Raw => tr("Raw"),
+            TexDelimiters::Dollars => "$...$",
+            TexDelimiters::Display => "\\[...\\]",
+            TexDelimiters::Equation => "\\begin{equation}",
+        }
+    }
+}
+
+/// Which output of Mathpix is converted to Typst.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum TexSource {
+    /// `text`, with `\(..\)` and `\[..\]` around the math.
+    Text,
+    /// `latex_styled`, only sent for a single formula.
+    LatexStyled,
+}
+
+impl TexSource {
+    pub const ALL: [TexSource; 2] = [TexSource::Text, TexSource::LatexStyled];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TexSource::Text => tr("Mathpix text"),
+            TexSource::LatexStyled => tr("Styled LaTeX"),
+        }
+    }
+}
+
+/// The TeX to convert from the chosen output and the output it came from,
+/// `text` when there is no `latex_styled`.
+pub fn conversion_source(source: TexSource, text: &str, latex_styled: &str) -> (String, TexSource) {
+    match source {
+        TexSource::LatexStyled if !latex_styled.trim().is_empty() => {
+            (format!("\\[\n{}\n\\]", latex_styled.trim()), TexSource::LatexStyled)
+        }
+        _ => (text.to_string(), TexSource::Text),
+    }
+}
+
+/// The math of a result that is a single formula, without the delimiters Mathpix put around it.
+/// `None` when the text mixes prose and math.
+pub fn single_formula(tex: &str) -> Option<&str> {
+    let tex = tex.trim();
+    for (open, close) in [("\\[", "\\]"), ("\\(", "\\)"), ("$$", "$$"), ("$", "$")] {
+        if let Some(inner) = tex.strip_prefix(open).and_then(|rest| rest.strip_suffix(close)) {
+            if !inner.contains(open) && !inner.contains(close) {
+                return Some(inner.trim());
+            }
+        }
+    }
+    None
+}
+
+/// Re-wrap a single formula in the chosen delimiters, anything else is left alone.
+pub fn wrap_tex(tex: &str, delimiters: TexDelimiters) -> String {
+    let Some(formula) = single_formula(tex).filter(|_| delimiters != TexDelimiters::Raw) else {
+        return tex.to_string();
+    };
+    match delimiters {
+        TexDelimiters::Raw => tex.to_string(),
+        TexDelimiters::Dollars => format!("${}$", formula),
+        TexDelimiters::Display => format!("\\[\n{}\n\\]", formula),
+        TexDelimiters::Equation => format!("\\begin{{equation}}\n{}\n\\end{{equation}}", formula),
+    }
+}
+
+/// Post-processing applied to the OCR output before it is copied or stored.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct OutputCleanup {
+    pub trim: bool,
+    pub collapse_blank_lines: bool,
+    /// Only applies to TeX copied with the raw delimiter style, the other styles re-wrap a single formula
+    /// and the stored TeX keeps its delimiters so the Typst can be regenerated.
+    pub strip_display_delimiters: bool,
+}
+
+impl OutputCleanup {
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = if self.collapse_blank_lines {
+            let mut collapsed = Vec::new();
+            let mut previous_blank = false;
+            for line in text.lines() {
+                let blank = line.trim().is_empty();
+                if !(blank && previous_blank) {
+                    collapsed.push(if blank { "" } else { line });
+                }
+                previous_blank = blank;
+            }
+            collapsed.join("\n")
+        } else {
+            text.to_string()
+        };
+        if self.trim {
+            text = text.trim().to_string();
+        }
+        text
+    }
+}
+
+/// TeX as it goes to the clipboard in CopyTeX mode.
+pub fn copy_tex(tex: &str, cleanup: &OutputCleanup, delimiters: TexDelimiters) -> String {
+    match delimiters {
+        TexDelimiters::Raw if cleanup.strip_display_delimiters => single_formula(tex).unwrap_or(tex).to_string(),
+        _ => wrap_tex(tex, delimiters),
+    }
+}
+
+/// What each clipboard mode outputs, see [`fill_template`] for the placeholders.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct OutputTemplates {
+    pub typst: String,
+    pub tex: String,
+    pub continuous: String,
+    pub unicode: String,
+}
+
+impl Default for OutputTemplates {
+    fn default() -> Self {
+        Self {
+            typst: "{typst}".to_string(),
+            tex: "{tex}".to_string(),
+            continuous: "{typst}".to_string(),
+            unicode: "{unicode}".to_string(),
+        }
+    }
+}
+
+/// `code` in a fenced Markdown code block tagged with `language`. The fence is longer than any run
+/// of backticks in the code, so Typst raw text cannot close it early.
+pub fn fenced_code(code: &str, language: &str) -> String {
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{fence}{language}\n{}\n{fence}", code.trim_end_matches('\n'))
+}
+
+/// Values for the placeholders of an output template.
+pub struct TemplateFields<'a> {
+    pub typst: &'a str,
+    pub tex: &'a str,
+    pub unicode: &'a str,
+    pub title: &'a str,
+    pub date: &'a str,
+    pub image_path: &'a str,
+}
+
+/// Replace `{typst}`, `{tex}`, `{unicode}`, `{title}`, `{date}` and `{image_path}` in a template. Other braces are
+/// kept as they are, and placeholders inside the filled in values are not replaced again.
+pub fn fill_template(template: &str, fields: &TemplateFields) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let value = match &rest[1..end] {
+                "typst" => fields.typst,
+                "tex" => fields.tex,
+                "unicode" => fields.unicode,
+                "title" => fields.title,
+                "date" => fields.date,
+                "image_path" => fields.image_path,
+                _ => return None,
+            };
+            Some((value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                output.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Rewrites of LaTeX that tex2typst does not handle well, run on the TeX before it is converted.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum Normalization {
+    EqnarrayToAlign,
+    MboxToText,
+    DisplayFractions,
+    BoldsymbolToMathbf,
+}
+
+impl Normalization {
+    pub const ALL: [Normalization; 4] = [
+        Normalization::EqnarrayToAlign,
+        Normalization::MboxToText,
+        Normalization::DisplayFractions,
+        Normalization::BoldsymbolToMathbf,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Normalization::EqnarrayToAlign => "eqnarray → align",
+            Normalization::MboxToText => "\\mbox, \\hbox → \\text",
+            Normalization::DisplayFractions => "\\dfrac, \\tfrac → \\frac",
+            Normalization::BoldsymbolToMathbf => "\\boldsymbol, \\bm → \\mathbf",
+        }
+    }
+
+    fn apply(&self, tex: &str) -> String {
+        let replacements: &[(&str, &str)] = match self {
+            Normalization::EqnarrayToAlign => &[
+                ("\\begin{eqnarray*}", "\\begin{align*}"),
+                ("\\end{eqnarray*}", "\\end{align*}"),
+                ("\\begin{eqnarray}", "\\begin{align}"),
+                ("\\end{eqnarray}", "\\end{align}"),
+                ("&=&", "&="),
+            ],
+            Normalization::MboxToText => &[("\\mbox{", "\\text{"), ("\\hbox{", "\\text{")],
+            Normalization::DisplayFractions => &[("\\dfrac", "\\frac"), ("\\tfrac", "\\frac")],
+            Normalization::BoldsymbolToMathbf => &[("\\boldsymbol{", "\\mathbf{"), ("\\bm{", "\\mathbf{")],
+        };
+        replacements
+            .iter()
+            .fold(tex.to_string(), |tex, (from, to)| tex.replace(from, to))
+    }
+}
+
+/// Run the enabled normalizations over the TeX.
+pub fn normalize(tex: &str, normalizations: &[Normalization]) -> String {
+    normalizations
+        .iter()
+        .fold(tex.to_string(), |tex, normalization| normalization.apply(&tex))
+}
+
+/// Problems found in the TeX that usually mean the OCR misread something.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LatexWarning {
+    UnbalancedBraces,
+    UnclosedEnvironment(String),
+    UnmatchedEnd(String),
+    UnbalancedLeftRight,
+}
+
+impl LatexWarning {
+    pub fn message(&self) -> String {
+        match self {
+            LatexWarning::UnbalancedBraces => tr("Unbalanced braces").to_string(),
+            LatexWarning::UnclosedEnvironment(name) => tr("Environment {} is not closed").replace("{}", name),
+            LatexWarning::UnmatchedEnd(name) => tr("\\end of {} without a \\begin").replace("{}", name),
+            LatexWarning::UnbalancedLeftRight => tr("Unbalanced \\left and \\right").to_string(),
+        }
+    }
+}
+
+/// A quick sanity check of braces and environments, not a LaTeX parser.
+pub fn validate(tex: &str) -> Vec<LatexWarning> {
+    let mut warnings = Vec::new();
+    let mut depth = 0i64;
+    let mut left_right = 0i64;
+    let mut environments = Vec::new();
+    let mut rest = tex;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '\\' => {
+                let command_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+                let (command, after) = rest.split_at(command_len);
+                let name = after.strip_prefix('{').and_then(|after| after.split_once('}')).map(|(name, _)| name);
+                match (command, name) {
+                    // escaped character such as \{ or \\
+                    ("", _) => rest = &rest[rest.chars().next().map_or(0, char::len_utf8)..],
+                    ("begin", Some(name)) => environments.push(name),
+                    ("end", Some(name)) => {
+                        if environments.last() == Some(&name) {
+                            environments.pop();
+                        } else {
+                            warnings.push(LatexWarning::UnmatchedEnd(name.to_string()));
+                        }
+                    }
+                    ("left", _) => left_right += 1,
+                    ("right", _) => left_right -= 1,
+                    _ => {}
+                }
+                if !command.is_empty() {
+                    rest = after;
+                }
+            }
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        if depth < 0 && !warnings.contains(&LatexWarning::UnbalancedBraces) {
+            warnings.push(LatexWarning::UnbalancedBraces);
+        }
+    }
+    if depth > 0 && !warnings.contains(&LatexWarning::UnbalancedBraces) {
+        warnings.push(LatexWarning::UnbalancedBraces);
+    }
+    if left_right != 0 {
+        warnings.push(LatexWarning::UnbalancedLeftRight);
+    }
+    warnings.extend(environments.into_iter().map(|name| LatexWarning::UnclosedEnvironment(name.to_string())));
+    warnings
+}
+
+/// Typst output formatting, in the spirit of typstyle: single spaces and wrapped long lines.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TypstFormat {
+    pub enabled: bool,
+    pub line_width: usize,
+}
+
+impl Default for TypstFormat {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            line_width: 80,
+        }
+    }
+}
+
+impl TypstFormat {
+    pub fn apply(&self, typst: &str) -> String {
+        if !self.enabled {
+            return typst.to_string();
+        }
+        let mut formatted = Vec::new();
+        for line in typst.lines() {
+            let indent_len = line.len() - line.trim_start().len();
+            let (indent, line) = line.split_at(indent_len);
+            let mut line = collapse_spaces(line);
+            let continuation = format!("{}  ", indent);
+            let mut prefix = indent.to_string();
+            while prefix.len() + line.len() > self.line_width {
+                let Some(at) = wrap_point(&line, self.line_width.saturating_sub(prefix.len())) else {
+                    break;
+                };
+                formatted.push(format!("{}{}", prefix, &line[..at]));
+                line = line[at + 1..].to_string();
+                prefix = continuation.clone();
+            }
+            formatted.push(format!("{}{}", prefix, line));
+        }
+        formatted.join("\n")
+    }
+}
+
+/// Positions of the spaces outside of string literals.
+fn spaces_outside_strings(line: &str) -> Vec<usize> {
+    let mut spaces = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' if in_string => escaped = !escaped,
+            '"' if !escaped => in_string = !in_string,
+            ' ' if !in_string => spaces.push(i),
+            _ => {}
+        }
+        if c != '\\' {
+            escaped = false;
+        }
+    }
+    spaces
+}
+
+fn collapse_spaces(line: &str) -> String {
+    let spaces = spaces_outside_strings(line);
+    let mut collapsed = String::new();
+    for (i, c) in line.char_indices() {
+        if c == ' ' && spaces.contains(&i) && spaces.contains(&(i + 1)) {
+            continue;
+        }
+        collapsed.push(c);
+    }
+    collapsed.trim_end().to_string()
+}
+
+/// Where to break a line that is too long: the last space within the width, preferably one before
+/// an operator so the continuation line starts with it.
+fn wrap_point(line: &str, width: usize) -> Option<usize> {
+    let spaces: Vec<usize> = spaces_outside_strings(line).into_iter().filter(|&i| i > 0 && i <= width).collect();
+    spaces
+        .iter()
+        .rev()
+        .find(|&&i| line[i + 1..].starts_with(['=', '+', '-', '<', '>']))
+        .or(spaces.last())
+        .copied()
+}
+
+/// The display equations of a result, each with its delimiters. Text between them stays with the
+/// equation before it, text before the first one with the first. Results without display
+/// delimiters are split into their non-empty lines.
+pub fn split_display_math(tex: &str) -> Vec<String> {
+    let mut equations: Vec<String> = Vec::new();
+    let mut leading = String::new();
+    let mut rest = tex;
+    loop {
+        let next = [("\\[", "\\]"), ("$$", "$$")]
+            .into_iter()
+            .filter_map(|(open, close)| rest.find(open).map(|start| (start, open, close)))
+            .min_by_key(|(start, _, _)| *start);
+        let Some((start, open, close)) = next else {
+            break;
+        };
+        let body = &rest[start + open.len()..];
+        let Some(end) = body.find(close) else {
+            break;
+        };
+        let before = rest[..start].trim();
+        match equations.last_mut() {
+            Some(last) if !before.is_empty() => {
+                last.push('\n');
+                last.push_str(before);
+            }
+            _ => leading = before.to_string(),
+        }
+        let equation = format!("{}{}{}", open, &body[..end], close);
+        if leading.is_empty() {
+            equations.push(equation);
+        } else {
+            equations.push(format!("{}\n{}", std::mem::take(&mut leading), equation));
+        }
+        rest = &body[end + close.len()..];
+    }
+    if let Some(last) = equations.last_mut() {
+        if !rest.trim().is_empty() {
+            last.push('\n');
+            last.push_str(rest.trim());
+        }
+    }
+    if equations.is_empty() {
+        equations = tex.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_string).collect();
+    }
+    equations
+}
+
+/// Mathpix Markdown as Pandoc Markdown: inline math in `$...$`, display math as `$$` in an
+/// `.equation` fenced div so Pandoc and Quarto filters can pick it up.
+pub fn tex_to_pandoc(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+    loop {
+        let next = [("\\(", "\\)", false), ("\\[", "\\]", true), ("$$", "$$", true)]
+            .into_iter()
+            .filter_map(|(open, close, display)| rest.find(open).map(|start| (start, open, close, display)))
+            .min_by_key(|(start, ..)| *start);
+        let Some((start, open, close, display)) = next else {
+            break;
+        };
+        let body = &rest[start + open.len()..];
+        let Some(end) = body.find(close) else {
+            break;
+        };
+        let math = body[..end].trim();
+        if display {
+            output.push_str(rest[..start].trim_end());
+            output.push_str(&format!("\n\n::: {{.equation}}\n$$\n{}\n$$\n:::\n\n", math));
+            rest = body[end + close.len()..].trim_start();
+        } else {
+            output.push_str(&rest[..start]);
+            output.push_str(&format!("${}$", math));
+            rest = &body[end + close.len()..];
+        }
+    }
+    output.push_str(rest);
+    output.trim().to_string()
+}
+
+/// The first `tabular` of the TeX as a Typst `#table`, with every cell run through `convert_cell`.
+pub fn tabular_to_typst(tex: &str, convert_cell: impl Fn(&str) -> String) -> Option<String> {
+    let start = tex.find("\\begin{tabular}")? + "\\begin{tabular}".len();
+    let body = &tex[start..];
+    // skip the column spec, which may contain braces itself
+    let spec_start = body.len() - body.trim_start().len();
+    if !body[spec_start..].starts_with('{') {
+        return None;
+    }
+    let mut depth = 0;
+    let spec_end = body[spec_start..].char_indices().find_map(|(i, c)| {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        (depth == 0).then_some(spec_start + i + 1)
+    })?;
+    let end = body.find("\\end{tabular}")?;
+    if end < spec_end {
+        return None;
+    }
+    let body = &body[spec_end..end];
+
+    let rows: Vec<Vec<String>> = body
+        .split("\\\\")
+        .map(|row| row.replace("\\hline", ""))
+        .filter(|row| !row.trim().is_empty())
+        .map(|row| row.split('&').map(|cell| convert_cell(cell.trim())).collect())
+        .collect();
+    let columns = rows.iter().map(Vec::len).max()?;
+    let mut table = format!("#table(\n  columns: {},\n", columns);
+    for row in rows {
+        let cells: Vec<String> = (0..columns).map(|i| format!("[{}]", row.get(i).map_or("", String::as_str))).collect();
+        table.push_str(&format!("  {},\n", cells.join(", ")));
+    }
+    table.push(')');
+    Some(table)
+}
+
+/// Convert the text around `<smiles>` tags with `convert`, the SMILES strings become Typst raw text.
+pub fn convert_with_smiles(text: &str, convert: impl Fn(&str) -> String) -> String {
+    let mut converted = String::new();
+    let mut rest = text;
+    while let Some((before, after)) = rest.split_once("<smiles>") {
+        let Some((smiles, after)) = after.split_once("</smiles>") else {
+            break;
+        };
+        if !before.trim().is_empty() {
+            converted.push_str(&convert(before));
+        }
+        converted.push_str(&format!("`{}`", smiles.trim()));
+        rest = after;
+    }
+    if !rest.trim().is_empty() {
+        converted.push_str(&convert(rest));
+    }
+    converted
+}
+
+/// Plain Unicode for TeX, for chat apps and emails that render neither TeX nor Typst.
+/// Scripts use Unicode superscript and subscript letters where they all exist, fractions use `⁄`.
+pub fn tex_to_unicode(tex: &str) -> String {
+    let chars: Vec<char> = tex.chars().collect();
+    let mut i = 0;
+    let unicode = unicode_group(&chars, &mut i, false);
+    let mut collapsed = String::with_capacity(unicode.len());
+    for c in unicode.chars() {
+        // spacing in TeX source is not meaningful, keep single spaces for readability
+        if c == ' ' && (collapsed.ends_with(' ') || collapsed.ends_with('\n') || collapsed.is_empty()) {
+            continue;
+        }
+        collapsed.push(c);
+    }
+    collapsed.lines().map(str::trim_end).collect::<Vec<_>>().join("\n").trim().to_string()
+}
+
+/// Convert until the end of the input, or the closing brace when `braced`.
+fn unicode_group(chars: &[char], i: &mut usize, braced: bool) -> String {
+    let mut output = String::new();
+    while *i < chars.len() {
+        let c = chars[*i];
+        match c {
+            '}' if braced => {
+                *i += 1;
+                return output;
+            }
+            '^' | '_' => {
+                *i += 1;
+                let script = unicode_argument(chars, i);
+                let script = script.trim();
+                let script_char = if c == '^' { superscript } else { subscript };
+                match script.chars().map(script_char).collect::<Option<String>>() {
+                    Some(converted) => output.push_str(&converted),
+                    None if script.chars().count() == 1 => output.push_str(&format!("{}{}", c, script)),
+                    None => output.push_str(&format!("{}({})", c, script)),
+                }
+            }
+            _ => output.push_str(&unicode_token(chars, i)),
+        }
+    }
+    output
+}
+
+/// The argument of a command or script: a braced group, a command or a single character.
+fn unicode_argument(chars: &[char], i: &mut usize) -> String {
+    while chars.get(*i) == Some(&' ') {
+        *i += 1;
+    }
+    match chars.get(*i) {
+        Some('{') => {
+            *i += 1;
+            unicode_group(chars, i, true)
+        }
+        Some(_) => unicode_token(chars, i),
+        None => String::new(),
+    }
+}
+
+/// Convert the character or command at `i`.
+fn unicode_token(chars: &[char], i: &mut usize) -> String {
+    let c = chars[*i];
+    *i += 1;
+    match c {
+        '{' => return unicode_group(chars, i, true),
+        '$' => return String::new(),
+        '&' | '~' => return " ".to_string(),
+        '\\' => {}
+        _ => return c.to_string(),
+    }
+
+    let start = *i;
+    while chars.get(*i).is_some_and(|c| c.is_ascii_alphabetic()) {
+        *i += 1;
+    }
+    if *i == start {
+        // a single character command like \\, \{ or \(
+        let Some(&c) = chars.get(*i) else {
+            return String::new();
+        };
+        *i += 1;
+        return match c {
+            '\\' => "\n".to_string(),
+            '(' | ')' | '[' | ']' => String::new(),
+            ',' | ';' | ':' | '!' | ' ' => " ".to_string(),
+            _ => c.to_string(),
+        };
+    }
+    let name: String = chars[start..*i].iter().collect();
+
+    let wrap = |text: String| {
+        if text.chars().count() > 1 && !text.chars().all(char::is_alphanumeric) {
+            format!("({})", text)
+        } else {
+            text
+        }
+    };
+    match name.as_str() {
+        "frac" | "dfrac" | "tfrac" => {
+            let numerator = unicode_argument(chars, i);
+            let denominator = unicode_argument(chars, i);
+            format!("{}⁄{}", wrap(numerator), wrap(denominator))
+        }
+        "sqrt" => {
+            let mut root = "√".to_string();
+            if chars.get(*i) == Some(&'[') {
+                let end = chars[*i..].iter().position(|c| *c == ']').map_or(chars.len(), |end| *i + end);
+                let degree: String = chars[*i + 1..end].iter().collect();
+                *i = (end + 1).min(chars.len());
+                root = match degree.trim() {
+                    "3" => "∛".to_string(),
+                    "4" => "∜".to_string(),
+                    degree => format!("{}√", degree.chars().filter_map(superscript).collect::<String>()),
+                };
+            }
+            format!("{}{}", root, wrap(unicode_argument(chars, i)))
+        }
+        "mathbb" => unicode_argument(chars, i)
+            .chars()
+            .map(|c| match c {
+                'R' => 'ℝ',
+                'N' => 'ℕ',
+                'Z' => 'ℤ',
+                'Q' => 'ℚ',
+                'C' => 'ℂ',
+                c => c,
+            })
+            .collect(),
+        "text" | "mathrm" | "mathbf" | "mathit" | "mathsf" | "mathcal" | "boldsymbol" | "operatorname" | "textbf"
+        | "textit" => unicode_argument(chars, i),
+        "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl" | "Bigr" => {
+            if chars.get(*i) == Some(&'.') {
+                *i += 1;
+            }
+            String::new()
+        }
+        "quad" | "qquad" => " ".to_string(),
+        "displaystyle" | "limits" | "nolimits" => String::new(),
+        name => unicode_symbol(name).map_or_else(|| name.to_string(), str::to_string),
+    }
+}
+
+fn unicode_symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" => "ϵ",
+        "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "rho" => "ρ",
+        "sigma" => "σ",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" => "ϕ",
+        "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "sum" => "∑",
+        "prod" => "∏",
+        "int" => "∫",
+        "iint" => "∬",
+        "oint" => "∮",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "pm" => "±",
+        "mp" => "∓",
+        "times" => "×",
+        "div" => "÷",
+        "cdot" => "·",
+        "cdots" | "ldots" | "dots" => "…",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "propto" => "∝",
+        "to" | "rightarrow" => "→",
+        "leftarrow" => "←",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "in" => "∈",
+        "notin" => "∉",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "cup" => "∪",
+        "cap" => "∩",
+        "emptyset" | "varnothing" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "neg" => "¬",
+        "wedge" | "land" => "∧",
+        "vee" | "lor" => "∨",
+        "prime" => "′",
+        "circ" => "∘",
+        "degree" => "°",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        _ => return None,
+    })
+}
+
+fn superscript(c: char) -> Option<char> {
+    const FROM: &str = "0123456789+-=()niabcdefghjklmoprstuvwxyz′";
+    const TO: &str = "⁰¹²³⁴⁵⁶⁷⁸⁹⁺⁻⁼⁽⁾ⁿⁱᵃᵇᶜᵈᵉᶠᵍʰʲᵏˡᵐᵒᵖʳˢᵗᵘᵛʷˣʸᶻ′";
+    FROM.chars().zip(TO.chars()).find(|(from, _)| *from == c).map(|(_, to)| to)
+}
+
+fn subscript(c: char) -> Option<char> {
+    const FROM: &str = "0123456789+-=()aehijklmnoprstuvx";
+    const TO: &str = "₀₁₂₃₄₅₆₇₈₉₊₋₌₍₎ₐₑₕᵢⱼₖₗₘₙₒₚᵣₛₜᵤᵥₓ";
+    FROM.chars().zip(TO.chars()).find(|(from, _)| *from == c).map(|(_, to)| to)
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+/// Estimated spending on snips beyond the Mathpix plan, in US dollars.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CostTracking {
+    pub enabled: bool,
+    /// Price of a snip sent after the plan is used up.
+    pub overage_price: f64,
+    /// Warn once a month costs more than this, 0 for no budget.
+    pub monthly_budget: f64,
+    /// Month the budget warning was last shown for, `YYYY-MM`.
+    pub warned_month: String,
+    /// Month `spent` adds up, `YYYY-MM`.
+    pub spent_month: String,
+    /// Estimated cost of the snips taken in `spent_month`, kept when they are deleted.
+    pub spent: f64,
+}
+
+impl Default for CostTracking {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            overage_price: 0.004,
+            monthly_budget: 0.0,
+            warned_month: String::new(),
+            spent_month: String::new(),
+            spent: 0.0,
+        }
+    }
+}
+
+impl CostTracking {
+    pub fn over_budget(&self, spent: f64) -> bool {
+        self.enabled && self.monthly_budget > 0.0 && spent > self.monthly_budget
+    }
+
+    /// Count the cost of a snip in the current month, starting over when a new month begins.
+    pub fn record(&mut self, cost: f64) {
+        let month = current_month();
+        if self.spent_month != month {
+            self.spent_month = month;
+            self.spent = 0.0;
+        }
+        self.spent += cost;
+    }
+
+    /// Estimated cost of the snips taken this month.
+    pub fn month_spent(&self) -> f64 {
+        if self.spent_month == current_month() {
+            self.spent
+        } else {
+            0.0
+        }
+    }
+}
+
+pub fn current_month() -> String {
+    chrono::Local::now().format("%Y-%m").to_string()
+}
@@ -0,0 +1,122 @@
+use crate::convert;
+
+/// Document the `.tex` export puts the snips into, at `{body}`.
+pub const DEFAULT_LATEX_TEMPLATE: &str = r"\documentclass{article}
+\usepackage{amsmath}
+\usepackage{amssymb}
+
+\begin{document}
+
+{body}
+
+\end{document}
+";
+
+/// What the document exports need of a snip.
+pub struct ExportSnip<'a> {
+    pub title: &'a str,
+    pub tex: &'a str,
+    pub typst: &'a str,
+    /// Path of the screenshot, empty when there is none.
+    pub image_path: String,
+}
+
+/// Pandoc/Quarto Markdown with a section per snip, holding its screenshot and its recognized text.
+pub fn markdown(snips: &[ExportSnip]) -> String {
+    let mut markdown = String::new();
+    for snip in snips {
+        let title = snip.title.trim();
+        markdown.push_str(&format!("## {}\n\n", if title.is_empty() { "Snip" } else { title }));
+        if !snip.image_path.is_empty() {
+            let alt = title.replace('[', "\\[").replace(']', "\\]");
+            markdown.push_str(&format!("![{}](<{}>)\n\n", alt, snip.image_path));
+        }
+        let text = convert::tex_to_pandoc(snip.tex);
+        if !text.is_empty() {
+            markdown.push_str(&text);
+            markdown.push_str("\n\n");
+        }
+    }
+    markdown
+}
+
+/// A LaTeX document from `template` with one `equation` environment per snip. Snips that mix prose
+/// and math are put in as they are.
+pub fn latex(snips: &[ExportSnip], template: &str) -> String {
+    let body: Vec<String> = snips
+        .iter()
+        .map(|snip| {
+            let content = match convert::single_formula(snip.tex) {
+                Some(formula) => format!("\\begin{{equation}}\n{}\n\\end{{equation}}", formula),
+                None => snip.tex.trim().to_string(),
+            };
+            let title = snip.title.trim();
+            if title.is_empty() { content } else { format!("% {}\n{}", title.replace('\n', " "), content) }
+        })
+        .collect();
+    template.replace("{body}", &body.join("\n\n"))
+}
+
+/// The TeX of one snip for org-mode: a single formula as an `equation` in a LaTeX export block,
+/// anything else as it is, since org reads `\(..\)` and `\[..\]` as LaTeX fragments.
+pub fn org_block(tex: &str) -> String {
+    match convert::single_formula(tex) {
+        Some(formula) => format!("#+begin_export latex\n\\begin{{equation}}\n{}\n\\end{{equation}}\n#+end_export", formula.trim()),
+        None => tex.trim().to_string(),
+    }
+}
+
+/// An org-mode outline with a heading per snip, linking its screenshot, for org-roam notes.
+pub fn org(snips: &[ExportSnip]) -> String {
+    let mut org = String::new();
+    for snip in snips {
+        let title = snip.title.trim().replace('\n', " ");
+        org.push_str(&format!("* {}\n", if title.is_empty() { "Snip" } else { &title }));
+        if !snip.image_path.is_empty() {
+            org.push_str(&format!("[[file:{}]]\n\n", snip.image_path));
+        }
+        let block = org_block(snip.tex);
+        if !block.is_empty() {
+            org.push_str(&block);
+            org.push_str("\n\n");
+        }
+    }
+    org
+}
+
+/// A printable HTML page per snip with its screenshot, TeX and Typst. It opens the print dialog
+/// when loaded, where browsers also offer saving as PDF.
+pub fn report_html(snips: &[ExportSnip]) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Typst Scan</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+section { break-after: page; }
+img { max-width: 100%; max-height: 40vh; border: 1px solid #ccc; }
+pre { white-space: pre-wrap; background: #f4f4f4; padding: 0.5em; }
+</style>
+</head>
+<body onload=\"window.print()\">
+",
+    );
+    for snip in snips {
+        html.push_str("<section>\n");
+        html.push_str(&format!("<h2>{}</h2>\n", escape_html(snip.title)));
+        if !snip.image_path.is_empty() {
+            html.push_str(&format!("<img src=\"file://{}\">\n", escape_html(&snip.image_path)));
+        }
+        html.push_str(&format!("<h3>TeX</h3>\n<pre>{}</pre>\n", escape_html(snip.tex)));
+        html.push_str(&format!("<h3>Typst</h3>\n<pre>{}</pre>\n", escape_html(snip.typst)));
+        html.push_str("</section>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,47 @@
+use crate::worker::{self, TlsSettings};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+pub(crate) const FOLDERS_API_URL: &str = "https://snip-api.mathpix.com/v1/folders";
+
+/// A folder of the Mathpix account, snips are uploaded into one to organize them in the web app.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct MathpixFolder {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct FoldersResponse {
+    #[serde(default)]
+    folders: Vec<MathpixFolder>,
+}
+
+/// Fetch the folders of the account in the background.
+pub fn fetch_folders(ctx: egui::Context, api_key: String, tls: TlsSettings) -> Receiver<Result<Vec<MathpixFolder>, String>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let folders = worker::http_client(&tls)
+            .get(FOLDERS_API_URL)
+            .headers(worker::request_headers(&api_key))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json::<FoldersResponse>())
+            .map(|response| response.folders)
+            .map_err(|e| e.to_string());
+        if let Err(e) = &folders {
+            eprintln!("Failed to fetch the Mathpix folders: {}", e);
+        }
+        let _ = sender.send(folders);
+        ctx.request_repaint();
+    });
+    receiver
+}
+
+/// Name of a folder for the UI, its id when it is not in the fetched list.
+pub fn folder_name<'a>(folders: &'a [MathpixFolder], id: &'a str) -> &'a str {
+    folders.iter().find(|folder| folder.id == id && !folder.name.is_empty()).map_or(id, |folder| folder.name.as_str())
+}
@@ -0,0 +1,94 @@
+use crate::capture;
+use crate::i18n::tr;
+use eframe::egui_wgpu::{WgpuConfiguration, WgpuSetup, WgpuSetupCreateNew};
+use eframe::wgpu;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// How the window is drawn. Some VMs and remote desktop sessions have no GPU wgpu can use.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GraphicsBackend {
+    #[default]
+    Wgpu,
+    /// OpenGL, often still works where wgpu finds no adapter.
+    Glow,
+    /// wgpu on a CPU adapter such as llvmpipe or WARP, slow but needs no GPU.
+    Software,
+}
+
+impl GraphicsBackend {
+    pub const ALL: [GraphicsBackend; 3] = [GraphicsBackend::Wgpu, GraphicsBackend::Glow, GraphicsBackend::Software];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GraphicsBackend::Wgpu => "wgpu",
+            GraphicsBackend::Glow => "glow",
+            GraphicsBackend::Software => "software",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GraphicsBackend::Wgpu => tr("GPU (wgpu)"),
+            GraphicsBackend::Glow => tr("OpenGL (glow)"),
+            GraphicsBackend::Software => tr("Software"),
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|backend| backend.name() == name.trim())
+    }
+
+    /// Backends to try in turn when this one fails to start.
+    pub fn fallbacks(self) -> Vec<GraphicsBackend> {
+        Self::ALL.into_iter().filter(|backend| *backend != self).collect()
+    }
+
+    pub fn apply(self, native_options: &mut eframe::NativeOptions) {
+        match self {
+            GraphicsBackend::Wgpu => native_options.renderer = eframe::Renderer::Wgpu,
+            GraphicsBackend::Glow => native_options.renderer = eframe::Renderer::Glow,
+            GraphicsBackend::Software => {
+                native_options.renderer = eframe::Renderer::Wgpu;
+                native_options.wgpu_options = WgpuConfiguration {
+                    wgpu_setup: WgpuSetup::CreateNew(WgpuSetupCreateNew {
+                        native_adapter_selector: Some(Arc::new(|adapters: &[wgpu::Adapter], _surface: Option<&wgpu::Surface<'_>>| {
+                            adapters
+                                .iter()
+                                .find(|adapter| adapter.get_info().device_type == wgpu::DeviceType::Cpu)
+                                .cloned()
+                                .ok_or_else(|| "No software adapter found".to_string())
+                        })),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                };
+            }
+        }
+    }
+}
+
+/// The backend picked in the settings is kept in a file of its own, it is needed before the app
+/// data can be read.
+fn saved_path(profile: Option<&str>) -> Option<PathBuf> {
+    let dir = capture::get_storage_dir()?;
+    Some(match profile {
+        Some(profile) => dir.join("profiles").join(profile).join("renderer"),
+        None => dir.join("renderer"),
+    })
+}
+
+pub fn load(profile: Option<&str>) -> Option<GraphicsBackend> {
+    let name = std::fs::read_to_string(saved_path(profile)?).ok()?;
+    GraphicsBackend::parse(&name)
+}
+
+pub fn save(profile: Option<&str>, backend: GraphicsBackend) {
+    let Some(path) = saved_path(profile) else {
+        return;
+    };
+    let written = path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|()| std::fs::write(&path, backend.name()));
+    if let Err(e) = written {
+        eprintln!("Failed to save the renderer to {:?}: {:?}", path, e);
+    }
+}
@@ -0,0 +1,489 @@
+use crate::app::ClipboardMode;
+#[cfg(target_os = "linux")]
+use crate::portal;
+use crate::worker::{OcrPreset, SnipTask, TaskKind};
+use eframe::egui;
+use egui_keybind::Shortcut;
+use livesplit_hotkey::{Hook, Hotkey, KeyCode, Modifiers};
+use std::sync::mpsc::Sender;
+
+/// Everything that can be bound to a global hotkey.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HotkeyAction {
+    Capture,
+    CaptureTeX,
+    CaptureContinuous,
+    CaptureEquation,
+    CaptureDocument,
+    CaptureTable,
+    CaptureChemistry,
+    CaptureText,
+    RepeatRegion,
+    ActiveWindow,
+    SavedWindow,
+    Scrcpy,
+    ClipboardImage,
+    ToggleAutoCapture,
+    CopyLatest,
+    PasteLatest,
+    ToggleWindow,
+    ToggleMiniOverlay,
+    QuickCopy,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 19] = [
+        HotkeyAction::Capture,
+        HotkeyAction::CaptureTeX,
+        HotkeyAction::CaptureContinuous,
+        HotkeyAction::CaptureEquation,
+        HotkeyAction::CaptureDocument,
+        HotkeyAction::CaptureTable,
+        HotkeyAction::CaptureChemistry,
+        HotkeyAction::CaptureText,
+        HotkeyAction::RepeatRegion,
+        HotkeyAction::ActiveWindow,
+        HotkeyAction::SavedWindow,
+        HotkeyAction::Scrcpy,
+        HotkeyAction::ClipboardImage,
+        HotkeyAction::ToggleAutoCapture,
+        HotkeyAction::CopyLatest,
+        HotkeyAction::PasteLatest,
+        HotkeyAction::ToggleWindow,
+        HotkeyAction::ToggleMiniOverlay,
+        HotkeyAction::QuickCopy,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HotkeyAction::Capture => "Capture",
+            HotkeyAction::CaptureTeX => "Capture as TeX",
+            HotkeyAction::CaptureContinuous => "Capture to continuous clipboard",
+            HotkeyAction::CaptureEquation => "Capture equation",
+            HotkeyAction::CaptureDocument => "Capture document",
+            HotkeyAction::CaptureTable => "Capture table",
+            HotkeyAction::CaptureChemistry => "Capture chemistry",
+            HotkeyAction::CaptureText => "Capture text",
+            HotkeyAction::RepeatRegion => "Repeat last region",
+            HotkeyAction::ActiveWindow => "Capture active window",
+            HotkeyAction::SavedWindow => "Capture saved window",
+            HotkeyAction::Scrcpy => "Capture scrcpy",
+            HotkeyAction::ClipboardImage => "OCR clipboard image",
+            HotkeyAction::ToggleAutoCapture => "Toggle auto capture",
+            HotkeyAction::CopyLatest => "Copy latest result again",
+            HotkeyAction::PasteLatest => "Paste latest result",
+            HotkeyAction::ToggleWindow => "Show/hide window",
+            HotkeyAction::ToggleMiniOverlay => "Show/hide mini window",
+            HotkeyAction::QuickCopy => "Quick copy popup",
+        }
+    }
+
+    /// The task to queue for the worker, `None` for actions handled by the UI.
+    fn snip_task(&self) -> Option<SnipTask> {
+        match self {
+            HotkeyAction::Capture => Some(SnipTask::new()),
+            HotkeyAction::CaptureTeX => Some(SnipTask::new().with_clipboard_mode(ClipboardMode::CopyTeX)),
+            HotkeyAction::CaptureContinuous => Some(SnipTask::new().with_clipboard_mode(ClipboardMode::Continuous)),
+            HotkeyAction::CaptureEquation => Some(SnipTask::new().with_preset(OcrPreset::Equation)),
+            HotkeyAction::CaptureDocument => Some(SnipTask::new().with_preset(OcrPreset::Document)),
+            HotkeyAction::CaptureTable => Some(SnipTask::new().with_preset(OcrPreset::Table)),
+            HotkeyAction::CaptureChemistry => Some(SnipTask::new().with_preset(OcrPreset::Chemistry)),
+            HotkeyAction::CaptureText => Some(SnipTask::new().with_preset(OcrPreset::Text)),
+            HotkeyAction::RepeatRegion => Some(SnipTask::with_kind(TaskKind::RepeatRegion)),
+            HotkeyAction::ActiveWindow => Some(SnipTask::with_kind(TaskKind::ActiveWindow)),
+            HotkeyAction::SavedWindow => Some(SnipTask::with_kind(TaskKind::SavedWindow)),
+            HotkeyAction::Scrcpy => Some(SnipTask::with_kind(TaskKind::Scrcpy)),
+            HotkeyAction::ClipboardImage => Some(SnipTask::with_kind(TaskKind::ClipboardImage)),
+            HotkeyAction::ToggleAutoCapture
+            | HotkeyAction::CopyLatest
+            | HotkeyAction::PasteLatest
+            | HotkeyAction::ToggleWindow
+            | HotkeyAction::ToggleMiniOverlay
+            | HotkeyAction::QuickCopy => None,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct HotkeyBinding {
+    pub action: HotkeyAction,
+    pub shortcut: Shortcut,
+    /// The hotkey currently registered with the hook, derived from `shortcut`.
+    pub hotkey: Option<Hotkey>,
+    /// The shortcut `hotkey` was registered from, restored when a new one fails to register.
+    #[serde(skip)]
+    registered_shortcut: Option<Shortcut>,
+    /// Why the last registration failed, shown next to the binding in the settings.
+    #[serde(skip)]
+    pub error: Option<String>,
+}
+
+impl HotkeyBinding {
+    fn unbound(action: HotkeyAction) -> Self {
+        Self {
+            action,
+            shortcut: Shortcut::new(None, None),
+            hotkey: None,
+            registered_shortcut: None,
+            error: None,
+        }
+    }
+
+    /// Register the saved hotkey at startup. A hotkey that is taken by now is dropped instead of aborting.
+    pub fn register_saved(&mut self, hook: &GlobalHotkeys) {
+        if self.hotkey.is_none() {
+            return;
+        }
+        // the shortcut is what the settings show, derive the hotkey from it again in case they drifted apart
+        let Some(hotkey) = shortcut_to_hotkey(&self.shortcut) else {
+            eprintln!("Saved shortcut for {:?} has no matching hotkey", self.action);
+            self.error = Some("This key cannot be used as a global hotkey".to_string());
+            self.hotkey = None;
+            return;
+        };
+        self.hotkey = Some(hotkey);
+        match hook.register(hotkey, self.action) {
+            Ok(()) => self.registered_shortcut = Some(self.shortcut.clone()),
+            Err(e) => {
+                eprintln!("Failed to register hotkey {} for {:?}: {}", describe(&hotkey), self.action, e);
+                self.error = Some(format!("{} could not be registered, it may be used by another application", describe(&hotkey)));
+                self.hotkey = None;
+            }
+        }
+    }
+
+    /// Register the recorded shortcut in place of the current hotkey. When that fails the previous
+    /// hotkey stays registered and the error is kept for the settings to show.
+    pub fn register(&mut self, hook: &GlobalHotkeys, in_use: &[(HotkeyAction, Hotkey)]) -> bool {
+        let Some(hotkey) = shortcut_to_hotkey(&self.shortcut) else {
+            self.fail("This key cannot be used as a global hotkey".to_string());
+            return false;
+        };
+        if let Some((other, _)) = in_use.iter().find(|(action, bound)| *action != self.action && *bound == hotkey) {
+            self.fail(format!("{} is already used for \"{}\"", describe(&hotkey), other.label()));
+            return false;
+        }
+
+        let previous = self.hotkey.take();
+        if let Some(previous) = previous {
+            hook.unregister(previous);
+        }
+        match hook.register(hotkey, self.action) {
+            Ok(()) => {
+                self.hotkey = Some(hotkey);
+                self.registered_shortcut = Some(self.shortcut.clone());
+                self.error = None;
+                true
+            }
+            Err(e) => {
+                eprintln!("Failed to register hotkey {} for {:?}: {}", describe(&hotkey), self.action, e);
+                if let Some(previous) = previous {
+                    self.hotkey = hook.register(previous, self.action).is_ok().then_some(previous);
+                }
+                self.fail(format!("{} could not be registered, it may be used by another application", describe(&hotkey)));
+                false
+            }
+        }
+    }
+
+    /// The hotkey was accepted by `register` but the desktop did not bind it in the end.
+    pub fn refused(&mut self, error: String) {
+        eprintln!("Hotkey for {:?} was not bound: {}", self.action, error);
+        self.hotkey = None;
+        self.registered_shortcut = None;
+        self.fail(error);
+    }
+
+    pub fn clear(&mut self, hook: &GlobalHotkeys) {
+        if let Some(hotkey) = self.hotkey.take() {
+            hook.unregister(hotkey);
+        }
+        self.shortcut = Shortcut::new(None, None);
+        self.registered_shortcut = None;
+        self.error = None;
+    }
+
+    fn fail(&mut self, error: String) {
+        self.error = Some(error);
+        self.shortcut = self.registered_shortcut.clone().unwrap_or_else(|| Shortcut::new(None, None));
+    }
+}
+
+/// Human readable key combination, e.g. `Ctrl+Alt+Z`.
+pub fn describe(hotkey: &Hotkey) -> String {
+    let mut description = String::new();
+    for (modifier, name) in [
+        (Modifiers::CONTROL, "Ctrl"),
+        (Modifiers::ALT, "Alt"),
+        (Modifiers::SHIFT, "Shift"),
+        (Modifiers::META, "Meta"),
+    ] {
+        if hotkey.modifiers.contains(modifier) {
+            description.push_str(name);
+            description.push('+');
+        }
+    }
+    let key = hotkey.key_code.name();
+    description.push_str(key.strip_prefix("Key").or_else(|| key.strip_prefix("Digit")).unwrap_or(key));
+    description
+}
+
+pub fn default_bindings() -> Vec<HotkeyBinding> {
+    HotkeyAction::ALL
+        .iter()
+        .map(|&action| match action {
+            HotkeyAction::Capture => HotkeyBinding {
+                action,
+                shortcut: Shortcut::new(
+                    Some(egui::KeyboardShortcut::new(
+                        egui::Modifiers::CTRL | egui::Modifiers::ALT,
+                        egui::Key::Z,
+                    )),
+                    None,
+                ),
+                hotkey: Some(Hotkey {
+                    key_code: KeyCode::KeyZ,
+                    modifiers: Modifiers::CONTROL | Modifiers::ALT,
+                }),
+                registered_shortcut: None,
+                error: None,
+            },
+            action => HotkeyBinding::unbound(action),
+        })
+        .collect()
+}
+
+/// Make sure every action has exactly one row, in the order of `HotkeyAction::ALL`,
+/// so bindings saved by older versions pick up newly added actions.
+pub fn normalize_bindings(bindings: &mut Vec<HotkeyBinding>) {
+    let mut normalized = Vec::with_capacity(HotkeyAction::ALL.len());
+    for action in HotkeyAction::ALL {
+        let binding = bindings.iter().find(|binding| binding.action == action).cloned();
+        normalized.push(binding.unwrap_or_else(|| HotkeyBinding::unbound(action)));
+    }
+    *bindings = normalized;
+}
+
+/// Routes hotkey presses: capture actions are queued for the worker, the rest goes to the UI.
+#[derive(Clone)]
+pub struct HotkeyDispatcher {
+    task_sender: Sender<SnipTask>,
+    action_sender: Sender<HotkeyAction>,
+    ctx: egui::Context,
+}
+
+impl HotkeyDispatcher {
+    pub fn new(task_sender: Sender<SnipTask>, action_sender: Sender<HotkeyAction>, ctx: egui::Context) -> Self {
+        Self {
+            task_sender,
+            action_sender,
+            ctx,
+        }
+    }
+
+    pub fn dispatch(&self, action: HotkeyAction) {
+        // the receivers only go away when the app is closing
+        match action.snip_task() {
+            Some(task) => {
+                let _ = self.task_sender.send(task);
+            }
+            None => {
+                let _ = self.action_sender.send(action);
+                self.ctx.request_repaint();
+            }
+        }
+    }
+
+    pub fn request_repaint(&self) {
+        self.ctx.request_repaint();
+    }
+}
+
+/// The global hotkey backend: livesplit-hotkey's hook, or the desktop portal on Wayland
+/// where applications cannot grab keys themselves.
+pub enum GlobalHotkeys {
+    Hook(Hook, HotkeyDispatcher),
+    #[cfg(target_os = "linux")]
+    Portal(portal::PortalShortcuts),
+}
+
+impl GlobalHotkeys {
+    pub fn new(dispatcher: HotkeyDispatcher) -> Self {
+        #[cfg(target_os = "linux")]
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            println!("Wayland session, using the GlobalShortcuts portal for hotkeys");
+            return GlobalHotkeys::Portal(portal::PortalShortcuts::new(dispatcher));
+        }
+
+        match Hook::new() {
+            Ok(hook) => GlobalHotkeys::Hook(hook, dispatcher),
+            #[cfg(target_os = "linux")]
+            Err(e) => {
+                eprintln!("Failed to create hotkey hook, falling back to the GlobalShortcuts portal: {:?}", e);
+                GlobalHotkeys::Portal(portal::PortalShortcuts::new(dispatcher))
+            }
+            #[cfg(not(target_os = "linux"))]
+            Err(e) => panic!("Failed to create hotkey hook: {:?}", e),
+        }
+    }
+
+    pub fn register(&self, hotkey: Hotkey, action: HotkeyAction) -> Result<(), String> {
+        match self {
+            GlobalHotkeys::Hook(hook, dispatcher) => {
+                let dispatcher = dispatcher.clone();
+                hook.register(hotkey, move || {
+                    println!("Hotkey pressed: {:?}", action);
+                    dispatcher.dispatch(action);
+                })
+                .map_err(|e| e.to_string())
+            }
+            #[cfg(target_os = "linux")]
+            GlobalHotkeys::Portal(portal) => {
+                portal.register(hotkey, action);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn unregister(&self, hotkey: Hotkey) {
+        match self {
+            GlobalHotkeys::Hook(hook, _) => {
+                if let Err(e) = hook.unregister(hotkey) {
+                    eprintln!("Failed to unregister hotkey: {:?}", e);
+                }
+            }
+            #[cfg(target_os = "linux")]
+            GlobalHotkeys::Portal(portal) => portal.unregister(hotkey),
+        }
+    }
+
+    /// Hotkeys that turned out not to be bound after `register` accepted them, which happens with
+    /// the portal as the desktop binds them later.
+    pub fn take_failures(&self) -> Vec<(HotkeyAction, String)> {
+        match self {
+            GlobalHotkeys::Hook(..) => Vec::new(),
+            #[cfg(target_os = "linux")]
+            GlobalHotkeys::Portal(portal) => portal.take_failures(),
+        }
+    }
+}
+
+/// Convert the keybind recorded in the settings into a hotkey for the global hook.
+pub fn shortcut_to_hotkey(shortcut: &Shortcut) -> Option<Hotkey> {
+    let keyboard = shortcut.keyboard()?;
+    let key_code = key_code(keyboard.logical_key)?;
+    let mut modifiers = Modifiers::empty();
+
+    if keyboard.modifiers.ctrl {
+        modifiers.insert(Modifiers::CONTROL);
+    }
+    if keyboard.modifiers.alt {
+        modifiers.insert(Modifiers::ALT);
+    }
+    if keyboard.modifiers.shift {
+        modifiers.insert(Modifiers::SHIFT);
+    }
+    if keyboard.modifiers.mac_cmd {
+        modifiers.insert(Modifiers::META);
+    }
+
+    Some(Hotkey { key_code, modifiers })
+}
+
+pub fn key_code(key: egui::Key) -> Option<KeyCode> {
+    KEY_CODES.iter().find(|(egui_key, _)| *egui_key == key).map(|(_, key_code)| *key_code)
+}
+
+/// egui keys and the physical key codes the global hook listens for, assuming a US layout
+/// for punctuation. Keys without a fixed physical key (e.g. `Pipe`, `Plus`) are left out.
+pub const KEY_CODES: &[(egui::Key, KeyCode)] = &[
+    (egui::Key::ArrowDown, KeyCode::ArrowDown),
+    (egui::Key::ArrowLeft, KeyCode::ArrowLeft),
+    (egui::Key::ArrowRight, KeyCode::ArrowRight),
+    (egui::Key::ArrowUp, KeyCode::ArrowUp),
+    (egui::Key::Escape, KeyCode::Escape),
+    (egui::Key::Tab, KeyCode::Tab),
+    (egui::Key::Backspace, KeyCode::Backspace),
+    (egui::Key::Enter, KeyCode::Enter),
+    (egui::Key::Space, KeyCode::Space),
+    (egui::Key::Insert, KeyCode::Insert),
+    (egui::Key::Delete, KeyCode::Delete),
+    (egui::Key::Home, KeyCode::Home),
+    (egui::Key::End, KeyCode::End),
+    (egui::Key::PageUp, KeyCode::PageUp),
+    (egui::Key::PageDown, KeyCode::PageDown),
+    (egui::Key::Copy, KeyCode::Copy),
+    (egui::Key::Cut, KeyCode::Cut),
+    (egui::Key::Paste, KeyCode::Paste),
+    (egui::Key::Comma, KeyCode::Comma),
+    (egui::Key::Backslash, KeyCode::Backslash),
+    (egui::Key::Slash, KeyCode::Slash),
+    (egui::Key::OpenBracket, KeyCode::BracketLeft),
+    (egui::Key::CloseBracket, KeyCode::BracketRight),
+    (egui::Key::Backtick, KeyCode::Backquote),
+    (egui::Key::Minus, KeyCode::Minus),
+    (egui::Key::Period, KeyCode::Period),
+    (egui::Key::Equals, KeyCode::Equal),
+    (egui::Key::Semicolon, KeyCode::Semicolon),
+    (egui::Key::Quote, KeyCode::Quote),
+    (egui::Key::Num0, KeyCode::Digit0),
+    (egui::Key::Num1, KeyCode::Digit1),
+    (egui::Key::Num2, KeyCode::Digit2),
+    (egui::Key::Num3, KeyCode::Digit3),
+    (egui::Key::Num4, KeyCode::Digit4),
+    (egui::Key::Num5, KeyCode::Digit5),
+    (egui::Key::Num6, KeyCode::Digit6),
+    (egui::Key::Num7, KeyCode::Digit7),
+    (egui::Key::Num8, KeyCode::Digit8),
+    (egui::Key::Num9, KeyCode::Digit9),
+    (egui::Key::A, KeyCode::KeyA),
+    (egui::Key::B, KeyCode::KeyB),
+    (egui::Key::C, KeyCode::KeyC),
+    (egui::Key::D, KeyCode::KeyD),
+    (egui::Key::E, KeyCode::KeyE),
+    (egui::Key::F, KeyCode::KeyF),
+    (egui::Key::G, KeyCode::KeyG),
+    (egui::Key::H, KeyCode::KeyH),
+    (egui::Key::I, KeyCode::KeyI),
+    (egui::Key::J, KeyCode::KeyJ),
+    (egui::Key::K, KeyCode::KeyK),
+    (egui::Key::L, KeyCode::KeyL),
+    (egui::Key::M, KeyCode::KeyM),
+    (egui::Key::N, KeyCode::KeyN),
+    (egui::Key::O, KeyCode::KeyO),
+    (egui::Key::P, KeyCode::KeyP),
+    (egui::Key::Q, KeyCode::KeyQ),
+    (egui::Key::R, KeyCode::KeyR),
+    (egui::Key::S, KeyCode::KeyS),
+    (egui::Key::T, KeyCode::KeyT),
+    (egui::Key::U, KeyCode::KeyU),
+    (egui::Key::V, KeyCode::KeyV),
+    (egui::Key::W, KeyCode::KeyW),
+    (egui::Key::X, KeyCode::KeyX),
+    (egui::Key::Y, KeyCode::KeyY),
+    (egui::Key::Z, KeyCode::KeyZ),
+    (egui::Key::F1, KeyCode::F1),
+    (egui::Key::F2, KeyCode::F2),
+    (egui::Key::F3, KeyCode::F3),
+    (egui::Key::F4, KeyCode::F4),
+    (egui::Key::F5, KeyCode::F5),
+    (egui::Key::F6, KeyCode::F6),
+    (egui::Key::F7, KeyCode::F7),
+    (egui::Key::F8, KeyCode::F8),
+    (egui::Key::F9, KeyCode::F9),
+    (egui::Key::F10, KeyCode::F10),
+    (egui::Key::F11, KeyCode::F11),
+    (egui::Key::F12, KeyCode::F12),
+    (egui::Key::F13, KeyCode::F13),
+    (egui::Key::F14, KeyCode::F14),
+    (egui::Key::F15, KeyCode::F15),
+    (egui::Key::F16, KeyCode::F16),
+    (egui::Key::F17, KeyCode::F17),
+    (egui::Key::F18, KeyCode::F18),
+    (egui::Key::F19, KeyCode::F19),
+    (egui::Key::F20, KeyCode::F20),
+    (egui::Key::F21, KeyCode::F21),
+    (egui::Key::F22, KeyCode::F22),
+    (egui::Key::F23, KeyCode::F23),
+    (egui::Key::F24, KeyCode::F24),
+];
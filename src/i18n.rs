@@ -0,0 +1,865 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Languages of the user interface. Texts are looked up by their English version,
+/// which is also used when a translation is missing.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+    English,
+    SimplifiedChinese,
+    Japanese,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::SimplifiedChinese, Language::Japanese];
+
+    /// The name of the language in that language.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::SimplifiedChinese => "简体中文",
+            Language::Japanese => "日本語",
+        }
+    }
+
+    /// The language matching the system locale, English if it is not translated.
+    pub fn detect() -> Language {
+        let locale = sys_locale::get_locale().unwrap_or_default().to_lowercase();
+        if locale.starts_with("zh") {
+            Language::SimplifiedChinese
+        } else if locale.starts_with("ja") {
+            Language::Japanese
+        } else {
+            Language::English
+        }
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+fn language() -> Language {
+    Language::ALL[LANGUAGE.load(Ordering::Relaxed) as usize]
+}
+
+/// Translate a UI text into the current language.
+pub fn tr(text: &'static str) -> &'static str {
+    let translated = match language() {
+        Language::English => None,
+        Language::SimplifiedChinese => zh_cn(text),
+        Language::Japanese => ja(text),
+    };
+    translated.unwrap_or(text)
+}
+
+fn zh_cn(text: &str) -> Option<&'static str> {
+    Some(match text {
+        // menu bar
+        "Snips" => "截图",
+        "Continuous Clipboard" => "连续剪贴板",
+        "Replace Rules" => "替换规则",
+        "Stats" => "统计",
+        "Settings" => "设置",
+        "Mini" => "迷你窗口",
+        "Show a small always-on-top window with the latest result" => "显示一个置顶的小窗口，其中包含最新结果",
+        "Keep this window above other windows" => "使此窗口保持在其他窗口之上",
+        // snips
+        "Capture" => "截取",
+        "Region" => "区域",
+        "Select a region to capture and remember, right click to pick a display" => "选择要截取并记住的区域，右键选择显示器",
+        "All displays" => "所有显示器",
+        "Repeat" => "重复",
+        "Capture the last selected region again" => "再次截取上次选择的区域",
+        "Clipboard" => "剪贴板",
+        "OCR the image on the clipboard" => "识别剪贴板中的图片",
+        "regenerate" => "重新生成",
+        "Copy" => "复制",
+        "No snips yet" => "还没有截图",
+        "Copied latest result" => "已复制最新结果",
+        "Capture failed:" => "截取失败：",
+        "Capture Countdown" => "截取倒计时",
+        "Capturing in {}…" => "{} 秒后截取…",
+        "Select Region" => "选择区域",
+        // continuous clipboard
+        "Clipboard Mode" => "剪贴板模式",
+        "Continuous" => "连续",
+        "Copy TeX" => "复制 TeX",
+        "Copy Typst" => "复制 Typst",
+        "copy all" => "全部复制",
+        "take all" => "全部取出",
+        "Auto capture last region" => "自动截取上次区域",
+        "Capture a region first" => "请先截取一个区域",
+        "every" => "每",
+        // replace rules
+        "load rules" => "加载规则",
+        "register rules" => "应用规则",
+        "Registered rules" => "规则已应用",
+        "Failed to parse rules" => "规则解析失败",
+        // settings
+        "Language" => "语言",
+        "System" => "跟随系统",
+        "Theme" => "主题",
+        "Default" => "默认",
+        "High Contrast" => "高对比度",
+        "Custom" => "自定义",
+        "Accent" => "强调色",
+        "Panels" => "面板",
+        "Code background" => "代码背景",
+        "Code text" => "代码文字",
+        "Extra Fonts" => "附加字体",
+        "add font…" => "添加字体…",
+        "For scripts the bundled fonts don't cover" => "用于内置字体未覆盖的文字",
+        "Mathpix API Key" => "Mathpix API 密钥",
+        "Global Hotkeys" => "全局快捷键",
+        "register" => "注册",
+        "clear" => "清除",
+        "Registered hotkey" => "快捷键已注册",
+        "scrcpy Window" => "scrcpy 窗口",
+        "Window Title:" => "窗口标题：",
+        "Leave empty to use the first scrcpy window" => "留空则使用第一个 scrcpy 窗口",
+        "detect" => "检测",
+        "No scrcpy window found" => "未找到 scrcpy 窗口",
+        "Crop:" => "裁剪：",
+        "top " => "上 ",
+        "right " => "右 ",
+        "bottom " => "下 ",
+        "left " => "左 ",
+        "Capture Delay" => "截取延迟",
+        "Count down before capturing, to open menus or hover over content first" => {
+            "截取前倒计时，以便先打开菜单或将鼠标悬停在内容上"
+        }
+        "Default Display" => "默认显示器",
+        "{} (primary)" => "{}（主显示器）",
+        "refresh" => "刷新",
+        "Settings File" => "设置文件",
+        "export…" => "导出…",
+        "import…" => "导入…",
+        "include API key" => "包含 API 密钥",
+        "Exported settings" => "设置已导出",
+        "Imported settings" => "设置已导入",
+        "Failed to export settings:" => "导出设置失败：",
+        "Failed to import settings:" => "导入设置失败：",
+        "write config.toml" => "写入 config.toml",
+        "Changes to config.toml in the storage folder are loaded automatically" => "存储文件夹中 config.toml 的更改会自动加载",
+        "Wrote config.toml" => "已写入 config.toml",
+        "Failed to load config.toml:" => "加载 config.toml 失败：",
+        "Delete All Snips" => "删除所有截图",
+        "delete!!!" => "删除!!!",
+        "API usage" => "API 用量",
+        "Bring Forward Window" => "前置窗口",
+        "Hide When Capturing" => "截取时隐藏",
+        "Minimize this window while taking a screenshot" => "截图时最小化此窗口",
+        "Auto Paste" => "自动粘贴",
+        "Paste into the target window after copying" => "复制后粘贴到目标窗口",
+        "Capture Window" => "截取窗口",
+        "Process name:" => "进程名：",
+        "Pick running window" => "选择运行中的窗口",
+        "No windows found, try refreshing" => "未找到窗口，请尝试刷新",
+        // setup wizard
+        "Welcome to Typst Scan" => "欢迎使用 Typst Scan",
+        "Choose the OCR service to use." => "选择要使用的识别服务。",
+        "The only supported service for now" => "目前唯一支持的服务",
+        "Enter your Mathpix API key." => "输入你的 Mathpix API 密钥。",
+        "validate" => "验证",
+        "The key works" => "密钥有效",
+        "Pick the global hotkey that starts a capture." => "选择用于开始截取的全局快捷键。",
+        "What should be copied after a capture?" => "截取后复制什么内容？",
+        "Select some math on screen to check that everything works." => "在屏幕上选择一些公式，检查一切是否正常。",
+        "It works:" => "成功：",
+        "Waiting for the result…" => "正在等待结果…",
+        "skip setup" => "跳过设置",
+        "Next" => "下一步",
+        "Done" => "完成",
+        "Typst Scan {} is available" => "Typst Scan {} 已发布",
+        "download" => "下载",
+        "What's new" => "更新内容",
+        "Updates" => "更新",
+        "Check for new releases on GitHub at startup" => "启动时在 GitHub 上检查新版本",
+        // stats
+        "Tags" => "标签",
+        "comma separated" => "用逗号分隔",
+        "Overview" => "概览",
+        "Average confidence" => "平均置信度",
+        "Average OCR latency" => "平均识别耗时",
+        "Snips per day" => "每日截图数",
+        "No dated snips yet" => "还没有带日期的截图",
+        "Most used tags" => "最常用的标签",
+        "No tags yet" => "还没有标签",
+        // snip details
+        "Details" => "详细信息",
+        "Captured" => "截取时间",
+        "Confidence" => "置信度",
+        "OCR time" => "识别耗时",
+        "Mathpix id" => "Mathpix ID",
+        "Processed at" => "处理时间",
+        "Upload time" => "上传耗时",
+        "Auto rotation" => "自动旋转",
+        "Font size" => "字号",
+        "OCR version" => "OCR 版本",
+        "No details were saved for this snip" => "此截图没有保存详细信息",
+        "Original image" => "原始图片",
+        "Rendered image" => "渲染图片",
+        "open" => "打开",
+        "Copied" => "已复制",
+        "Raw" => "原样",
+        "TeX Delimiters" => "TeX 定界符",
+        "Wrapping of single formulas copied in Copy TeX mode" => "以复制 TeX 模式复制单个公式时使用的包裹方式",
+        "Output Cleanup" => "输出清理",
+        "Trim leading and trailing whitespace" => "去除首尾空白",
+        "Collapse repeated blank lines" => "合并连续空行",
+        "Strip the math delimiters from TeX copied as raw" => "以原样方式复制 TeX 时去除数学定界符",
+        "TeX Normalization" => "TeX 规范化",
+        "Unbalanced braces" => "花括号不匹配",
+        "Environment {} is not closed" => "环境 {} 未闭合",
+        "\\end of {} without a \\begin" => "{} 的 \\end 没有对应的 \\begin",
+        "Unbalanced \\left and \\right" => "\\left 与 \\right 不匹配",
+        "Conversion to Typst failed" => "转换为 Typst 失败",
+        "Typst Formatting" => "Typst 格式化",
+        "Format the Typst output" => "格式化 Typst 输出",
+        "characters per line" => "每行字符数",
+        "split" => "拆分",
+        "Make a snip of every equation" => "为每个公式创建一个截图",
+        "Merge {} snips" => "合并 {} 个截图",
+        "Merge Joiner" => "合并分隔符",
+        "between the Typst of merged snips, \\n for a line break" => "合并截图的 Typst 之间的内容，\\n 表示换行",
+        "Cancel" => "取消",
+        "Draw" => "手写",
+        "Recognize" => "识别",
+        "Undo" => "撤销",
+        "Clear" => "清除",
+        "Reconvert All Snips" => "重新转换所有截图",
+        "reconvert…" => "重新转换…",
+        "Convert the TeX of every snip again with the current rules and converter" => "使用当前的规则和转换器重新转换每个截图的 TeX",
+        "{} of {} snips change" => "{} 个截图有变化（共 {} 个）",
+        "Apply" => "应用",
+        "Equation" => "公式",
+        "Document" => "文档",
+        "Table" => "表格",
+        "Chemistry" => "化学",
+        "What the captures contain" => "截图包含的内容类型",
+        "Capture equation" => "截取公式",
+        "Capture document" => "截取文档",
+        "Capture table" => "截取表格",
+        "Capture chemistry" => "截取化学式",
+        "Smart Crop" => "智能裁剪",
+        "Crop captures to the formula in their middle" => "将截图裁剪为中间的公式",
+        "Pixel ratio" => "像素比",
+        "Screenshot Format" => "截图格式",
+        "quality" => "质量",
+        "Keep the lossless PNG, only upload the JPEG" => "保留无损 PNG，仅上传 JPEG",
+        "Click to zoom" => "点击放大",
+        "Actual size" => "实际大小",
+        "Ctrl+scroll to zoom, drag to pan" => "Ctrl+滚轮缩放，拖动平移",
+        "compare" => "对比",
+        "Show the capture next to the rendered result" => "将截图与渲染结果并排显示",
+        "Snip moved to the trash, press Ctrl+Z to undo" => "截图已移至回收站，按 Ctrl+Z 撤销",
+        "Undo delete" => "撤销删除",
+        "Delete" => "删除",
+        "Trash" => "回收站",
+        "Snips in the trash are removed for good after" => "回收站中的截图将在以下时间后被永久删除：",
+        "days" => "天",
+        "Empty trash" => "清空回收站",
+        "The trash is empty" => "回收站是空的",
+        "Restore" => "恢复",
+        "Delete permanently" => "永久删除",
+        "Move all snips to the trash" => "将所有截图移至回收站",
+        "Exported {} screenshots" => "已导出 {} 张截图",
+        "Failed to export screenshots:" => "导出截图失败：",
+        "Export screenshots…" => "导出截图…",
+        "Mathpix rejected the API key, check it in the settings" => "Mathpix 拒绝了 API 密钥，请在设置中检查",
+        "Too many requests to Mathpix, wait a moment and try again" => "对 Mathpix 的请求过多，请稍后重试",
+        "The Mathpix snip quota is used up" => "Mathpix 识别额度已用完",
+        "The image is too large for Mathpix, capture a smaller region" => "图片对 Mathpix 来说太大，请截取更小的区域",
+        "Mathpix found nothing to recognize in the image" => "Mathpix 未在图片中找到可识别的内容",
+        "Mathpix reported an error:" => "Mathpix 报告了错误：",
+        "Mathpix returned" => "Mathpix 返回",
+        "Recognition failed" => "识别失败",
+        "Mathpix did not finish the snip in time" => "Mathpix 未能及时完成识别",
+        "Result Cache" => "结果缓存",
+        "Reuse the result of an identical image instead of sending it again" => "对相同图片复用之前的结果，而不是再次发送",
+        "Online" => "在线",
+        "Offline" => "离线",
+        "Offline, {} captures will be sent when the network is back" => "离线中，{} 个截图将在网络恢复后发送",
+        "TLS" => "TLS",
+        "Root certificates:" => "根证书：",
+        "system only" => "仅系统证书",
+        "A PEM file with extra certificates to trust" => "包含额外信任证书的 PEM 文件",
+        "Accept invalid certificates" => "接受无效证书",
+        "⚠ Insecure: anyone on the network can read your API key and captures" => "⚠ 不安全：网络中的任何人都能读取你的 API 密钥和截图",
+        "Choose…" => "选择…",
+        "Request Queue" => "请求队列",
+        "requests at once" => "个同时请求",
+        "captures may wait" => "个截图可排队等待",
+        "When full:" => "队列满时：",
+        "drop the oldest" => "丢弃最早的",
+        "drop the new capture" => "丢弃新的截图",
+        "The queue is full, dropped the oldest capture" => "队列已满，已丢弃最早的截图",
+        "The queue is full, dropped the capture" => "队列已满，已丢弃该截图",
+        "Latency" => "延迟",
+        "Upload" => "上传",
+        "OCR" => "OCR",
+        "Conversion" => "转换",
+        "Average end-to-end latency" => "平均端到端延迟",
+        "Number equations" => "为公式编号",
+        "Typst label added after each entry, {n} is the number" => "每条内容后添加的 Typst 标签，{n} 为编号",
+        "next: {}" => "下一个：{}",
+        "reset" => "重置",
+        "History" => "历史",
+        "Keep the last" => "保留最近",
+        "copied texts" => "条复制内容",
+        "Nothing was copied yet" => "还没有复制过内容",
+        "Copy again" => "再次复制",
+        "Frontmost window" => "最前面的窗口",
+        "Leave empty to paste into the frontmost window" => "留空则粘贴到最前面的窗口",
+        "Wait before pasting:" => "粘贴前等待：",
+        "Where results are pasted" => "结果粘贴到哪里",
+        "Quick copy popup" => "快速复制弹窗",
+        "Search snips…" => "搜索截图…",
+        "No matching snips" => "没有匹配的截图",
+        "Output Templates" => "输出模板",
+        "Placeholders: {typst} {tex} {unicode} {title} {date} {image_path}" => "占位符：{typst} {tex} {unicode} {title} {date} {image_path}",
+        "Added to the continuous clipboard" => "已添加到连续剪贴板",
+        "Failed to copy the image:" => "复制图片失败：",
+        "Copy as:" => "复制为：",
+        "continuous" => "连续",
+        "image" => "图片",
+        "Text" => "文本",
+        "Capture text" => "截取文本",
+        "Copy Unicode" => "复制 Unicode",
+        "Plain text math for chat apps and emails" => "用于聊天软件和邮件的纯文本数学公式",
+        "Export Markdown…" => "导出 Markdown…",
+        "Exported to {}" => "已导出到 {}",
+        "Failed to export:" => "导出失败：",
+        "Export .tex…" => "导出 .tex…",
+        "LaTeX Export" => "LaTeX 导出",
+        "The snips go at {body}, one equation environment each" => "截图内容放在 {body} 处，每个一个 equation 环境",
+        "Share…" => "分享…",
+        "Failed to share:" => "分享失败：",
+        "Mail" => "邮件",
+        "Messages" => "信息",
+        "Share sheet" => "系统分享",
+        "Print / Save as PDF…" => "打印 / 另存为 PDF…",
+        "Exported backup" => "已导出备份",
+        "Failed to export backup:" => "导出备份失败：",
+        "Restored backup with {} new snips" => "已恢复备份，新增 {} 个截图",
+        "Failed to restore backup:" => "恢复备份失败：",
+        "Backup" => "备份",
+        "restore…" => "恢复…",
+        "password (optional)" => "密码（可选）",
+        "Backups exported with a password are encrypted with age" => "设置密码后导出的备份会用 age 加密",
+        "🔒 Typst Scan is locked" => "🔒 Typst Scan 已锁定",
+        "passcode" => "密码",
+        "Unlock" => "解锁",
+        "Wrong passcode" => "密码错误",
+        "App Lock" => "应用锁",
+        "lock now" => "立即锁定",
+        "lock when restored" => "恢复窗口时锁定",
+        "Ask for the passcode again after the window was minimized" => "窗口最小化后再次要求输入密码",
+        "Hide the snips behind a passcode on launch" => "启动时用密码隐藏截图",
+        "set" => "设置",
+        "remove" => "移除",
+        "Newest first" => "最新在前",
+        "Oldest first" => "最早在前",
+        "By title" => "按标题",
+        "No snip selected" => "未选择截图",
+        "No rendered image for this snip" => "此截图没有渲染图像",
+        "Preview" => "预览",
+        "Rendered" => "渲染结果",
+        "Layout" => "布局",
+        "Put the panes of the snips view back in place" => "将截图视图的面板恢复原位",
+        "Open in a new window" => "在新窗口中打开",
+        "Frame time" => "帧时间",
+        "Events this frame" => "本帧事件",
+        "Worker queue" => "工作队列",
+        "captures" => "个截图",
+        "uploads" => "个上传",
+        "Image memory" => "图像内存",
+        "images" => "张图像",
+        "Performance Overlay" => "性能浮层",
+        "show" => "显示",
+        "Frame time, pending events, worker queues and memory used by images" => "帧时间、待处理事件、工作队列和图像占用的内存",
+        "GPU (wgpu)" => "GPU (wgpu)",
+        "OpenGL (glow)" => "OpenGL (glow)",
+        "Software" => "软件渲染",
+        "Renderer" => "渲染器",
+        "in use:" => "当前使用:",
+        "Used from the next launch. When it fails to start, the others are tried in turn" => "下次启动时生效。启动失败时会依次尝试其他渲染器",
+        "Automatic Backups" => "自动备份",
+        "keep" => "保留",
+        "restore from…" => "从备份恢复…",
+        "No backups yet" => "还没有备份",
+        "Written to the storage directory before data from an older version is migrated and then regularly" => "在迁移旧版本数据之前以及之后定期写入存储目录",
+        "editor" => "编辑器",
+        "Failed to send to the editor:" => "发送到编辑器失败:",
+        "Send to Editor" => "发送到编辑器",
+        "Send results to the editor instead of the clipboard" => "将结果发送到编辑器而不是剪贴板",
+        "Run by the shell with the Typst on stdin, {file} is a file holding it" => "由 shell 运行，Typst 通过 stdin 传入，{file} 是保存它的文件",
+        "Editor Bridge" => "编辑器桥接",
+        "Accept commands from editor plugins on a local socket" => "通过本地套接字接受编辑器插件的命令",
+        "Plugins can start captures, read the latest result and subscribe to new ones" => "插件可以开始截图、读取最新结果并订阅新结果",
+        "Result File" => "结果文件",
+        "Write the Typst of every result to a file" => "将每个结果的 Typst 写入文件",
+        "Replaced with each result. A FIFO made with mkfifo gets the result once something reads from it" => "每个结果都会替换文件内容。用 mkfifo 创建的 FIFO 会在有程序读取时收到结果",
+        "Export org-mode…" => "导出 org-mode…",
+        "Mathpix text" => "Mathpix 文本",
+        "Styled LaTeX" => "样式化 LaTeX",
+        "Output of Mathpix the Typst is converted from" => "Typst 转换所用的 Mathpix 输出",
+        "Typst converted from" => "Typst 转换来源",
+        "Typst Source" => "Typst 来源",
+        "Converted to Typst in the Equation preset, Mathpix styles only single formulas" => "在公式预设中转换为 Typst，Mathpix 仅为单个公式提供样式化输出",
+        "Extra Formats" => "额外格式",
+        "Also requested from Mathpix and kept with each snip" => "同时向 Mathpix 请求并随每个截图保存",
+        "Failed to fetch the Mathpix folders:" => "获取 Mathpix 文件夹失败：",
+        "No folder" => "无文件夹",
+        "Refresh folders" => "刷新文件夹",
+        "Mathpix folder the captures are uploaded into" => "截图上传到的 Mathpix 文件夹",
+        "All folders" => "所有文件夹",
+        "Mathpix folder" => "Mathpix 文件夹",
+        "Preset Folders" => "预设文件夹",
+        "Default folder" => "默认文件夹",
+        "Mathpix folder for the captures of each preset, the default is the one in the toolbar" => "每个预设截图使用的 Mathpix 文件夹，默认为工具栏中选择的文件夹",
+        "Mathpix did not return a share link" => "Mathpix 未返回分享链接",
+        "Share link copied" => "分享链接已复制",
+        "Failed to get a share link:" => "获取分享链接失败：",
+        "share link" => "分享链接",
+        "Copy a public link to the snip on Mathpix" => "复制该截图在 Mathpix 上的公开链接",
+        "Only {} Mathpix snips left, periodic capture is paused until the quota is reset. Captures started by hand, from a hotkey, a button or the bridge, are still sent." => "Mathpix 仅剩 {} 次识别，定时截图已暂停，直到额度重置。通过快捷键、按钮或桥接手动发起的截图仍会发送。",
+        "Low Quota" => "额度不足",
+        "Pause periodic capture below" => "剩余少于",
+        "snips left, 0 never pauses it" => "次时暂停定时截图，0 表示从不暂停",
+        "Snips this month cost an estimated ${}, more than the budget" => "本月截图的估计费用为 ${}，已超出预算",
+        "Cost" => "费用",
+        "This month" => "本月",
+        "Monthly budget" => "每月预算",
+        "Cost Tracking" => "费用跟踪",
+        "Estimate the cost of each snip" => "估算每次截图的费用",
+        "Overage price" => "超额单价",
+        "Failed to copy:" => "复制失败：",
+        "The capture worker stopped, restart Typst Scan to capture again" => "截图线程已停止，请重启 Typst Scan 后再截图",
+        "The stored TeX keeps them, the other delimiter styles replace them" => "保存的 TeX 保留定界符，其他定界符样式会替换它们",
+        "Only periodic capture is paused, captures started by hand are always sent" => "仅暂停定时截图，手动发起的截图始终会发送",
+        // hotkey actions
+        "Capture as TeX" => "截取为 TeX",
+        "Capture to continuous clipboard" => "截取到连续剪贴板",
+        "Repeat last region" => "重复上次区域",
+        "Capture active window" => "截取活动窗口",
+        "Capture saved window" => "截取已保存的窗口",
+        "Capture scrcpy" => "截取 scrcpy",
+        "OCR clipboard image" => "识别剪贴板图片",
+        "Toggle auto capture" => "切换自动截取",
+        "Copy latest result again" => "再次复制最新结果",
+        "Paste latest result" => "粘贴最新结果",
+        "Show/hide window" => "显示/隐藏窗口",
+        "Show/hide mini window" => "显示/隐藏迷你窗口",
+        _ => return None,
+    })
+}
+
+fn ja(text: &str) -> Option<&'static str> {
+    Some(match text {
+        // menu bar
+        "Snips" => "スニップ",
+        "Continuous Clipboard" => "連続クリップボード",
+        "Replace Rules" => "置換ルール",
+        "Stats" => "統計",
+        "Settings" => "設定",
+        "Mini" => "ミニ",
+        "Show a small always-on-top window with the latest result" => "最新の結果を表示する小さな最前面ウィンドウを表示",
+        "Keep this window above other windows" => "このウィンドウを常に最前面に表示",
+        // snips
+        "Capture" => "キャプチャ",
+        "Region" => "範囲",
+        "Select a region to capture and remember, right click to pick a display" => {
+            "キャプチャして記憶する範囲を選択、右クリックでディスプレイを選択"
+        }
+        "All displays" => "すべてのディスプレイ",
+        "Repeat" => "繰り返し",
+        "Capture the last selected region again" => "最後に選択した範囲を再度キャプチャ",
+        "Clipboard" => "クリップボード",
+        "OCR the image on the clipboard" => "クリップボードの画像を認識",
+        "regenerate" => "再生成",
+        "Copy" => "コピー",
+        "No snips yet" => "まだスニップがありません",
+        "Copied latest result" => "最新の結果をコピーしました",
+        "Capture failed:" => "キャプチャに失敗しました：",
+        "Capture Countdown" => "キャプチャのカウントダウン",
+        "Capturing in {}…" => "{} 秒後にキャプチャ…",
+        "Select Region" => "範囲を選択",
+        // continuous clipboard
+        "Clipboard Mode" => "クリップボードモード",
+        "Continuous" => "連続",
+        "Copy TeX" => "TeX をコピー",
+        "Copy Typst" => "Typst をコピー",
+        "copy all" => "すべてコピー",
+        "take all" => "すべて取り出す",
+        "Auto capture last region" => "最後の範囲を自動キャプチャ",
+        "Capture a region first" => "先に範囲をキャプチャしてください",
+        "every" => "間隔",
+        // replace rules
+        "load rules" => "ルールを読み込む",
+        "register rules" => "ルールを適用",
+        "Registered rules" => "ルールを適用しました",
+        "Failed to parse rules" => "ルールの解析に失敗しました",
+        // settings
+        "Language" => "言語",
+        "System" => "システム",
+        "Theme" => "テーマ",
+        "Default" => "デフォルト",
+        "High Contrast" => "ハイコントラスト",
+        "Custom" => "カスタム",
+        "Accent" => "アクセント",
+        "Panels" => "パネル",
+        "Code background" => "コードの背景",
+        "Code text" => "コードの文字",
+        "Extra Fonts" => "追加フォント",
+        "add font…" => "フォントを追加…",
+        "For scripts the bundled fonts don't cover" => "同梱フォントで表示できない文字用",
+        "Mathpix API Key" => "Mathpix API キー",
+        "Global Hotkeys" => "グローバルホットキー",
+        "register" => "登録",
+        "clear" => "クリア",
+        "Registered hotkey" => "ホットキーを登録しました",
+        "scrcpy Window" => "scrcpy ウィンドウ",
+        "Window Title:" => "ウィンドウタイトル：",
+        "Leave empty to use the first scrcpy window" => "空欄の場合は最初の scrcpy ウィンドウを使用",
+        "detect" => "検出",
+        "No scrcpy window found" => "scrcpy ウィンドウが見つかりません",
+        "Crop:" => "トリミング：",
+        "top " => "上 ",
+        "right " => "右 ",
+        "bottom " => "下 ",
+        "left " => "左 ",
+        "Capture Delay" => "キャプチャ遅延",
+        "Count down before capturing, to open menus or hover over content first" => {
+            "キャプチャ前にカウントダウンし、先にメニューを開いたり内容にホバーしたりできるようにする"
+        }
+        "Default Display" => "デフォルトのディスプレイ",
+        "{} (primary)" => "{}（メイン）",
+        "refresh" => "更新",
+        "Settings File" => "設定ファイル",
+        "export…" => "エクスポート…",
+        "import…" => "インポート…",
+        "include API key" => "API キーを含める",
+        "Exported settings" => "設定をエクスポートしました",
+        "Imported settings" => "設定をインポートしました",
+        "Failed to export settings:" => "設定のエクスポートに失敗しました：",
+        "Failed to import settings:" => "設定のインポートに失敗しました：",
+        "write config.toml" => "config.toml に書き出す",
+        "Changes to config.toml in the storage folder are loaded automatically" => {
+            "保存フォルダ内の config.toml の変更は自動的に読み込まれます"
+        }
+        "Wrote config.toml" => "config.toml に書き出しました",
+        "Failed to load config.toml:" => "config.toml の読み込みに失敗しました：",
+        "Delete All Snips" => "すべてのスニップを削除",
+        "delete!!!" => "削除!!!",
+        "API usage" => "API 使用量",
+        "Bring Forward Window" => "ウィンドウを前面に表示",
+        "Hide When Capturing" => "キャプチャ中は非表示",
+        "Minimize this window while taking a screenshot" => "スクリーンショット中はこのウィンドウを最小化",
+        "Auto Paste" => "自動貼り付け",
+        "Paste into the target window after copying" => "コピー後に対象ウィンドウへ貼り付け",
+        "Capture Window" => "キャプチャするウィンドウ",
+        "Process name:" => "プロセス名：",
+        "Pick running window" => "実行中のウィンドウを選択",
+        "No windows found, try refreshing" => "ウィンドウが見つかりません。更新してください",
+        // setup wizard
+        "Welcome to Typst Scan" => "Typst Scan へようこそ",
+        "Choose the OCR service to use." => "使用する認識サービスを選択してください。",
+        "The only supported service for now" => "現在サポートされている唯一のサービス",
+        "Enter your Mathpix API key." => "Mathpix API キーを入力してください。",
+        "validate" => "検証",
+        "The key works" => "キーは有効です",
+        "Pick the global hotkey that starts a capture." => "キャプチャを開始するグローバルホットキーを選択してください。",
+        "What should be copied after a capture?" => "キャプチャ後に何をコピーしますか？",
+        "Select some math on screen to check that everything works." => "画面上の数式を選択して、動作を確認してください。",
+        "It works:" => "成功しました：",
+        "Waiting for the result…" => "結果を待っています…",
+        "skip setup" => "セットアップをスキップ",
+        "Next" => "次へ",
+        "Done" => "完了",
+        "Typst Scan {} is available" => "Typst Scan {} が利用可能です",
+        "download" => "ダウンロード",
+        "What's new" => "新機能",
+        "Updates" => "アップデート",
+        "Check for new releases on GitHub at startup" => "起動時に GitHub で新しいリリースを確認",
+        // stats
+        "Tags" => "タグ",
+        "comma separated" => "カンマ区切り",
+        "Overview" => "概要",
+        "Average confidence" => "平均信頼度",
+        "Average OCR latency" => "平均認識時間",
+        "Snips per day" => "1 日あたりのスニップ",
+        "No dated snips yet" => "日付のあるスニップはまだありません",
+        "Most used tags" => "よく使うタグ",
+        "No tags yet" => "まだタグがありません",
+        // snip details
+        "Details" => "詳細",
+        "Captured" => "キャプチャ日時",
+        "Confidence" => "信頼度",
+        "OCR time" => "認識時間",
+        "Mathpix id" => "Mathpix ID",
+        "Processed at" => "処理日時",
+        "Upload time" => "アップロード時間",
+        "Auto rotation" => "自動回転",
+        "Font size" => "フォントサイズ",
+        "OCR version" => "OCR バージョン",
+        "No details were saved for this snip" => "このスニップには詳細が保存されていません",
+        "Original image" => "元の画像",
+        "Rendered image" => "レンダリング画像",
+        "open" => "開く",
+        "Copied" => "コピーしました",
+        "Raw" => "そのまま",
+        "TeX Delimiters" => "TeX の区切り記号",
+        "Wrapping of single formulas copied in Copy TeX mode" => "TeX コピーモードで単一の数式をコピーするときの囲み方",
+        "Output Cleanup" => "出力の整形",
+        "Trim leading and trailing whitespace" => "前後の空白を削除",
+        "Collapse repeated blank lines" => "連続する空行をまとめる",
+        "Strip the math delimiters from TeX copied as raw" => "TeX をそのままコピーするとき数式の区切り記号を除く",
+        "TeX Normalization" => "TeX の正規化",
+        "Unbalanced braces" => "中括弧の対応が取れていません",
+        "Environment {} is not closed" => "環境 {} が閉じられていません",
+        "\\end of {} without a \\begin" => "{} の \\end に対応する \\begin がありません",
+        "Unbalanced \\left and \\right" => "\\left と \\right の対応が取れていません",
+        "Conversion to Typst failed" => "Typst への変換に失敗しました",
+        "Typst Formatting" => "Typst の整形",
+        "Format the Typst output" => "Typst の出力を整形する",
+        "characters per line" => "1 行の文字数",
+        "split" => "分割",
+        "Make a snip of every equation" => "数式ごとにスニップを作成",
+        "Merge {} snips" => "{} 個のスニップを結合",
+        "Merge Joiner" => "結合の区切り",
+        "between the Typst of merged snips, \\n for a line break" => "結合したスニップの Typst の間に入れる文字列、\\n は改行",
+        "Cancel" => "キャンセル",
+        "Draw" => "手書き",
+        "Recognize" => "認識",
+        "Undo" => "元に戻す",
+        "Clear" => "消去",
+        "Reconvert All Snips" => "すべてのスニップを再変換",
+        "reconvert…" => "再変換…",
+        "Convert the TeX of every snip again with the current rules and converter" => "現在のルールと変換器で各スニップの TeX を再変換します",
+        "{} of {} snips change" => "{} 個のスニップが変更されます（全 {} 個）",
+        "Apply" => "適用",
+        "Equation" => "数式",
+        "Document" => "文書",
+        "Table" => "表",
+        "Chemistry" => "化学",
+        "What the captures contain" => "キャプチャの内容の種類",
+        "Capture equation" => "数式をキャプチャ",
+        "Capture document" => "文書をキャプチャ",
+        "Capture table" => "表をキャプチャ",
+        "Capture chemistry" => "化学式をキャプチャ",
+        "Smart Crop" => "スマート切り抜き",
+        "Crop captures to the formula in their middle" => "キャプチャを中央の数式に切り抜く",
+        "Pixel ratio" => "ピクセル比",
+        "Screenshot Format" => "スクリーンショットの形式",
+        "quality" => "品質",
+        "Keep the lossless PNG, only upload the JPEG" => "ロスレスの PNG を残し、JPEG だけをアップロード",
+        "Click to zoom" => "クリックで拡大",
+        "Actual size" => "実際のサイズ",
+        "Ctrl+scroll to zoom, drag to pan" => "Ctrl+スクロールで拡大縮小、ドラッグで移動",
+        "compare" => "比較",
+        "Show the capture next to the rendered result" => "キャプチャとレンダリング結果を並べて表示",
+        "Snip moved to the trash, press Ctrl+Z to undo" => "スニップをゴミ箱に移動しました。Ctrl+Z で元に戻せます",
+        "Undo delete" => "削除を取り消す",
+        "Delete" => "削除",
+        "Trash" => "ゴミ箱",
+        "Snips in the trash are removed for good after" => "ゴミ箱のスニップは次の期間が過ぎると完全に削除されます：",
+        "days" => "日",
+        "Empty trash" => "ゴミ箱を空にする",
+        "The trash is empty" => "ゴミ箱は空です",
+        "Restore" => "復元",
+        "Delete permanently" => "完全に削除",
+        "Move all snips to the trash" => "すべてのスニップをゴミ箱に移動",
+        "Exported {} screenshots" => "{} 枚のスクリーンショットをエクスポートしました",
+        "Failed to export screenshots:" => "スクリーンショットのエクスポートに失敗しました：",
+        "Export screenshots…" => "スクリーンショットをエクスポート…",
+        "Mathpix rejected the API key, check it in the settings" => "Mathpix が API キーを拒否しました。設定を確認してください",
+        "Too many requests to Mathpix, wait a moment and try again" => "Mathpix へのリクエストが多すぎます。しばらく待ってから再試行してください",
+        "The Mathpix snip quota is used up" => "Mathpix のスニップ枠を使い切りました",
+        "The image is too large for Mathpix, capture a smaller region" => "画像が Mathpix には大きすぎます。より小さい領域をキャプチャしてください",
+        "Mathpix found nothing to recognize in the image" => "Mathpix は画像内に認識できる内容を見つけられませんでした",
+        "Mathpix reported an error:" => "Mathpix がエラーを報告しました：",
+        "Mathpix returned" => "Mathpix の応答",
+        "Recognition failed" => "認識に失敗しました",
+        "Mathpix did not finish the snip in time" => "Mathpix が時間内にスニップを完了しませんでした",
+        "Result Cache" => "結果キャッシュ",
+        "Reuse the result of an identical image instead of sending it again" => "同じ画像には再送信せず以前の結果を再利用する",
+        "Online" => "オンライン",
+        "Offline" => "オフライン",
+        "Offline, {} captures will be sent when the network is back" => "オフラインです。{} 件のキャプチャはネットワーク復帰後に送信されます",
+        "TLS" => "TLS",
+        "Root certificates:" => "ルート証明書：",
+        "system only" => "システムのみ",
+        "A PEM file with extra certificates to trust" => "追加で信頼する証明書を含む PEM ファイル",
+        "Accept invalid certificates" => "無効な証明書を受け入れる",
+        "⚠ Insecure: anyone on the network can read your API key and captures" => "⚠ 安全ではありません：ネットワーク上の誰でも API キーとキャプチャを読み取れます",
+        "Choose…" => "選択…",
+        "Request Queue" => "リクエストキュー",
+        "requests at once" => "件の同時リクエスト",
+        "captures may wait" => "件のキャプチャが待機可能",
+        "When full:" => "満杯のとき：",
+        "drop the oldest" => "最も古いものを破棄",
+        "drop the new capture" => "新しいキャプチャを破棄",
+        "The queue is full, dropped the oldest capture" => "キューが満杯のため、最も古いキャプチャを破棄しました",
+        "The queue is full, dropped the capture" => "キューが満杯のため、キャプチャを破棄しました",
+        "Latency" => "レイテンシ",
+        "Upload" => "アップロード",
+        "OCR" => "OCR",
+        "Conversion" => "変換",
+        "Average end-to-end latency" => "平均エンドツーエンドレイテンシ",
+        "Number equations" => "数式に番号を付ける",
+        "Typst label added after each entry, {n} is the number" => "各項目の後に付ける Typst ラベル、{n} は番号",
+        "next: {}" => "次：{}",
+        "reset" => "リセット",
+        "History" => "履歴",
+        "Keep the last" => "直近",
+        "copied texts" => "件のコピーを保持",
+        "Nothing was copied yet" => "まだ何もコピーされていません",
+        "Copy again" => "もう一度コピー",
+        "Frontmost window" => "最前面のウィンドウ",
+        "Leave empty to paste into the frontmost window" => "空欄の場合は最前面のウィンドウに貼り付けます",
+        "Wait before pasting:" => "貼り付け前の待機：",
+        "Where results are pasted" => "結果の貼り付け先",
+        "Quick copy popup" => "クイックコピーポップアップ",
+        "Search snips…" => "スニップを検索…",
+        "No matching snips" => "一致するスニップはありません",
+        "Output Templates" => "出力テンプレート",
+        "Placeholders: {typst} {tex} {unicode} {title} {date} {image_path}" => "プレースホルダー：{typst} {tex} {unicode} {title} {date} {image_path}",
+        "Added to the continuous clipboard" => "連続クリップボードに追加しました",
+        "Failed to copy the image:" => "画像のコピーに失敗しました：",
+        "Copy as:" => "形式を指定してコピー：",
+        "continuous" => "連続",
+        "image" => "画像",
+        "Text" => "テキスト",
+        "Capture text" => "テキストをキャプチャ",
+        "Copy Unicode" => "Unicode をコピー",
+        "Plain text math for chat apps and emails" => "チャットアプリやメール向けのプレーンテキスト数式",
+        "Export Markdown…" => "Markdown をエクスポート…",
+        "Exported to {}" => "{} にエクスポートしました",
+        "Failed to export:" => "エクスポートに失敗しました：",
+        "Export .tex…" => ".tex をエクスポート…",
+        "LaTeX Export" => "LaTeX エクスポート",
+        "The snips go at {body}, one equation environment each" => "スニップは {body} の位置に、それぞれ equation 環境として入ります",
+        "Share…" => "共有…",
+        "Failed to share:" => "共有に失敗しました：",
+        "Mail" => "メール",
+        "Messages" => "メッセージ",
+        "Share sheet" => "共有シート",
+        "Print / Save as PDF…" => "印刷 / PDF として保存…",
+        "Exported backup" => "バックアップをエクスポートしました",
+        "Failed to export backup:" => "バックアップのエクスポートに失敗しました：",
+        "Restored backup with {} new snips" => "バックアップを復元しました（新しいスニップ {} 件）",
+        "Failed to restore backup:" => "バックアップの復元に失敗しました：",
+        "Backup" => "バックアップ",
+        "restore…" => "復元…",
+        "password (optional)" => "パスワード（任意）",
+        "Backups exported with a password are encrypted with age" => "パスワード付きでエクスポートしたバックアップは age で暗号化されます",
+        "🔒 Typst Scan is locked" => "🔒 Typst Scan はロックされています",
+        "passcode" => "パスコード",
+        "Unlock" => "ロック解除",
+        "Wrong passcode" => "パスコードが違います",
+        "App Lock" => "アプリロック",
+        "lock now" => "今すぐロック",
+        "lock when restored" => "復元時にロック",
+        "Ask for the passcode again after the window was minimized" => "ウィンドウを最小化した後に再度パスコードを求めます",
+        "Hide the snips behind a passcode on launch" => "起動時にパスコードでスニップを隠します",
+        "set" => "設定",
+        "remove" => "削除",
+        "Newest first" => "新しい順",
+        "Oldest first" => "古い順",
+        "By title" => "タイトル順",
+        "No snip selected" => "スニップが選択されていません",
+        "No rendered image for this snip" => "このスニップにはレンダリング画像がありません",
+        "Preview" => "プレビュー",
+        "Rendered" => "レンダリング",
+        "Layout" => "レイアウト",
+        "Put the panes of the snips view back in place" => "スニップ画面のパネルを元の配置に戻します",
+        "Open in a new window" => "新しいウィンドウで開く",
+        "Frame time" => "フレーム時間",
+        "Events this frame" => "このフレームのイベント",
+        "Worker queue" => "ワーカーキュー",
+        "captures" => "件のキャプチャ",
+        "uploads" => "件のアップロード",
+        "Image memory" => "画像メモリ",
+        "images" => "枚の画像",
+        "Performance Overlay" => "パフォーマンス表示",
+        "show" => "表示",
+        "Frame time, pending events, worker queues and memory used by images" => "フレーム時間、保留中のイベント、ワーカーキュー、画像が使うメモリ",
+        "GPU (wgpu)" => "GPU (wgpu)",
+        "OpenGL (glow)" => "OpenGL (glow)",
+        "Software" => "ソフトウェア",
+        "Renderer" => "レンダラー",
+        "in use:" => "使用中:",
+        "Used from the next launch. When it fails to start, the others are tried in turn" => "次回の起動から使われます。起動に失敗した場合は他のレンダラーを順に試します",
+        "Automatic Backups" => "自動バックアップ",
+        "keep" => "保持数",
+        "restore from…" => "バックアップから復元…",
+        "No backups yet" => "まだバックアップがありません",
+        "Written to the storage directory before data from an older version is migrated and then regularly" => "旧バージョンのデータを移行する前と、その後定期的に保存フォルダーに書き込まれます",
+        "editor" => "エディター",
+        "Failed to send to the editor:" => "エディターへの送信に失敗しました:",
+        "Send to Editor" => "エディターに送信",
+        "Send results to the editor instead of the clipboard" => "結果をクリップボードではなくエディターに送信",
+        "Run by the shell with the Typst on stdin, {file} is a file holding it" => "シェルで実行され、Typst は標準入力に渡されます。{file} はそれを含むファイルです",
+        "Editor Bridge" => "エディター連携",
+        "Accept commands from editor plugins on a local socket" => "ローカルソケットでエディタープラグインからのコマンドを受け付ける",
+        "Plugins can start captures, read the latest result and subscribe to new ones" => "プラグインはキャプチャの開始、最新の結果の取得、新しい結果の購読ができます",
+        "Result File" => "結果ファイル",
+        "Write the Typst of every result to a file" => "各結果の Typst をファイルに書き込む",
+        "Replaced with each result. A FIFO made with mkfifo gets the result once something reads from it" => "結果ごとに置き換えられます。mkfifo で作った FIFO には読み取られた時点で結果が書き込まれます",
+        "Export org-mode…" => "org-mode をエクスポート…",
+        "Mathpix text" => "Mathpix テキスト",
+        "Styled LaTeX" => "スタイル付き LaTeX",
+        "Output of Mathpix the Typst is converted from" => "Typst の変換元となる Mathpix の出力",
+        "Typst converted from" => "Typst の変換元",
+        "Typst Source" => "Typst の変換元",
+        "Converted to Typst in the Equation preset, Mathpix styles only single formulas" => "数式プリセットで Typst に変換します。Mathpix がスタイル付きで返すのは単一の数式のみです",
+        "Extra Formats" => "追加の形式",
+        "Also requested from Mathpix and kept with each snip" => "Mathpix にも要求し、各スニップと一緒に保存します",
+        "Failed to fetch the Mathpix folders:" => "Mathpix フォルダーを取得できませんでした：",
+        "No folder" => "フォルダーなし",
+        "Refresh folders" => "フォルダーを更新",
+        "Mathpix folder the captures are uploaded into" => "キャプチャのアップロード先の Mathpix フォルダー",
+        "All folders" => "すべてのフォルダー",
+        "Mathpix folder" => "Mathpix フォルダー",
+        "Preset Folders" => "プリセットのフォルダー",
+        "Default folder" => "既定のフォルダー",
+        "Mathpix folder for the captures of each preset, the default is the one in the toolbar" => "プリセットごとのキャプチャの Mathpix フォルダー。既定はツールバーで選んだフォルダーです",
+        "Mathpix did not return a share link" => "Mathpix が共有リンクを返しませんでした",
+        "Share link copied" => "共有リンクをコピーしました",
+        "Failed to get a share link:" => "共有リンクを取得できませんでした：",
+        "share link" => "共有リンク",
+        "Copy a public link to the snip on Mathpix" => "Mathpix 上のスニップの公開リンクをコピー",
+        "Only {} Mathpix snips left, periodic capture is paused until the quota is reset. Captures started by hand, from a hotkey, a button or the bridge, are still sent." => "Mathpix のスニップが残り {} 回のため、クォータがリセットされるまで定期キャプチャを一時停止しています。ホットキー、ボタン、ブリッジから手動で始めたキャプチャは引き続き送信されます。",
+        "Low Quota" => "クォータ不足",
+        "Pause periodic capture below" => "残りが次を下回ると定期キャプチャを一時停止：",
+        "snips left, 0 never pauses it" => "回（0 で一時停止しない）",
+        "Snips this month cost an estimated ${}, more than the budget" => "今月のスニップの推定費用は ${} で、予算を超えています",
+        "Cost" => "費用",
+        "This month" => "今月",
+        "Monthly budget" => "月間予算",
+        "Cost Tracking" => "費用の記録",
+        "Estimate the cost of each snip" => "各スニップの費用を見積もる",
+        "Overage price" => "超過分の単価",
+        "Failed to copy:" => "コピーに失敗しました：",
+        "The capture worker stopped, restart Typst Scan to capture again" => "キャプチャ処理が停止しました。再度キャプチャするには Typst Scan を再起動してください",
+        "The stored TeX keeps them, the other delimiter styles replace them" => "保存される TeX には残り、ほかの区切り記号スタイルでは置き換えられます",
+        "Only periodic capture is paused, captures started by hand are always sent" => "一時停止するのは定期キャプチャだけで、手動で始めたキャプチャは常に送信されます",
+        // hotkey actions
+        "Capture as TeX" => "TeX としてキャプチャ",
+        "Capture to continuous clipboard" => "連続クリップボードへキャプチャ",
+        "Repeat last region" => "最後の範囲を繰り返す",
+        "Capture active window" => "アクティブウィンドウをキャプチャ",
+        "Capture saved window" => "保存したウィンドウをキャプチャ",
+        "Capture scrcpy" => "scrcpy をキャプチャ",
+        "OCR clipboard image" => "クリップボード画像を認識",
+        "Toggle auto capture" => "自動キャプチャの切り替え",
+        "Copy latest result again" => "最新の結果を再コピー",
+        "Paste latest result" => "最新の結果を貼り付け",
+        "Show/hide window" => "ウィンドウの表示/非表示",
+        "Show/hide mini window" => "ミニウィンドウの表示/非表示",
+        _ => return None,
+    })
+}
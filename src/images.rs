@@ -0,0 +1,188 @@
+use crate::capture;
+use eframe::egui;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+/// How many images may stay decoded in the image loaders. The least recently shown ones beyond
+/// that are forgotten and loaded again when they come back into view.
+const MAX_LOADED_IMAGES: usize = 64;
+
+/// A screenshot scaled down and saved next to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scaled {
+    /// Shown instead of the full screenshot in the detail view.
+    Preview,
+    /// For the list and the quick copy palette, made for every snip in the background.
+    Thumbnail,
+}
+
+impl Scaled {
+    /// Longest side in pixels.
+    pub fn size(self) -> u32 {
+        match self {
+            Scaled::Preview => 600,
+            Scaled::Thumbnail => 96,
+        }
+    }
+
+    /// Added before the extension of the screenshot.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Scaled::Preview => "preview",
+            Scaled::Thumbnail => "thumb",
+        }
+    }
+
+    pub const ALL: [Scaled; 2] = [Scaled::Preview, Scaled::Thumbnail];
+}
+
+type Preview = (String, f32);
+
+enum Request {
+    /// Needed on screen now.
+    Show(String, Scaled),
+    /// Thumbnails to make while nothing is needed on screen.
+    Background(Vec<String>),
+}
+
+/// Keeps the memory of the image loaders bounded with long histories: screenshots are shown as
+/// downscaled previews where they are small anyway, and images not shown for a while are evicted.
+pub struct ImageCache {
+    frame: u64,
+    /// Frame in which each image URI was last shown.
+    last_shown: HashMap<String, u64>,
+    /// Scaled URI of each screenshot URI and how much smaller it is, the screenshot itself when
+    /// it is small enough.
+    scaled: HashMap<(String, Scaled), Preview>,
+    /// Screenshots whose scaled version is being made.
+    pending: HashSet<(String, Scaled)>,
+    /// Screenshots already sent for thumbnails in the background.
+    queued_thumbnails: HashSet<String>,
+    requests: Sender<Request>,
+    finished: Receiver<((String, Scaled), Preview)>,
+}
+
+impl ImageCache {
+    /// Scaled images are made on a thread of their own, so scrolling never waits for a screenshot
+    /// to be decoded. Those needed on screen go first, thumbnails for the rest when it is idle.
+    pub fn new(ctx: egui::Context) -> Self {
+        let (requests, request_receiver) = mpsc::channel();
+        let (finished_sender, finished) = mpsc::channel();
+        thread::spawn(move || {
+            let mut background = VecDeque::new();
+            loop {
+                let request = match request_receiver.try_recv() {
+                    Ok(request) => request,
+                    Err(TryRecvError::Empty) => match background.pop_front() {
+                        Some(image) => Request::Show(image, Scaled::Thumbnail),
+                        None => match request_receiver.recv() {
+                            Ok(request) => request,
+                            Err(_) => break,
+                        },
+                    },
+                    Err(TryRecvError::Disconnected) => break,
+                };
+                let (image, scaled) = match request {
+                    Request::Show(image, scaled) => (image, scaled),
+                    Request::Background(images) => {
+                        background.extend(images);
+                        continue;
+                    }
+                };
+                let path = capture::resolve_image_path(&image);
+                let preview = match capture::create_scaled(&path, scaled.suffix(), scaled.size()) {
+                    Some((preview, factor)) => (format!("file://{}", preview.display()), factor),
+                    None => (capture::image_uri(&image), 1.0),
+                };
+                if finished_sender.send(((image, scaled), preview)).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self {
+            frame: 0,
+            last_shown: HashMap::new(),
+            scaled: HashMap::new(),
+            pending: HashSet::new(),
+            queued_thumbnails: HashSet::new(),
+            requests,
+            finished,
+        }
+    }
+
+    /// The URI to show a stored screenshot with at most `Scaled::Preview` pixels and the factor it was
+    /// scaled down by. `None` while the preview is still being made, show a placeholder then.
+    pub fn preview(&mut self, image: &str) -> Option<Preview> {
+        self.get(image, Scaled::Preview)
+    }
+
+    /// Like `preview`, for the small thumbnail.
+    pub fn thumbnail(&mut self, image: &str) -> Option<Preview> {
+        self.get(image, Scaled::Thumbnail)
+    }
+
+    fn get(&mut self, image: &str, scaled: Scaled) -> Option<Preview> {
+        if image.is_empty() {
+            return None;
+        }
+        while let Ok((key, preview)) = self.finished.try_recv() {
+            self.pending.remove(&key);
+            self.scaled.insert(key, preview);
+        }
+        let key = (image.to_string(), scaled);
+        let Some(preview) = self.scaled.get(&key).cloned() else {
+            if self.pending.insert(key) && self.requests.send(Request::Show(image.to_string(), scaled)).is_err() {
+                eprintln!("The image thread has stopped");
+            }
+            return None;
+        };
+        self.shown(&preview.0);
+        Some(preview)
+    }
+
+    /// Make thumbnails for screenshots that have none yet, in the background.
+    pub fn generate_thumbnails<'a>(&mut self, images: impl Iterator<Item = &'a str>) {
+        let new: Vec<String> = images
+            .filter(|image| !image.is_empty() && !self.queued_thumbnails.contains(*image))
+            .map(str::to_string)
+            .collect();
+        if new.is_empty() {
+            return;
+        }
+        self.queued_thumbnails.extend(new.iter().cloned());
+        if self.requests.send(Request::Background(new)).is_err() {
+            eprintln!("The image thread has stopped");
+        }
+    }
+
+    /// Images currently counted against `MAX_LOADED_IMAGES`.
+    pub fn loaded(&self) -> usize {
+        self.last_shown.len()
+    }
+
+    /// Mark an image as shown in this frame.
+    pub fn shown(&mut self, uri: &str) {
+        if !uri.is_empty() {
+            self.last_shown.insert(uri.to_string(), self.frame);
+        }
+    }
+
+    /// Evict the images shown longest ago once there are too many, keeping those of this frame.
+    pub fn end_frame(&mut self, ctx: &egui::Context) {
+        if self.last_shown.len() > MAX_LOADED_IMAGES {
+            let mut shown: Vec<(String, u64)> = self.last_shown.drain().collect();
+            shown.sort_by_key(|(_, frame)| std::cmp::Reverse(*frame));
+            for (uri, frame) in shown.split_off(MAX_LOADED_IMAGES) {
+                if frame == self.frame {
+                    self.last_shown.insert(uri, frame);
+                } else {
+                    ctx.forget_image(&uri);
+                }
+            }
+            self.last_shown.extend(shown);
+        }
+        self.frame += 1;
+    }
+}
@@ -0,0 +1,78 @@
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Cost of the scrypt hash, slow enough that short passcodes cannot be guessed from the app data.
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// Passcode asked for on launch, and optionally when the window is restored, before any snip is
+/// shown. Only a salted scrypt hash of the passcode is stored.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct AppLock {
+    /// Empty when the lock is off.
+    passcode_hash: String,
+    salt: String,
+    /// `scrypt`, or empty for the SHA-256 hash of older versions, replaced on the next unlock.
+    kdf: String,
+    /// Lock again when the window is restored after being minimized.
+    pub on_restore: bool,
+}
+
+impl AppLock {
+    pub fn is_enabled(&self) -> bool {
+        !self.passcode_hash.is_empty()
+    }
+
+    pub fn set_passcode(&mut self, passcode: &str) {
+        self.salt = Uuid::new_v4().simple().to_string();
+        self.passcode_hash = scrypt_hash(&self.salt, passcode);
+        self.kdf = "scrypt".to_string();
+    }
+
+    pub fn disable(&mut self) {
+        self.passcode_hash.clear();
+        self.salt.clear();
+        self.kdf.clear();
+    }
+
+    /// Check the passcode, moving a hash of an older version to scrypt once it matched.
+    pub fn unlocks(&mut self, passcode: &str) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+        let matches = if self.kdf == "scrypt" {
+            scrypt_hash(&self.salt, passcode) == self.passcode_hash
+        } else {
+            sha256_hash(&self.salt, passcode) == self.passcode_hash
+        };
+        if matches && self.kdf != "scrypt" {
+            self.set_passcode(passcode);
+        }
+        matches
+    }
+}
+
+fn scrypt_hash(salt: &str, passcode: &str) -> String {
+    let mut output = [0u8; 32];
+    let hashed = scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P, output.len())
+        .map_err(|e| e.to_string())
+        .and_then(|params| {
+            scrypt::scrypt(passcode.as_bytes(), salt.as_bytes(), &params, &mut output).map_err(|e| e.to_string())
+        });
+    if let Err(e) = hashed {
+        // an empty hash never matches, so a failure keeps the snips locked
+        eprintln!("Failed to hash the passcode: {}", e);
+        return String::new();
+    }
+    hex(&output)
+}
+
+fn sha256_hash(salt: &str, passcode: &str) -> String {
+    hex(&Sha256::new().chain_update(salt).chain_update(passcode).finalize())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -1,28 +1,143 @@
 use eframe::{run_native, App};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
 
 mod app;
+mod backup;
+mod bridge;
 mod worker;
+mod platform;
+mod capture;
+mod convert;
+mod cost;
+mod export;
+mod folders;
+mod graphics;
+mod hotkeys;
+mod i18n;
+mod images;
+mod lock;
+mod perf;
+mod result_file;
+mod search;
+mod settings;
+mod store;
+mod theme;
+mod update;
+#[cfg(target_os = "linux")]
+mod portal;
 mod tests;
 
 use app::TypstScan;
 use crate::app::TypstScanData;
+use crate::graphics::GraphicsBackend;
+
+const USAGE: &str = "Usage: TypstScan [--config <path>] [--profile <name>] [--renderer wgpu|glow|software] [--minimized] [--capture-on-start] [--verbose]";
+
+/// Flags given on the command line.
+#[derive(Debug, Default, Clone)]
+pub struct StartupOptions {
+    /// Config file to watch instead of `config.toml` in the storage directory.
+    pub config: Option<PathBuf>,
+    /// Keep snips and settings apart from the default profile.
+    pub profile: Option<String>,
+    /// Overrides the renderer from the settings. Set to the one in use once the window is up.
+    pub renderer: Option<GraphicsBackend>,
+    pub minimized: bool,
+    pub capture_on_start: bool,
+    pub verbose: bool,
+}
+
+impl StartupOptions {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = StartupOptions::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => options.config = Some(args.next().ok_or("--config needs a path")?.into()),
+                "--profile" => options.profile = Some(args.next().ok_or("--profile needs a name")?),
+                "--renderer" => {
+                    let name = args.next().ok_or("--renderer needs wgpu, glow or software")?;
+                    options.renderer = Some(GraphicsBackend::parse(&name).ok_or(format!("Unknown renderer: {}", name))?);
+                }
+                "--minimized" => options.minimized = true,
+                "--capture-on-start" => options.capture_on_start = true,
+                "--verbose" => options.verbose = true,
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Whether `--verbose` was given, for extra diagnostics on the console.
+pub fn verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match StartupOptions::parse(args.into_iter()) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    VERBOSE.store(options.verbose, Ordering::Relaxed);
+    if verbose() {
+        println!("Startup options: {:?}", options);
+    }
+
+    let renderer = options.renderer.or_else(|| graphics::load(options.profile.as_deref())).unwrap_or_default();
+    let started = Arc::new(AtomicBool::new(false));
+    let mut result = run(renderer, options.clone(), started.clone());
+    // the window never came up, try the other renderers before giving up
+    for fallback in renderer.fallbacks() {
+        match &result {
+            Err(e) if !started.load(Ordering::Relaxed) => {
+                eprintln!("Failed to start with the {} renderer: {}, trying {}", renderer.name(), e, fallback.name());
+                result = run(fallback, options.clone(), started.clone());
+            }
+            _ => break,
+        }
+    }
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(renderer: GraphicsBackend, mut options: StartupOptions, started: Arc<AtomicBool>) -> eframe::Result {
     // Create a global API key that is shared between app and worker
     let global_app_data: Arc<Mutex<TypstScanData>> = Arc::new(Mutex::new(TypstScanData::default()));
 
     // Create channels for sending tasks to the worker thread and receiving results
     let (task_sender, task_receiver) = mpsc::channel::<worker::SnipTask>();
-    let (result_sender, result_receiver) = mpsc::channel::<worker::TaskResult>();
-
-    worker::start_worker(task_receiver, result_sender, global_app_data.clone()); // need to get api key from app storage here
+    let (event_sender, event_receiver) = mpsc::channel::<worker::WorkerEvent>();
 
-    let native_options = eframe::NativeOptions::default();
+    let mut native_options = eframe::NativeOptions::default();
+    renderer.apply(&mut native_options);
+    options.renderer = Some(renderer);
+    if let Some(profile) = &options.profile {
+        // every profile keeps its own persisted app data
+        native_options.persistence_path =
+            capture::get_storage_dir().map(|dir| dir.join("profiles").join(profile).join("app.ron"));
+    }
     run_native(
         "Typst Scan",
         native_options,
-        Box::new(|cc| Ok(Box::new(TypstScan::new(cc, task_sender, result_receiver, global_app_data)))),
+        Box::new(move |cc| {
+            started.store(true, Ordering::Relaxed);
+            // the worker needs the egui context to wake up the UI when it has something to show
+            worker::start_worker(task_receiver, event_sender, global_app_data.clone(), cc.egui_ctx.clone());
+            Ok(Box::new(TypstScan::new(cc, task_sender, event_receiver, global_app_data, options)))
+        }),
     )
-    .unwrap();
 }
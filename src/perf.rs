@@ -0,0 +1,86 @@
+use crate::i18n::tr;
+use eframe::egui;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Frames the average frame time is taken over.
+const FRAME_HISTORY: usize = 120;
+
+/// Numbers for the debug overlay, to narrow down slowdowns with big histories.
+#[derive(Default)]
+pub struct PerfStats {
+    frame_started: Option<Instant>,
+    /// How long the last frames took to build, newest last.
+    frame_times: VecDeque<Duration>,
+    /// Worker events handled in the last frame.
+    pub events: usize,
+}
+
+impl PerfStats {
+    pub fn begin_frame(&mut self) {
+        self.frame_started = Some(Instant::now());
+    }
+
+    pub fn end_frame(&mut self) {
+        if let Some(started) = self.frame_started.take() {
+            if self.frame_times.len() == FRAME_HISTORY {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(started.elapsed());
+        }
+    }
+
+    /// Frame time, event backlog, worker queues and image memory in a corner of the window.
+    /// `waiting_tasks` are captures not yet started, `pending_uploads` those queued or being sent.
+    pub fn show(&self, ctx: &egui::Context, waiting_tasks: usize, pending_uploads: usize, shown_images: usize) {
+        let last = self.frame_times.back().copied().unwrap_or_default();
+        let average = self.frame_times.iter().sum::<Duration>() / self.frame_times.len().max(1) as u32;
+        let worst = self.frame_times.iter().max().copied().unwrap_or_default();
+        egui::Area::new(egui::Id::new("perf_overlay"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-8.0, -8.0])
+            .interactable(false)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+                    egui::Grid::new("perf_overlay_grid").num_columns(2).show(ui, |ui| {
+                        ui.label(tr("Frame time"));
+                        ui.label(format!(
+                            "{:.1} ms (avg {:.1}, max {:.1})",
+                            last.as_secs_f64() * 1000.0,
+                            average.as_secs_f64() * 1000.0,
+                            worst.as_secs_f64() * 1000.0
+                        ));
+                        ui.end_row();
+                        ui.label(tr("Events this frame"));
+                        ui.label(self.events.to_string());
+                        ui.end_row();
+                        ui.label(tr("Worker queue"));
+                        ui.label(format!("{} {}, {} {}", waiting_tasks, tr("captures"), pending_uploads, tr("uploads")));
+                        ui.end_row();
+                        ui.label(tr("Image memory"));
+                        ui.label(format!("{} ({} {})", format_bytes(loaded_image_bytes(ctx)), shown_images, tr("images")));
+                        ui.end_row();
+                    });
+                });
+            });
+    }
+}
+
+/// Bytes held by the image loaders: the file contents, decoded images and textures.
+fn loaded_image_bytes(ctx: &egui::Context) -> usize {
+    let loaders = ctx.loaders();
+    let bytes: usize = loaders.bytes.lock().iter().map(|loader| loader.byte_size()).sum();
+    let images: usize = loaders.image.lock().iter().map(|loader| loader.byte_size()).sum();
+    let textures: usize = loaders.texture.lock().iter().map(|loader| loader.byte_size()).sum();
+    bytes + images + textures
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    if bytes as f64 >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB)
+    } else {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    }
+}
@@ -0,0 +1,585 @@
+use crate::capture::CaptureRegion;
+use crate::i18n::tr;
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use std::io::Write;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(target_os = "macos")]
+use std::process::Command;
+
+/// Raise the configured target window so it is frontmost.
+pub fn bring_forward(process_name: &str, window_title: &str) {
+    #[cfg(target_os = "macos")]
+    {
+        // the names are passed as arguments, quotes in a window title cannot change the script
+        let script = r#"
+            on run argv
+                set processName to item 1 of argv
+                set windowTitle to item 2 of argv
+                tell application "System Events"
+                    tell process processName
+                        set frontmost to true
+                        if windowTitle is not "" then
+                            perform action "AXRaise" of (first window whose name is windowTitle)
+                        end if
+                    end tell
+                end tell
+            end run
+        "#;
+
+        match Command::new("osascript").arg("-e").arg(script).arg(process_name).arg(window_title).output() {
+            Ok(out) => println!("{:?}", out),
+            Err(e) => eprintln!("Failed to bring {process_name} forward: {:?}", e),
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        win32::bring_forward(process_name, window_title);
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::bring_forward(process_name, window_title);
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        eprintln!("Bringing {process_name} ({window_title}) forward is not supported on this platform");
+    }
+}
+
+/// Send the platform paste shortcut (Cmd+V on macOS, Ctrl+V elsewhere) to the frontmost application.
+pub fn simulate_paste() {
+    let mut enigo = match Enigo::new(&Settings::default()) {
+        Ok(enigo) => enigo,
+        Err(e) => {
+            eprintln!("Failed to create input simulator: {:?}", e);
+            return;
+        }
+    };
+
+    // give the target window a moment to become key before sending the shortcut
+    thread::sleep(Duration::from_millis(150));
+    if let Err(e) = press_paste_shortcut(&mut enigo) {
+        eprintln!("Failed to simulate paste: {:?}", e);
+    }
+}
+
+fn press_paste_shortcut(enigo: &mut Enigo) -> enigo::InputResult<()> {
+    #[cfg(target_os = "macos")]
+    let modifier = Key::Meta;
+    #[cfg(not(target_os = "macos"))]
+    let modifier = Key::Control;
+
+    enigo.key(modifier, Direction::Press)?;
+    enigo.key(Key::Unicode('v'), Direction::Click)?;
+    enigo.key(modifier, Direction::Release)
+}
+
+/// Bounds of the frontmost window, used to capture it without interactive selection.
+pub fn active_window_region() -> Option<CaptureRegion> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            tell application "System Events"
+                set frontProc to first process whose frontmost is true
+                tell front window of frontProc
+                    set {x, y} to position
+                    set {w, h} to size
+                end tell
+            end tell
+            return (x as text) & "," & (y as text) & "," & (w as text) & "," & (h as text)
+        "#;
+
+        let out = Command::new("osascript").arg("-e").arg(script).output().ok()?;
+        let values: Vec<f32> = String::from_utf8_lossy(&out.stdout)
+            .trim()
+            .split(',')
+            .filter_map(|value| value.trim().parse().ok())
+            .collect();
+        match values[..] {
+            [x, y, width, height] => Some(CaptureRegion { x, y, width, height }),
+            _ => None,
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        win32::active_window_region()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::active_window_region()
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        None
+    }
+}
+
+/// The window results are pasted into, the frontmost window when neither process nor title is set.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct PasteTarget {
+    pub process_name: String,
+    pub window_title: String,
+    /// Extra wait after raising the window, for applications that are slow to take focus.
+    pub delay_ms: u64,
+}
+
+impl PasteTarget {
+    pub fn is_frontmost(&self) -> bool {
+        self.process_name.is_empty() && self.window_title.is_empty()
+    }
+
+    pub fn label(&self) -> String {
+        if self.is_frontmost() {
+            tr("Frontmost window").to_string()
+        } else if self.window_title.is_empty() {
+            self.process_name.clone()
+        } else {
+            format!("{} — {}", self.process_name, self.window_title)
+        }
+    }
+}
+
+/// Raise the paste target and paste the clipboard into it.
+pub fn paste_into(target: &PasteTarget) {
+    if !target.is_frontmost() {
+        bring_forward(&target.process_name, &target.window_title);
+        thread::sleep(Duration::from_millis(target.delay_ms));
+    }
+    simulate_paste();
+}
+
+/// Sends results to a running editor with a command instead of the clipboard, for example
+/// `nvim --server /tmp/nvim.sock --remote-tab {file}`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct EditorCommand {
+    pub enabled: bool,
+    /// Run by the shell with the text on stdin, `{file}` is replaced with the quoted path of a file
+    /// holding it.
+    pub command: String,
+}
+
+impl EditorCommand {
+    pub fn is_active(&self) -> bool {
+        self.enabled && !self.command.trim().is_empty()
+    }
+}
+
+/// Run the editor command with `text`, waiting for it so failures can be reported.
+pub fn send_to_editor(command: &str, text: &str) -> Result<(), String> {
+    // the editor may read the file after the command returned, it is removed on the next send
+    static LAST_FILE: std::sync::Mutex<Option<tempfile::TempPath>> = std::sync::Mutex::new(None);
+    let mut file = tempfile::Builder::new()
+        .prefix("typst_scan_")
+        .suffix(".typ")
+        .tempfile()
+        .map_err(|e| format!("Failed to create a temporary file: {}", e))?;
+    file.write_all(text.as_bytes()).map_err(|e| format!("{:?}: {}", file.path(), e))?;
+    let file = file.into_temp_path();
+    let command = command.replace("{file}", &shell_quote(&file.to_string_lossy()));
+    *LAST_FILE.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(file);
+    #[cfg(target_os = "windows")]
+    let mut shell = std::process::Command::new("cmd");
+    #[cfg(target_os = "windows")]
+    shell.args(["/C", &command]);
+    #[cfg(not(target_os = "windows"))]
+    let mut shell = std::process::Command::new("sh");
+    #[cfg(not(target_os = "windows"))]
+    shell.args(["-c", &command]);
+
+    let mut child = shell.stdin(Stdio::piped()).spawn().map_err(|e| format!("Failed to run {:?}: {}", command, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // commands that only read `{file}` close stdin early, that is fine
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("{:?} exited with {}", command, status));
+    }
+    Ok(())
+}
+
+/// A path as a single argument of `cmd /C`, where it cannot contain `"`.
+#[cfg(target_os = "windows")]
+fn shell_quote(path: &str) -> String {
+    format!("\"{}\"", path)
+}
+
+/// A path as a single argument of `sh -c`.
+#[cfg(not(target_os = "windows"))]
+fn shell_quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', "'\\''"))
+}
+
+/// Services a snip can be shared to, as label and service name, empty where the system has no
+/// share sheet.
+#[cfg(target_os = "macos")]
+pub const SHARE_SERVICES: &[(&str, &str)] =
+    &[("Mail", "NSSharingServiceNameComposeEmail"), ("Messages", "NSSharingServiceNameComposeMessage")];
+#[cfg(target_os = "windows")]
+pub const SHARE_SERVICES: &[(&str, &str)] = &[("Share sheet", "share_sheet")];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub const SHARE_SERVICES: &[(&str, &str)] = &[];
+
+/// Hand the text and image of a snip to a service of [`SHARE_SERVICES`].
+pub fn share(service: &str, title: &str, text: &str, image_path: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = title;
+        // JavaScript for Automation reaches AppKit's sharing services without linking Objective-C
+        let literal = |s: &str| serde_json::to_string(s).unwrap_or_default();
+        let script = format!(
+            r#"
+            ObjC.import('AppKit');
+            var items = $.NSMutableArray.array;
+            items.addObject($({text}));
+            if ({image_path} !== '') items.addObject($.NSURL.fileURLWithPath({image_path}));
+            var service = $.NSSharingService.sharingServiceNamed($.{service});
+            if (!service.canPerformWithItems(items)) throw new Error('The service cannot share this snip');
+            service.performWithItems(items);
+        "#,
+            text = literal(text),
+            image_path = literal(image_path),
+        );
+        // the service hands off to another app, no need to wait for it
+        thread::spawn(move || match Command::new("osascript").args(["-l", "JavaScript", "-e", &script]).output() {
+            Ok(out) if !out.status.success() => eprintln!("Failed to share: {}", String::from_utf8_lossy(&out.stderr)),
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to share: {:?}", e),
+        });
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let _ = service;
+        win32::share(title, text, image_path).map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = (title, text, image_path);
+        Err(format!("Sharing to {service} is not supported on this platform"))
+    }
+}
+
+/// A window of a running application that can be used as the bring-forward target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetWindow {
+    pub process_name: String,
+    pub window_title: String,
+}
+
+/// Enumerate the windows of the currently running (non-background) applications.
+pub fn list_windows() -> Vec<TargetWindow> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            set output to ""
+            tell application "System Events"
+                repeat with proc in (every process whose background only is false)
+                    set procName to name of proc
+                    repeat with win in (every window of proc)
+                        try
+                            set output to output & procName & tab & (name of win) & linefeed
+                        end try
+                    end repeat
+                end repeat
+            end tell
+            return output
+        "#;
+
+        match Command::new("osascript").arg("-e").arg(script).output() {
+            Ok(out) => String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .filter_map(|line| line.split_once('\t'))
+                .map(|(process_name, window_title)| TargetWindow {
+                    process_name: process_name.to_string(),
+                    window_title: window_title.to_string(),
+                })
+                .collect(),
+            Err(e) => {
+                eprintln!("Failed to list windows: {:?}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        win32::list_windows()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::list_windows()
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        Vec::new()
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    use super::TargetWindow;
+    use crate::capture::{self, CaptureRegion};
+    use std::ptr;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use windows_sys::Win32::Foundation::{CloseHandle, BOOL, HWND, LPARAM, RECT};
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, FindWindowW, GetForegroundWindow, GetWindowRect, GetWindowTextLengthW, GetWindowTextW,
+        GetWindowThreadProcessId, IsIconic, IsWindowVisible, SetForegroundWindow, ShowWindow, SW_RESTORE,
+    };
+
+    pub fn bring_forward(process_name: &str, window_title: &str) {
+        let mut hwnd: HWND = ptr::null_mut();
+        if !window_title.is_empty() {
+            let title = to_wide(window_title);
+            hwnd = unsafe { FindWindowW(ptr::null(), title.as_ptr()) };
+        }
+        if hwnd.is_null() && !process_name.is_empty() {
+            // no exact title match, fall back to the first titled window of the process
+            let process_name = process_name.trim_end_matches(".exe");
+            hwnd = visible_windows()
+                .into_iter()
+                .find(|&hwnd| {
+                    !get_window_title(hwnd).is_empty() && get_process_name(hwnd).eq_ignore_ascii_case(process_name)
+                })
+                .unwrap_or(ptr::null_mut());
+        }
+        if hwnd.is_null() {
+            eprintln!("No window found for process {process_name:?} / title {window_title:?}");
+            return;
+        }
+
+        unsafe {
+            if IsIconic(hwnd) != 0 {
+                ShowWindow(hwnd, SW_RESTORE);
+            }
+            if SetForegroundWindow(hwnd) == 0 {
+                eprintln!("SetForegroundWindow failed for {window_title:?}");
+            }
+        }
+    }
+
+    pub fn active_window_region() -> Option<CaptureRegion> {
+        let mut rect = RECT { left: 0, top: 0, right: 0, bottom: 0 };
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_null() || GetWindowRect(hwnd, &mut rect) == 0 {
+                return None;
+            }
+        }
+        let width = (rect.right - rect.left).max(0) as u32;
+        let height = (rect.bottom - rect.top).max(0) as u32;
+        Some(capture::region_from_physical(rect.left, rect.top, width, height))
+    }
+
+    pub fn list_windows() -> Vec<TargetWindow> {
+        visible_windows()
+            .into_iter()
+            .map(|hwnd| TargetWindow {
+                process_name: get_process_name(hwnd),
+                window_title: get_window_title(hwnd),
+            })
+            .filter(|window| !window.window_title.is_empty())
+            .collect()
+    }
+
+    fn visible_windows() -> Vec<HWND> {
+        unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+            let windows = &mut *(lparam as *mut Vec<HWND>);
+            if IsWindowVisible(hwnd) != 0 {
+                windows.push(hwnd);
+            }
+            1
+        }
+
+        let mut windows: Vec<HWND> = Vec::new();
+        unsafe {
+            EnumWindows(Some(collect), &mut windows as *mut Vec<HWND> as LPARAM);
+        }
+        windows
+    }
+
+    fn get_window_title(hwnd: HWND) -> String {
+        unsafe {
+            let len = GetWindowTextLengthW(hwnd);
+            if len <= 0 {
+                return String::new();
+            }
+            let mut buf = vec![0u16; len as usize + 1];
+            let copied = GetWindowTextW(hwnd, buf.as_mut_ptr(), buf.len() as i32);
+            String::from_utf16_lossy(&buf[..copied.max(0) as usize])
+        }
+    }
+
+    fn get_process_name(hwnd: HWND) -> String {
+        unsafe {
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(hwnd, &mut pid);
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                return String::new();
+            }
+            let mut buf = [0u16; 1024];
+            let mut size = buf.len() as u32;
+            let ok = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, buf.as_mut_ptr(), &mut size);
+            CloseHandle(process);
+            if ok == 0 {
+                return String::new();
+            }
+            let path = String::from_utf16_lossy(&buf[..size as usize]);
+            std::path::Path::new(&path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default()
+        }
+    }
+
+    /// What the share sheet asks for when it opens, the handler is registered once per window.
+    static SHARED: Mutex<Option<(String, String, String)>> = Mutex::new(None);
+    static SHARE_HANDLER: AtomicBool = AtomicBool::new(false);
+
+    /// Open the share sheet of the foreground window, which is ours while the user clicks Share.
+    pub fn share(title: &str, text: &str, image_path: &str) -> windows::core::Result<()> {
+        use windows::core::{factory, HSTRING};
+        use windows::ApplicationModel::DataTransfer::{DataRequestedEventArgs, DataTransferManager};
+        use windows::Foundation::{AsyncOperationCompletedHandler, TypedEventHandler};
+        use windows::Storage::StorageFile;
+        use windows::Storage::Streams::RandomAccessStreamReference;
+        use windows::Win32::UI::Shell::IDataTransferManagerInterop;
+
+        // a panic while the lock was held leaves nothing half written, the value is replaced anyway
+        *SHARED.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            Some((title.to_string(), text.to_string(), image_path.to_string()));
+        let hwnd = windows::Win32::Foundation::HWND(unsafe { GetForegroundWindow() });
+        let interop = factory::<DataTransferManager, IDataTransferManagerInterop>()?;
+        if !SHARE_HANDLER.swap(true, Ordering::SeqCst) {
+            let manager: DataTransferManager = unsafe { interop.GetForWindow(hwnd)? };
+            manager.DataRequested(&TypedEventHandler::new(
+                |_: &Option<DataTransferManager>, args: &Option<DataRequestedEventArgs>| {
+                    let shared = SHARED.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+                    let (Some(args), Some((title, text, image_path))) = (args, shared) else {
+                        return Ok(());
+                    };
+                    let request = args.Request()?;
+                    let data = request.Data()?;
+                    data.Properties()?.SetTitle(&HSTRING::from(title))?;
+                    data.SetText(&HSTRING::from(text))?;
+                    if !image_path.is_empty() {
+                        // the handler must not block, the share UI waits for the deferral instead
+                        let deferral = request.GetDeferral()?;
+                        let operation = StorageFile::GetFileFromPathAsync(&HSTRING::from(image_path))?;
+                        operation.SetCompleted(&AsyncOperationCompletedHandler::new(move |operation, _status| {
+                            let attached = operation
+                                .as_ref()
+                                .ok_or_else(windows::core::Error::empty)
+                                .and_then(|operation| operation.GetResults())
+                                .and_then(|file| RandomAccessStreamReference::CreateFromFile(&file))
+                                .and_then(|bitmap| data.SetBitmap(&bitmap));
+                            if let Err(e) = attached {
+                                eprintln!("Failed to attach the screenshot to the share: {:?}", e);
+                            }
+                            deferral.Complete()
+                        }))?;
+                    }
+                    Ok(())
+                },
+            ))?;
+        }
+        unsafe { interop.ShowShareUIForWindow(hwnd) }
+    }
+
+    fn to_wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+}
+
+/// X11 (and XWayland) window handling through `wmctrl`, falling back to `xdotool`.
+///
+/// Native Wayland clients cannot be raised by other applications, so only windows
+/// served through XWayland can be targeted there.
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::TargetWindow;
+    use crate::capture::{self, CaptureRegion};
+    use std::process::Command;
+
+    pub fn bring_forward(process_name: &str, window_title: &str) {
+        if !window_title.is_empty() && run("wmctrl", &["-a", window_title]) {
+            return;
+        }
+        if !window_title.is_empty()
+            && run("xdotool", &["search", "--onlyvisible", "--name", window_title, "windowactivate"])
+        {
+            return;
+        }
+        if !process_name.is_empty()
+            && run("xdotool", &["search", "--onlyvisible", "--class", process_name, "windowactivate"])
+        {
+            return;
+        }
+        eprintln!("Failed to bring {process_name} ({window_title}) forward, is wmctrl or xdotool installed?");
+    }
+
+    pub fn list_windows() -> Vec<TargetWindow> {
+        let output = match Command::new("wmctrl").arg("-lp").output() {
+            Ok(output) if output.status.success() => output,
+            _ => {
+                eprintln!("Failed to list windows, is wmctrl installed?");
+                return Vec::new();
+            }
+        };
+
+        // each line looks like: <window id> <desktop> <pid> <host> <title...>
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let mut columns = line.split_whitespace();
+                let pid = columns.nth(2)?;
+                let _host = columns.next()?;
+                let window_title = columns.collect::<Vec<_>>().join(" ");
+                let process_name = std::fs::read_to_string(format!("/proc/{pid}/comm")).unwrap_or_default();
+                Some(TargetWindow {
+                    process_name: process_name.trim().to_string(),
+                    window_title,
+                })
+            })
+            .filter(|window| !window.window_title.is_empty())
+            .collect()
+    }
+
+    pub fn active_window_region() -> Option<CaptureRegion> {
+        let output = Command::new("xdotool").args(["getactivewindow", "getwindowgeometry", "--shell"]).output().ok()?;
+        let geometry = String::from_utf8_lossy(&output.stdout);
+        let value = |key: &str| {
+            geometry
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                .and_then(|value| value.trim().parse::<i64>().ok())
+        };
+        let (x, y) = (value("X")?, value("Y")?);
+        let (width, height) = (value("WIDTH")?, value("HEIGHT")?);
+        Some(capture::region_from_physical(x as i32, y as i32, width as u32, height as u32))
+    }
+
+    fn run(program: &str, args: &[&str]) -> bool {
+        Command::new(program).args(args).output().map(|out| out.status.success()).unwrap_or(false)
+    }
+}
@@ -0,0 +1,187 @@
+use crate::hotkeys::{self, HotkeyAction, HotkeyDispatcher};
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+use ashpd::desktop::Session;
+use futures_util::StreamExt;
+use livesplit_hotkey::{Hotkey, Modifiers};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Global shortcuts through the XDG desktop portal, for Wayland sessions.
+///
+/// The portal binds all shortcuts of a session at once, so every change closes the previous session
+/// and opens a new one with the full list. The desktop may ask the user to confirm or change the
+/// triggers, shortcuts it does not bind are reported through [`PortalShortcuts::take_failures`].
+pub struct PortalShortcuts {
+    bindings: Arc<Mutex<Vec<(Hotkey, HotkeyAction)>>>,
+    generation: Arc<AtomicUsize>,
+    dispatcher: HotkeyDispatcher,
+    /// The session of the last bind, closed when the shortcuts are bound again.
+    session: Arc<Mutex<Option<Session<'static, GlobalShortcuts<'static>>>>>,
+    failures: Arc<Mutex<Vec<(HotkeyAction, String)>>>,
+}
+
+impl PortalShortcuts {
+    pub fn new(dispatcher: HotkeyDispatcher) -> Self {
+        Self {
+            bindings: Arc::new(Mutex::new(Vec::new())),
+            generation: Arc::new(AtomicUsize::new(0)),
+            dispatcher,
+            session: Arc::new(Mutex::new(None)),
+            failures: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn register(&self, hotkey: Hotkey, action: HotkeyAction) {
+        if let Ok(mut bindings) = self.bindings.lock() {
+            bindings.push((hotkey, action));
+        }
+        self.rebind();
+    }
+
+    pub fn unregister(&self, hotkey: Hotkey) {
+        if let Ok(mut bindings) = self.bindings.lock() {
+            bindings.retain(|(bound, _)| *bound != hotkey);
+        }
+        self.rebind();
+    }
+
+    /// Shortcuts the desktop refused or failed to bind since the last call, with the reason.
+    pub fn take_failures(&self) -> Vec<(HotkeyAction, String)> {
+        self.failures.lock().map(|mut failures| std::mem::take(&mut *failures)).unwrap_or_default()
+    }
+
+    fn rebind(&self) {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let portal = Portal {
+            bindings: self.bindings.clone(),
+            generation,
+            current_generation: self.generation.clone(),
+            dispatcher: self.dispatcher.clone(),
+            session: self.session.clone(),
+            failures: self.failures.clone(),
+        };
+        thread::spawn(move || {
+            // registrations at startup come in a burst, only bind the last state
+            thread::sleep(Duration::from_millis(300));
+            if !portal.is_current() {
+                return;
+            }
+            if let Err(e) = pollster::block_on(portal.listen()) {
+                eprintln!("GlobalShortcuts portal failed: {:?}", e);
+            }
+        });
+    }
+}
+
+/// What the thread of one bind shares with [`PortalShortcuts`].
+struct Portal {
+    bindings: Arc<Mutex<Vec<(Hotkey, HotkeyAction)>>>,
+    generation: usize,
+    current_generation: Arc<AtomicUsize>,
+    dispatcher: HotkeyDispatcher,
+    session: Arc<Mutex<Option<Session<'static, GlobalShortcuts<'static>>>>>,
+    failures: Arc<Mutex<Vec<(HotkeyAction, String)>>>,
+}
+
+impl Portal {
+    fn is_current(&self) -> bool {
+        self.current_generation.load(Ordering::SeqCst) == self.generation
+    }
+
+    async fn listen(self) -> ashpd::Result<()> {
+        // the desktop keeps the shortcuts of a session until it is closed, even once a newer one has them
+        let previous = self.session.lock().ok().and_then(|mut session| session.take());
+        if let Some(previous) = previous {
+            if let Err(e) = previous.close().await {
+                eprintln!("Failed to close the previous GlobalShortcuts session: {:?}", e);
+            }
+        }
+        let bindings = self.bindings.lock().map(|bindings| bindings.clone()).unwrap_or_default();
+        if bindings.is_empty() {
+            return Ok(());
+        }
+
+        let portal = GlobalShortcuts::new().await?;
+        let session = portal.create_session().await?;
+        let shortcuts: Vec<NewShortcut> = bindings
+            .iter()
+            .map(|(hotkey, action)| {
+                NewShortcut::new(format!("{:?}", action), action.label()).preferred_trigger(trigger(hotkey).as_str())
+            })
+            .collect();
+        let bound: Vec<String> = match portal.bind_shortcuts(&session, &shortcuts, None).await.and_then(|request| request.response()) {
+            Ok(response) => response.shortcuts().iter().map(|shortcut| shortcut.id().to_string()).collect(),
+            Err(e) => {
+                eprintln!("The GlobalShortcuts portal did not bind the shortcuts: {:?}", e);
+                Vec::new()
+            }
+        };
+        let (bindings, refused): (Vec<_>, Vec<_>) =
+            bindings.into_iter().partition(|(_, action)| bound.contains(&format!("{:?}", action)));
+        self.refuse(&refused);
+        // a newer bind that already started would not know to close this session
+        let stale = match self.session.lock() {
+            Ok(mut slot) if self.is_current() => {
+                *slot = Some(session);
+                None
+            }
+            _ => Some(session),
+        };
+        if let Some(stale) = stale {
+            return stale.close().await;
+        }
+        println!("Bound {} shortcuts through the GlobalShortcuts portal", bindings.len());
+
+        let mut activated = portal.receive_activated().await?;
+        while let Some(activation) = activated.next().await {
+            if !self.is_current() {
+                // a newer session took over and closed this one
+                break;
+            }
+            if let Some((_, action)) = bindings.iter().find(|(_, action)| format!("{:?}", action) == activation.shortcut_id()) {
+                println!("Hotkey pressed: {:?}", action);
+                self.dispatcher.dispatch(*action);
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget the shortcuts the desktop did not bind and report them, so the settings show them as failed.
+    fn refuse(&self, refused: &[(Hotkey, HotkeyAction)]) {
+        if refused.is_empty() {
+            return;
+        }
+        if let Ok(mut bindings) = self.bindings.lock() {
+            bindings.retain(|binding| !refused.contains(binding));
+        }
+        if let Ok(mut failures) = self.failures.lock() {
+            failures.extend(refused.iter().map(|(hotkey, action)| {
+                (*action, format!("{} was not bound by the desktop, it may be used by another application", hotkeys::describe(hotkey)))
+            }));
+        }
+        self.dispatcher.request_repaint();
+    }
+}
+
+/// Preferred trigger in the notation of the XDG shortcuts spec, e.g. `CTRL+ALT+z`.
+pub fn trigger(hotkey: &Hotkey) -> String {
+    let mut trigger = String::new();
+    for (modifier, name) in [
+        (Modifiers::CONTROL, "CTRL"),
+        (Modifiers::ALT, "ALT"),
+        (Modifiers::SHIFT, "SHIFT"),
+        (Modifiers::META, "LOGO"),
+    ] {
+        if hotkey.modifiers.contains(modifier) {
+            trigger.push_str(name);
+            trigger.push('+');
+        }
+    }
+    // key codes are named after the W3C key codes, e.g. KeyZ and Digit1
+    let key = hotkey.key_code.name();
+    let key = key.strip_prefix("Key").or_else(|| key.strip_prefix("Digit")).unwrap_or(key);
+    trigger.push_str(&key.to_lowercase());
+    trigger
+}
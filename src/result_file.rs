@@ -0,0 +1,74 @@
+use crate::capture;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+/// Writes the Typst of every result to a file, or a FIFO made with `mkfifo`, so editors can read
+/// the latest one without a plugin, e.g. `:r ~/.local/share/typstscan/latest.typ` in Vim.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ResultFile {
+    pub enabled: bool,
+    /// Empty for `latest.typ` in the storage directory.
+    pub path: String,
+}
+
+impl ResultFile {
+    pub fn path(&self) -> Option<PathBuf> {
+        if self.path.trim().is_empty() {
+            capture::get_storage_dir().map(|dir| dir.join("latest.typ"))
+        } else {
+            Some(PathBuf::from(self.path.trim()))
+        }
+    }
+}
+
+/// Writes on a thread of its own, opening a FIFO waits until something reads from it.
+pub struct ResultFileWriter {
+    sender: Sender<(PathBuf, String)>,
+}
+
+impl ResultFileWriter {
+    pub fn start() -> Self {
+        let (sender, receiver) = mpsc::channel::<(PathBuf, String)>();
+        thread::spawn(move || {
+            while let Ok(mut latest) = receiver.recv() {
+                // only the newest result matters to a reader that was away
+                while let Ok(newer) = receiver.try_recv() {
+                    latest = newer;
+                }
+                let (path, text) = latest;
+                if let Err(e) = write(&path, &text) {
+                    eprintln!("Failed to write the result to {:?}: {:?}", path, e);
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    pub fn write(&self, path: PathBuf, text: String) {
+        let _ = self.sender.send((path, text));
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &std::path::Path) -> bool {
+    false
+}
+
+fn write(path: &std::path::Path, text: &str) -> std::io::Result<()> {
+    if is_fifo(path) {
+        return std::fs::write(path, text);
+    }
+    // replaced in one go, so a reader never sees half a result
+    let temp_path = path.with_extension("tmp");
+    std::fs::write(&temp_path, text)?;
+    std::fs::rename(&temp_path, path)
+}
@@ -0,0 +1,31 @@
+/// Score of `text` for a fuzzy `query`, `None` when the query letters don't all appear in order.
+/// Consecutive letters and letters at the start of a word count more.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let mut score = 0;
+    let mut matched = 0;
+    let mut previous_matched = false;
+    let mut previous = ' ';
+    for c in text.chars() {
+        let lowercase = c.to_lowercase().next().unwrap_or(c);
+        if matched < query.len() && lowercase == query[matched] {
+            score += 1;
+            if previous_matched {
+                score += 4;
+            }
+            if !previous.is_alphanumeric() {
+                score += 2;
+            }
+            matched += 1;
+            previous_matched = true;
+        } else {
+            previous_matched = false;
+        }
+        previous = c;
+    }
+    (matched == query.len()).then_some(score)
+}
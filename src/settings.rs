@@ -0,0 +1,123 @@
+use crate::app::TypstScanData;
+use crate::capture;
+use eframe::egui;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// Fields of `TypstScanData` that hold the user's data or UI state rather than settings.
+const NOT_SETTINGS: &[&str] = &[
+    "snip_items",
+    "trash",
+    "main_view",
+    "layout",
+    "selected_snip_item",
+    "api_used",
+    "api_limit",
+    "continuous_clipboard",
+    "copy_history",
+    "next_equation_number",
+    "replace_rules_json",
+    "last_region",
+    "onboarding_done",
+    "dismissed_update",
+    "mathpix_folders",
+];
+
+const SECRETS: &[&str] = &["mathpix_api_key", "app_lock"];
+
+/// The settings part of the app data, for moving them to another machine.
+pub fn export_settings(data: &TypstScanData, include_secrets: bool) -> Result<String, String> {
+    serde_json::to_string_pretty(&settings_value(data, include_secrets)?).map_err(|e| e.to_string())
+}
+
+/// The settings as a TOML config file. TOML has no null, so unset options are left out.
+pub fn export_config(data: &TypstScanData, include_secrets: bool) -> Result<String, String> {
+    let mut settings = settings_value(data, include_secrets)?;
+    remove_nulls(&mut settings);
+    toml::to_string_pretty(&settings).map_err(|e| e.to_string())
+}
+
+pub fn settings_value(data: &TypstScanData, include_secrets: bool) -> Result<Value, String> {
+    let mut settings = serde_json::to_value(data).map_err(|e| e.to_string())?;
+    if let Value::Object(fields) = &mut settings {
+        fields.retain(|key, _| !NOT_SETTINGS.contains(&key.as_str()));
+        if !include_secrets {
+            fields.retain(|key, _| !SECRETS.contains(&key.as_str()));
+        }
+    }
+    Ok(settings)
+}
+
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            fields.retain(|_, value| !value.is_null());
+            fields.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+pub fn parse_json(settings: &str) -> Result<Value, String> {
+    serde_json::from_str(settings).map_err(|e| e.to_string())
+}
+
+pub fn parse_config(config: &str) -> Result<Value, String> {
+    toml::from_str(config).map_err(|e| e.to_string())
+}
+
+/// Apply exported settings on top of the current data. Settings missing from the file,
+/// and the user's snips, stay as they are.
+pub fn import_settings(data: &TypstScanData, settings: Value) -> Result<TypstScanData, String> {
+    let Value::Object(imported) = settings else {
+        return Err("The settings file does not contain an object".to_string());
+    };
+    let mut merged = serde_json::to_value(data).map_err(|e| e.to_string())?;
+    if let Value::Object(fields) = &mut merged {
+        for (key, value) in imported {
+            if !NOT_SETTINGS.contains(&key.as_str()) {
+                fields.insert(key, value);
+            }
+        }
+    }
+    serde_json::from_value(merged).map_err(|e| e.to_string())
+}
+
+/// `config.toml` in the storage directory (of the profile), read at startup and whenever it changes.
+pub fn config_path(profile: Option<&str>) -> Option<PathBuf> {
+    let dir = capture::get_storage_dir()?;
+    Some(match profile {
+        Some(profile) => dir.join("profiles").join(profile).join("config.toml"),
+        None => dir.join("config.toml"),
+    })
+}
+
+/// Poll the config file for changes and send its new content.
+pub fn watch_config(path: PathBuf, ctx: egui::Context) -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut last_modified = None;
+        loop {
+            let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+            if modified.is_some() && modified != last_modified {
+                last_modified = modified;
+                match std::fs::read_to_string(&path) {
+                    Ok(config) => {
+                        println!("Loading config from {:?}", path);
+                        if sender.send(config).is_err() {
+                            break;
+                        }
+                        ctx.request_repaint();
+                    }
+                    Err(e) => eprintln!("Failed to read config {:?}: {:?}", path, e),
+                }
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+    });
+    receiver
+}
@@ -0,0 +1,143 @@
+use crate::app::{SnipItem, TrashedSnip};
+use crate::capture;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+const ORDER_FILE: &str = "order.json";
+const TRASH_FILE: &str = "trash.json";
+
+/// Snips kept as one JSON file each next to the app data, so a save only writes the snips that
+/// changed instead of the whole history.
+pub struct SnipStore {
+    dir: PathBuf,
+    /// Fingerprint of the content last written to each file.
+    written: HashMap<String, u64>,
+    /// Snips changed since the last save, the others are not serialized again.
+    changed: HashSet<Uuid>,
+    /// Snips in the trash when it was last written, with when they were deleted.
+    written_trash: Vec<(Uuid, i64)>,
+}
+
+impl SnipStore {
+    pub fn new(profile: Option<&str>) -> Option<Self> {
+        let dir = capture::get_storage_dir()?;
+        Some(Self::at(match profile {
+            Some(profile) => dir.join("profiles").join(profile).join("snips"),
+            None => dir.join("snips"),
+        }))
+    }
+
+    pub fn at(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            written: HashMap::new(),
+            changed: HashSet::new(),
+            written_trash: Vec::new(),
+        }
+    }
+
+    /// Write the snip again on the next save. Snips that are added or removed are noticed without this.
+    pub fn mark_changed(&mut self, id: Uuid) {
+        self.changed.insert(id);
+    }
+
+    /// The saved snips and trash, `None` when nothing was saved here yet.
+    pub fn load(&mut self) -> Option<(Vec<SnipItem>, Vec<TrashedSnip>)> {
+        let order: Vec<Uuid> = self.read(ORDER_FILE)?;
+        let snip_items = order.iter().filter_map(|id| self.read(&format!("{}.json", id))).collect();
+        let trash: Vec<TrashedSnip> = self.read(TRASH_FILE).unwrap_or_default();
+        self.written_trash = trash.iter().map(TrashedSnip::key).collect();
+        println!("Loaded snips from {:?}", self.dir);
+        Some((snip_items, trash))
+    }
+
+    /// Write the snips that are new or changed since the last save and remove the files of deleted ones.
+    pub fn save(&mut self, snip_items: &[SnipItem], trash: &[TrashedSnip]) {
+        if let Err(e) = std::fs::create_dir_all(&self.dir) {
+            eprintln!("Failed to create {:?}: {:?}", self.dir, e);
+            return;
+        }
+
+        let mut current = Vec::with_capacity(snip_items.len() + 2);
+        let changed = std::mem::take(&mut self.changed);
+        for snip_item in snip_items {
+            let file_name = format!("{}.json", snip_item.id());
+            // a snip without a file was added or restored since the last save
+            if (changed.contains(&snip_item.id()) || !self.written.contains_key(&file_name))
+                && !self.write(&file_name, snip_item)
+            {
+                // try again on the next save
+                self.changed.insert(snip_item.id());
+            }
+            current.push(file_name);
+        }
+        let order: Vec<Uuid> = snip_items.iter().map(SnipItem::id).collect();
+        self.write(ORDER_FILE, &order);
+        // snips in the trash are not edited, it only changes when snips come and go
+        let trash_keys: Vec<(Uuid, i64)> = trash.iter().map(TrashedSnip::key).collect();
+        let trash_unwritten = trash_keys != self.written_trash || !self.written.contains_key(TRASH_FILE);
+        if trash_unwritten && self.write(TRASH_FILE, &trash) {
+            self.written_trash = trash_keys;
+        }
+        current.extend([ORDER_FILE.to_string(), TRASH_FILE.to_string()]);
+
+        let removed: Vec<String> = self.written.keys().filter(|name| !current.contains(name)).cloned().collect();
+        for file_name in removed {
+            if let Err(e) = std::fs::remove_file(self.dir.join(&file_name)) {
+                eprintln!("Failed to remove {:?}: {:?}", file_name, e);
+            }
+            self.written.remove(&file_name);
+        }
+    }
+
+    fn read<T: DeserializeOwned>(&mut self, file_name: &str) -> Option<T> {
+        let json = std::fs::read_to_string(self.dir.join(file_name)).ok()?;
+        match serde_json::from_str(&json) {
+            Ok(value) => {
+                self.written.insert(file_name.to_string(), fingerprint(&json));
+                Some(value)
+            }
+            Err(e) => {
+                eprintln!("Failed to read {:?}: {:?}", file_name, e);
+                None
+            }
+        }
+    }
+
+    /// Write a file unless it already has this content, false when that failed.
+    fn write(&mut self, file_name: &str, value: &impl serde::Serialize) -> bool {
+        let json = match serde_json::to_string(value) {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!("Failed to serialize {:?}: {:?}", file_name, e);
+                return false;
+            }
+        };
+        let fingerprint = fingerprint(&json);
+        if self.written.get(file_name) == Some(&fingerprint) {
+            return true;
+        }
+        // write next to the file and rename, so a crash never leaves half a snip behind
+        let path = self.dir.join(file_name);
+        let temp_path = path.with_extension("json.tmp");
+        match std::fs::write(&temp_path, &json).and_then(|()| std::fs::rename(&temp_path, &path)) {
+            Ok(()) => {
+                self.written.insert(file_name.to_string(), fingerprint);
+                true
+            }
+            Err(e) => {
+                eprintln!("Failed to save {:?}: {:?}", path, e);
+                false
+            }
+        }
+    }
+}
+
+fn fingerprint(json: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    json.hash(&mut hasher);
+    hasher.finish()
+}
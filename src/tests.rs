@@ -37,4 +37,427 @@ mod tests {
         let output = Command::new("osascript").arg("-e").arg(script).output().unwrap();
         println!("{:?}", output);
     }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn portal_trigger() {
+        use crate::portal::trigger;
+        use livesplit_hotkey::{Hotkey, KeyCode, Modifiers};
+
+        let hotkey = Hotkey {
+            key_code: KeyCode::KeyZ,
+            modifiers: Modifiers::CONTROL | Modifiers::ALT,
+        };
+        assert_eq!(trigger(&hotkey), "CTRL+ALT+z");
+
+        let hotkey = Hotkey {
+            key_code: KeyCode::Digit1,
+            modifiers: Modifiers::SHIFT,
+        };
+        assert_eq!(trigger(&hotkey), "SHIFT+1");
+    }
+
+    #[test]
+    fn hotkey_key_mapping() {
+        use crate::hotkeys::{shortcut_to_hotkey, KEY_CODES};
+        use eframe::egui;
+        use egui_keybind::Shortcut;
+        use livesplit_hotkey::{KeyCode, Modifiers};
+
+        for (i, (key, key_code)) in KEY_CODES.iter().enumerate() {
+            assert!(!KEY_CODES[..i].iter().any(|(other, _)| other == key), "{:?} is mapped twice", key);
+            assert!(!KEY_CODES[..i].iter().any(|(_, other)| other == key_code), "{:?} is mapped twice", key_code);
+        }
+
+        let shortcut = Shortcut::new(
+            Some(egui::KeyboardShortcut::new(egui::Modifiers::CTRL | egui::Modifiers::SHIFT, egui::Key::Num5)),
+            None,
+        );
+        let hotkey = shortcut_to_hotkey(&shortcut).unwrap();
+        assert_eq!(hotkey.key_code, KeyCode::Digit5);
+        assert_eq!(hotkey.modifiers, Modifiers::CONTROL | Modifiers::SHIFT);
+
+        let shortcut = Shortcut::new(Some(egui::KeyboardShortcut::new(egui::Modifiers::ALT, egui::Key::Pipe)), None);
+        assert!(shortcut_to_hotkey(&shortcut).is_none());
+    }
+
+    #[test]
+    fn update_version_comparison() {
+        use crate::update::is_newer;
+
+        assert!(is_newer("0.2.0", "0.1.0"));
+        assert!(is_newer("0.10.0", "0.9.3"));
+        assert!(is_newer("1.0.1", "1.0"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0-beta", "0.1.0"));
+        assert!(!is_newer("0.0.9", "0.1.0"));
+    }
+
+    #[test]
+    fn startup_options() {
+        use crate::StartupOptions;
+
+        let args = ["--profile", "work", "--minimized", "--config", "/tmp/scan.toml"].map(String::from);
+        let options = StartupOptions::parse(args.into_iter()).unwrap();
+        assert_eq!(options.profile.as_deref(), Some("work"));
+        assert_eq!(options.config, Some("/tmp/scan.toml".into()));
+        assert!(options.minimized);
+        assert!(!options.capture_on_start);
+
+        assert!(StartupOptions::parse(["--profile".to_string()].into_iter()).is_err());
+        assert!(StartupOptions::parse(["--unknown".to_string()].into_iter()).is_err());
+
+        let options = StartupOptions::parse(["--renderer", "glow"].map(String::from).into_iter()).unwrap();
+        assert_eq!(options.renderer, Some(crate::graphics::GraphicsBackend::Glow));
+        assert!(StartupOptions::parse(["--renderer", "vulkan"].map(String::from).into_iter()).is_err());
+    }
+
+    #[test]
+    fn tex_normalization() {
+        use crate::convert::{normalize, Normalization};
+
+        let tex = "\\begin{eqnarray}\nx &=& \\mbox{if } \\dfrac{1}{2}\n\\end{eqnarray}";
+        assert_eq!(
+            normalize(tex, &Normalization::ALL),
+            "\\begin{align}\nx &= \\text{if } \\frac{1}{2}\n\\end{align}"
+        );
+        assert_eq!(normalize(tex, &[]), tex);
+    }
+
+    #[test]
+    fn latex_validation() {
+        use crate::convert::{validate, LatexWarning};
+
+        assert!(validate("\\frac{a}{b} \\{x\\} \\left( \\leftarrow \\right)").is_empty());
+        assert_eq!(validate("\\frac{a}{b"), vec![LatexWarning::UnbalancedBraces]);
+        assert_eq!(validate("\\left( x"), vec![LatexWarning::UnbalancedLeftRight]);
+        assert_eq!(
+            validate("\\begin{align} x \\end{aligned}"),
+            vec![
+                LatexWarning::UnmatchedEnd("aligned".to_string()),
+                LatexWarning::UnclosedEnvironment("align".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn typst_formatting() {
+        use crate::convert::TypstFormat;
+
+        let format = TypstFormat { enabled: true, line_width: 20 };
+        assert_eq!(format.apply("$ a  +  b $"), "$ a + b $");
+        assert_eq!(format.apply("$ alpha + beta + gamma = delta $"), "$ alpha + beta\n  + gamma = delta $");
+        assert_eq!(format.apply("\"a    long  string\" x"), "\"a    long  string\"\n  x");
+        assert_eq!(TypstFormat::default().apply("$ a  +  b $"), "$ a  +  b $");
+    }
+
+    #[test]
+    fn split_equations() {
+        use crate::convert::split_display_math;
+
+        assert_eq!(split_display_math("\\[ a \\]\ntext\n$$ b $$"), vec!["\\[ a \\]\ntext", "$$ b $$"]);
+        assert_eq!(
+            split_display_math("Intro\n\\[ a \\]\n\\[ b \\] end"),
+            vec!["Intro\n\\[ a \\]", "\\[ b \\]\nend"]
+        );
+        assert_eq!(split_display_math("\\( x \\)\n\n\\( y \\)\n"), vec!["\\( x \\)", "\\( y \\)"]);
+        assert_eq!(split_display_math("\\[\nx = 1\n\\]").len(), 1);
+    }
+
+    #[test]
+    fn table_and_chemistry_conversion() {
+        use crate::convert::{convert_with_smiles, tabular_to_typst};
+
+        let tex = "\\begin{tabular}{|c|p{2cm}|}\n\\hline a & b \\\\\n\\hline c \\\\\n\\end{tabular}";
+        let table = tabular_to_typst(tex, |cell| cell.to_uppercase()).unwrap();
+        assert_eq!(table, "#table(\n  columns: 2,\n  [A], [B],\n  [C], [],\n)");
+        assert!(tabular_to_typst("x", |cell| cell.to_string()).is_none());
+        assert!(tabular_to_typst("\\begin{tabular}\\end{tabular}", |cell| cell.to_string()).is_none());
+        assert!(tabular_to_typst("\\begin{tabular}{l\\end{tabular}}", |cell| cell.to_string()).is_none());
+        let spaced = tabular_to_typst("\\begin{tabular} {ll} a & b \\end{tabular}", |cell| cell.to_string()).unwrap();
+        assert_eq!(spaced, "#table(\n  columns: 2,\n  [a], [b],\n)");
+
+        let converted = convert_with_smiles("Ethanol <smiles>CCO</smiles>", |text| format!("[{}]", text.trim()));
+        assert_eq!(converted, "[Ethanol]`CCO`");
+    }
+
+    #[test]
+    fn reconvert_keeps_presets() {
+        use crate::app::TypstScanData;
+        use crate::convert::TexSource;
+        use crate::worker::{convert_tex, OcrPreset};
+
+        let prose = "Let $x$ be \\emph{small}";
+        let table = "\\begin{tabular}{ll}\na & b \\\\\nc & d\n\\end{tabular}";
+        let defaults = TypstScanData::default();
+        let settings = defaults.conversion_settings();
+        let (table_typst, _) = convert_tex(&settings, OcrPreset::Table, table, "", TexSource::Text);
+        assert!(table_typst.starts_with("#table("));
+        let snip = |id: u128, tex: &str, typst: &str, preset: &str| {
+            serde_json::json!({
+                "id": uuid::Uuid::from_u128(id),
+                "title": "",
+                "local_image": "",
+                "original_image": "",
+                "rendered_image": "",
+                "tex": tex,
+                "typst": typst,
+                "typst_source": "Text",
+                "preset": preset,
+            })
+        };
+        let data: TypstScanData = serde_json::from_value(serde_json::json!({
+            "snip_items": [snip(1, prose, prose, "Text"), snip(2, table, &table_typst, "Table")],
+        }))
+        .unwrap();
+        assert!(data.reconvert_changes().is_empty());
+    }
+
+    #[test]
+    fn paste_target_migration() {
+        use crate::app::TypstScanData;
+
+        let mut data: TypstScanData = serde_json::from_str(
+            r#"{"bring_forward": true, "target_process_name": "scrcpy", "target_window_title": "NoteX3"}"#,
+        )
+        .unwrap();
+        assert!(data.paste_target.is_none());
+        data.migrate_paste_target();
+        let paste_target = data.paste_target.unwrap();
+        assert_eq!(paste_target.process_name, "scrcpy");
+        assert_eq!(paste_target.window_title, "NoteX3");
+    }
+
+    #[test]
+    fn tex_delimiters() {
+        use crate::convert::{wrap_tex, TexDelimiters};
+
+        assert_eq!(wrap_tex("\\( x^2 \\)", TexDelimiters::Dollars), "$x^2$");
+        assert_eq!(wrap_tex("$$a+b$$", TexDelimiters::Display), "\\[\na+b\n\\]");
+        assert_eq!(
+            wrap_tex("\\[\n\\frac{1}{2}\n\\]", TexDelimiters::Equation),
+            "\\begin{equation}\n\\frac{1}{2}\n\\end{equation}"
+        );
+        assert_eq!(wrap_tex("\\( x \\)", TexDelimiters::Raw), "\\( x \\)");
+        // mixed text and math stays as it is
+        assert_eq!(wrap_tex("Let \\( x \\) and \\( y \\)", TexDelimiters::Dollars), "Let \\( x \\) and \\( y \\)");
+    }
+
+    #[test]
+    fn screenshot_file_names() {
+        use crate::capture::sanitize_file_name;
+
+        assert_eq!(sanitize_file_name("E = mc^2"), "E _ mc_2");
+        assert_eq!(sanitize_file_name("a/b\\c:d"), "a_b_c_d");
+        assert_eq!(sanitize_file_name("  ***  "), "snip");
+    }
+
+    #[test]
+    fn fuzzy_search() {
+        use crate::search::fuzzy_score;
+
+        assert!(fuzzy_score("qf", "quadratic formula").is_some());
+        assert!(fuzzy_score("fq", "quadratic formula").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        // consecutive letters beat scattered ones
+        assert!(fuzzy_score("int", "integral") > fuzzy_score("int", "i n t"));
+    }
+
+    #[test]
+    fn output_templates() {
+        use crate::convert::{fill_template, TemplateFields};
+
+        let fields = TemplateFields {
+            typst: "x^2 {title}",
+            tex: "x^2",
+            unicode: "x²",
+            title: "Square",
+            date: "2025-01-01",
+            image_path: "/tmp/a.png",
+        };
+        assert_eq!(
+            fill_template("#figure($ {typst} $, caption: [{title}])", &fields),
+            "#figure($ x^2 {title} $, caption: [Square])"
+        );
+        assert_eq!(fill_template("{unknown} {date} {", &fields), "{unknown} 2025-01-01 {");
+    }
+
+    #[test]
+    fn unicode_math() {
+        use crate::convert::tex_to_unicode;
+
+        assert_eq!(tex_to_unicode("\\( \\sum_{i} x_{i}^{2} \\)"), "∑ᵢ xᵢ²");
+        assert_eq!(tex_to_unicode("\\frac{a+b}{2} \\leq \\sqrt{x+1}"), "(a+b)⁄2 ≤ √(x+1)");
+        assert_eq!(tex_to_unicode("\\alpha^{\\text{max}} + x^{q+1}"), "αᵐᵃˣ + x^(q+1)");
+        assert_eq!(tex_to_unicode("\\left( \\mathbb{R} \\right)"), "( ℝ )");
+    }
+
+    #[test]
+    fn pandoc_markdown() {
+        use crate::convert::tex_to_pandoc;
+
+        assert_eq!(
+            tex_to_pandoc("Let \\( x^2 \\) be\n\\[ a = b \\]\nthen"),
+            "Let $x^2$ be\n\n::: {.equation}\n$$\na = b\n$$\n:::\n\nthen"
+        );
+        assert_eq!(tex_to_pandoc("$$x$$"), "::: {.equation}\n$$\nx\n$$\n:::");
+        assert_eq!(tex_to_pandoc("no math \\( open"), "no math \\( open");
+    }
+
+    #[test]
+    fn latex_export() {
+        use crate::export::{latex, ExportSnip};
+
+        let snips = [
+            ExportSnip { title: "Square", tex: "\\[ x^2 \\]", typst: "x^2", image_path: String::new() },
+            ExportSnip { title: "", tex: "Prose with \\( y \\)", typst: "Prose with $y$", image_path: String::new() },
+        ];
+        assert_eq!(
+            latex(&snips, "begin\n{body}\nend"),
+            "begin\n% Square\n\\begin{equation}\nx^2\n\\end{equation}\n\nProse with \\( y \\)\nend"
+        );
+    }
+
+    #[test]
+    fn tex_source() {
+        use crate::convert::{conversion_source, TexSource};
+
+        assert_eq!(
+            conversion_source(TexSource::LatexStyled, "\\( x^{2} \\)", "x^2"),
+            ("\\[\nx^2\n\\]".to_string(), TexSource::LatexStyled)
+        );
+        assert_eq!(
+            conversion_source(TexSource::LatexStyled, "Prose \\( y \\)", ""),
+            ("Prose \\( y \\)".to_string(), TexSource::Text)
+        );
+        assert_eq!(conversion_source(TexSource::Text, "\\( z \\)", "z"), ("\\( z \\)".to_string(), TexSource::Text));
+    }
+
+    #[test]
+    fn org_export() {
+        use crate::export::{org, ExportSnip};
+
+        let snips = [
+            ExportSnip { title: "Square", tex: "\\[ x^2 \\]", typst: "x^2", image_path: "/tmp/a.png".to_string() },
+            ExportSnip { title: "", tex: "Prose with \\( y \\)", typst: "Prose with $y$", image_path: String::new() },
+        ];
+        assert_eq!(
+            org(&snips),
+            "* Square\n[[file:/tmp/a.png]]\n\n#+begin_export latex\n\\begin{equation}\nx^2\n\\end{equation}\n#+end_export\n\n\
+             * Snip\nProse with \\( y \\)\n\n"
+        );
+    }
+
+    #[test]
+    fn fenced_code_blocks() {
+        use crate::convert::fenced_code;
+
+        assert_eq!(fenced_code("x^2\n", "typst"), "```typst\nx^2\n```");
+        assert_eq!(fenced_code("a ```raw``` b", "typst"), "````typst\na ```raw``` b\n````");
+    }
+
+    fn test_snip(id: u128, tex: &str) -> crate::app::SnipItem {
+        serde_json::from_value(serde_json::json!({
+            "id": uuid::Uuid::from_u128(id),
+            "title": "",
+            "local_image": "",
+            "original_image": "",
+            "rendered_image": "",
+            "tex": tex,
+            "typst": "",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn snip_store_round_trip() {
+        use crate::store::SnipStore;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut snip_items = vec![test_snip(1, "a"), test_snip(2, "b")];
+        let mut store = SnipStore::at(dir.path().to_path_buf());
+        store.save(&snip_items, &[]);
+
+        // only snips marked as changed are written again
+        snip_items[0].tex = "edited".to_string();
+        snip_items[1].tex = "not marked".to_string();
+        store.mark_changed(snip_items[0].id());
+        store.save(&snip_items, &[]);
+
+        let (loaded, trash) = SnipStore::at(dir.path().to_path_buf()).load().unwrap();
+        assert_eq!(loaded.iter().map(|item| item.id()).collect::<Vec<_>>(), [1, 2].map(uuid::Uuid::from_u128));
+        assert_eq!(loaded[0].tex, "edited");
+        assert_eq!(loaded[1].tex, "b");
+        assert!(trash.is_empty());
+    }
+
+    #[test]
+    fn snip_store_delete() {
+        use crate::store::SnipStore;
+
+        let dir = tempfile::tempdir().unwrap();
+        let snip_items = vec![test_snip(1, "a"), test_snip(2, "b"), test_snip(3, "c")];
+        let mut store = SnipStore::at(dir.path().to_path_buf());
+        store.save(&snip_items, &[]);
+        store.save(&snip_items[1..], &[]);
+
+        assert!(!dir.path().join(format!("{}.json", uuid::Uuid::from_u128(1))).exists());
+        let (loaded, _) = SnipStore::at(dir.path().to_path_buf()).load().unwrap();
+        assert_eq!(loaded.iter().map(|item| item.id()).collect::<Vec<_>>(), [2, 3].map(uuid::Uuid::from_u128));
+    }
+
+    #[test]
+    fn snip_store_legacy_migration() {
+        use crate::app::TypstScanData;
+        use crate::store::SnipStore;
+
+        // older versions kept the snips in the app data, an empty store tells the app to move them over
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = SnipStore::at(dir.path().to_path_buf());
+        assert!(store.load().is_none());
+        let legacy_json = serde_json::json!({ "snip_items": [test_snip(1, "a"), test_snip(2, "b")] });
+        let legacy: TypstScanData = serde_json::from_value(legacy_json).unwrap();
+        store.save(&legacy.snip_items, &[]);
+
+        let (loaded, _) = SnipStore::at(dir.path().to_path_buf()).load().unwrap();
+        assert_eq!(loaded.iter().map(|item| item.tex.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        // the app data no longer carries the snips once they are in the store
+        let saved = serde_json::to_value(&legacy).unwrap();
+        assert!(saved.get("snip_items").is_none());
+    }
+
+    #[test]
+    fn backup_round_trip() {
+        use crate::backup::{read_backup, write_backup, Backup};
+
+        let backup = Backup {
+            settings: serde_json::json!({ "language": "En" }),
+            snip_items: Vec::new(),
+            trash: Vec::new(),
+        };
+
+        let plain = write_backup(&backup, None).unwrap();
+        assert_eq!(read_backup(&plain, "").unwrap().settings, backup.settings);
+        // the password is not needed for a file that is not encrypted
+        assert_eq!(read_backup(&plain, "unused").unwrap().settings, backup.settings);
+
+        let encrypted = write_backup(&backup, Some("correct horse")).unwrap();
+        assert!(encrypted.starts_with(b"age-encryption.org/v1"));
+        assert_eq!(read_backup(&encrypted, "correct horse").unwrap().settings, backup.settings);
+        assert!(read_backup(&encrypted, "wrong horse").is_err());
+        assert!(read_backup(&encrypted, "").is_err());
+    }
+
+    #[test]
+    fn app_lock_passcode() {
+        use crate::lock::AppLock;
+
+        let mut lock = AppLock::default();
+        assert!(!lock.is_enabled());
+        lock.set_passcode("1234");
+        assert!(lock.unlocks("1234"));
+        assert!(!lock.unlocks("4321"));
+        lock.disable();
+        assert!(lock.unlocks(""));
+    }
 }
@@ -0,0 +1,126 @@
+use crate::i18n::tr;
+use eframe::egui::{self, Color32, Theme, Visuals};
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum ThemePreset {
+    /// egui's light and dark themes, following the light/dark toggle.
+    Default,
+    SolarizedLight,
+    SolarizedDark,
+    HighContrast,
+    /// The built-in light or dark theme with the colors picked in the settings.
+    Custom,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 5] = [
+        ThemePreset::Default,
+        ThemePreset::SolarizedLight,
+        ThemePreset::SolarizedDark,
+        ThemePreset::HighContrast,
+        ThemePreset::Custom,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThemePreset::Default => tr("Default"),
+            ThemePreset::SolarizedLight => "Solarized Light",
+            ThemePreset::SolarizedDark => "Solarized Dark",
+            ThemePreset::HighContrast => tr("High Contrast"),
+            ThemePreset::Custom => tr("Custom"),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ThemeSettings {
+    pub preset: ThemePreset,
+    pub accent: Color32,
+    pub panel_background: Color32,
+    pub code_background: Color32,
+    pub code_text: Color32,
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        Self::from_preset(ThemePreset::Default)
+    }
+}
+
+impl ThemeSettings {
+    /// The colors of a preset, `Custom` starts from the dark theme.
+    pub fn from_preset(preset: ThemePreset) -> Self {
+        let (accent, panel_background, code_background, code_text) = match preset {
+            ThemePreset::Default | ThemePreset::Custom => {
+                let dark = Visuals::dark();
+                (dark.selection.bg_fill, dark.panel_fill, dark.extreme_bg_color, dark.text_color())
+            }
+            ThemePreset::SolarizedLight => (
+                Color32::from_rgb(0x26, 0x8b, 0xd2),
+                Color32::from_rgb(0xfd, 0xf6, 0xe3),
+                Color32::from_rgb(0xee, 0xe8, 0xd5),
+                Color32::from_rgb(0x65, 0x7b, 0x83),
+            ),
+            ThemePreset::SolarizedDark => (
+                Color32::from_rgb(0x26, 0x8b, 0xd2),
+                Color32::from_rgb(0x00, 0x2b, 0x36),
+                Color32::from_rgb(0x07, 0x36, 0x42),
+                Color32::from_rgb(0x93, 0xa1, 0xa1),
+            ),
+            ThemePreset::HighContrast => (Color32::from_rgb(0xff, 0xd7, 0x00), Color32::BLACK, Color32::BLACK, Color32::WHITE),
+        };
+        Self {
+            preset,
+            accent,
+            panel_background,
+            code_background,
+            code_text,
+        }
+    }
+
+    /// Color for the text of the TeX and Typst editors, `None` to keep the theme's text color.
+    pub fn code_text_color(&self) -> Option<Color32> {
+        (self.preset != ThemePreset::Default).then_some(self.code_text)
+    }
+
+    pub fn apply(&self, ctx: &egui::Context) {
+        match self.preset {
+            ThemePreset::Default => {
+                ctx.set_visuals_of(Theme::Dark, Visuals::dark());
+                ctx.set_visuals_of(Theme::Light, Visuals::light());
+            }
+            ThemePreset::Custom => {
+                for (theme, mut visuals) in [(Theme::Dark, Visuals::dark()), (Theme::Light, Visuals::light())] {
+                    self.colorize(&mut visuals);
+                    ctx.set_visuals_of(theme, visuals);
+                }
+            }
+            // presets look the same regardless of the light/dark toggle
+            ThemePreset::SolarizedLight | ThemePreset::SolarizedDark | ThemePreset::HighContrast => {
+                let mut visuals = if self.preset == ThemePreset::SolarizedLight {
+                    Visuals::light()
+                } else {
+                    Visuals::dark()
+                };
+                self.colorize(&mut visuals);
+                if self.preset == ThemePreset::HighContrast {
+                    visuals.override_text_color = Some(Color32::WHITE);
+                    visuals.widgets.noninteractive.bg_stroke.color = Color32::WHITE;
+                }
+                ctx.set_visuals_of(Theme::Dark, visuals.clone());
+                ctx.set_visuals_of(Theme::Light, visuals);
+            }
+        }
+    }
+
+    fn colorize(&self, visuals: &mut Visuals) {
+        visuals.selection.bg_fill = self.accent;
+        visuals.hyperlink_color = self.accent;
+        visuals.widgets.hovered.bg_stroke.color = self.accent;
+        visuals.panel_fill = self.panel_background;
+        visuals.window_fill = self.panel_background;
+        visuals.extreme_bg_color = self.code_background;
+        visuals.code_bg_color = self.code_background;
+    }
+}
@@ -0,0 +1,67 @@
+use crate::worker::{self, TlsSettings};
+use eframe::egui;
+use serde::Deserialize;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/Unpredictability/TypstScan/releases/latest";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Release {
+    pub tag_name: String,
+    pub html_url: String,
+    #[serde(default)]
+    pub body: String,
+}
+
+impl Release {
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+}
+
+/// Look up the latest release in the background, the receiver gets it only when it is newer
+/// than the running version.
+pub fn check_for_update(ctx: egui::Context, tls: TlsSettings) -> Receiver<Release> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let release = worker::http_client(&tls)
+            .get(RELEASES_URL)
+            .header("User-Agent", "TypstScan")
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json::<Release>());
+        match release {
+            Ok(release) if is_newer(release.version(), env!("CARGO_PKG_VERSION")) => {
+                println!("Update available: {}", release.tag_name);
+                let _ = sender.send(release);
+                ctx.request_repaint();
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to check for updates: {:?}", e),
+        }
+    });
+    receiver
+}
+
+/// Compare dotted version numbers, ignoring pre-release suffixes.
+pub fn is_newer(version: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    let (version, current) = (parse(version), parse(current));
+    for i in 0..version.len().max(current.len()) {
+        let (a, b) = (version.get(i).copied().unwrap_or(0), current.get(i).copied().unwrap_or(0));
+        if a != b {
+            return a > b;
+        }
+    }
+    false
+}
@@ -1,28 +1,1016 @@
-use crate::app::{ClipboardMode, TypstScanData};
+use crate::app::{ClipboardMode, ReplaceRule, SnipItem, TypstScanData};
+use crate::capture::{self, CaptureRegion, Margins};
+use crate::convert::{
+    self, Normalization, OutputCleanup, OutputTemplates, TemplateFields, TexDelimiters, TexSource, TypstFormat,
+};
+use crate::folders;
+use crate::i18n::tr;
+use crate::platform::{self, PasteTarget};
 use arboard::Clipboard;
+use eframe::egui;
 use reqwest::blocking::multipart::Part;
 use reqwest::blocking::{multipart, Client};
 use reqwest::header;
 use serde::Deserialize;
 use serde_json::json;
-use std::process::Command;
-use std::sync::mpsc::{Receiver, Sender};
+use sha2::{Digest, Sha256};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 use tex2typst_rs::text_and_tex2typst;
 use uuid::Uuid;
 
-#[cfg(target_os = "windows")]
-use screen_snip;
-
 pub fn start_worker(
     task_receiver: Receiver<SnipTask>,
-    result_sender: Sender<TaskResult>,
+    event_sender: Sender<WorkerEvent>,
     app_data: Arc<Mutex<TypstScanData>>,
+    ctx: egui::Context,
 ) -> thread::JoinHandle<()> {
+    let event_sender = EventSender { sender: event_sender, ctx };
     thread::spawn(move || {
-        // Options payload (similar to the Swift `options` dictionary)
-        let options_payload = json!({
+        let mut tls = app_data.lock().map(|app_data| app_data.tls.clone()).unwrap_or_default();
+        let mut client = http_client(&tls);
+
+        // fingerprint of the previous periodic frame, unchanged frames are not sent for OCR
+        let mut last_periodic_frame = None;
+        let upload_pool = UploadPool::new(&event_sender);
+        let mut last_network_check = Instant::now();
+
+        loop {
+            if last_network_check.elapsed() >= NETWORK_CHECK_INTERVAL {
+                last_network_check = Instant::now();
+                upload_pool.retry_offline(&client, &app_data, &event_sender);
+            }
+            let snip_task = match task_receiver.recv_timeout(NETWORK_CHECK_INTERVAL) {
+                Ok(snip_task) => {
+                    WAITING_TASKS.fetch_sub(1, Ordering::Relaxed);
+                    snip_task
+                }
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            let current_tls = app_data.lock().map(|app_data| app_data.tls.clone()).unwrap_or_default();
+            if current_tls != tls {
+                tls = current_tls;
+                client = http_client(&tls);
+            }
+            let periodic = snip_task.kind == TaskKind::Periodic;
+            if periodic && app_data.lock().is_ok_and(|app_data| app_data.quota_low()) {
+                continue;
+            }
+            // nothing is on screen to wait for when the image comes from the clipboard or the canvas
+            let off_screen = matches!(snip_task.kind, TaskKind::ClipboardImage | TaskKind::Drawing(_));
+            if let Ok(app_data) = app_data.lock() {
+                if app_data.bring_forward && !periodic && !off_screen {
+                    platform::bring_forward(&app_data.target_process_name, &app_data.target_window_title);
+                }
+            }
+
+            let capture_delay = app_data.lock().map(|app_data| app_data.capture_delay_secs).unwrap_or(0);
+            if capture_delay > 0 && !periodic && !off_screen {
+                for remaining in (1..=capture_delay).rev() {
+                    event_sender.send(WorkerEvent::Countdown(remaining));
+                    thread::sleep(Duration::from_secs(1));
+                }
+                event_sender.send(WorkerEvent::Countdown(0));
+                // give the countdown window time to close before capturing
+                thread::sleep(Duration::from_millis(200));
+            }
+
+            let hide_window = app_data.lock().map(|app_data| app_data.hide_when_capturing).unwrap_or(false)
+                && matches!(
+                    snip_task.kind,
+                    TaskKind::Interactive | TaskKind::Region(_) | TaskKind::RepeatRegion | TaskKind::ActiveWindow
+                );
+            if hide_window {
+                event_sender.send(WorkerEvent::HideWindow);
+                // wait for the minimize animation
+                thread::sleep(Duration::from_millis(400));
+            }
+
+            let capture_started = Instant::now();
+            let mut captured_region = None;
+            let screenshot = match &snip_task.kind {
+                TaskKind::Interactive => capture::get_screenshot(),
+                TaskKind::Region(region) => {
+                    // let the selection overlay disappear before grabbing the screen
+                    thread::sleep(Duration::from_millis(300));
+                    captured_region = Some(*region);
+                    capture::capture_region(region)
+                }
+                TaskKind::SavedWindow => {
+                    let target = app_data
+                        .lock()
+                        .ok()
+                        .map(|app_data| (app_data.capture_process_name.clone(), app_data.capture_window_title.clone()));
+                    match target {
+                        Some((process_name, window_title)) if !(process_name.is_empty() && window_title.is_empty()) => {
+                            capture::capture_window(&process_name, &window_title, &Margins::default())
+                        }
+                        _ => {
+                            eprintln!("No capture window configured");
+                            None
+                        }
+                    }
+                }
+                TaskKind::Scrcpy => {
+                    let settings = app_data
+                        .lock()
+                        .ok()
+                        .map(|app_data| (app_data.scrcpy_window_title.clone(), app_data.scrcpy_crop));
+                    settings.and_then(|(window_title, crop)| {
+                        platform::bring_forward(SCRCPY_PROCESS_NAME, &window_title);
+                        // wait for the mirrored screen to be drawn on top
+                        thread::sleep(Duration::from_millis(200));
+                        capture::capture_window(SCRCPY_PROCESS_NAME, &window_title, &crop)
+                    })
+                }
+                TaskKind::ActiveWindow => match platform::active_window_region() {
+                    Some(region) => {
+                        captured_region = Some(region);
+                        capture::capture_region(&region)
+                    }
+                    None => {
+                        eprintln!("Could not determine the active window");
+                        None
+                    }
+                },
+                TaskKind::ClipboardImage => capture::clipboard_image(),
+                TaskKind::Drawing(strokes) => capture::render_strokes(strokes),
+                TaskKind::RepeatRegion | TaskKind::Periodic => {
+                    let last_region = app_data.lock().ok().and_then(|app_data| app_data.last_region);
+                    match last_region {
+                        Some(region) => capture::capture_region(&region),
+                        None => {
+                            eprintln!("No previous region to repeat, capture a region first");
+                            None
+                        }
+                    }
+                }
+            };
+
+            if hide_window {
+                event_sender.send(WorkerEvent::RestoreWindow);
+            }
+
+            let screenshot = match screenshot {
+                Some(screenshot_path) if periodic => {
+                    let frame = std::fs::read(&screenshot_path).ok().map(|data| {
+                        let mut hasher = DefaultHasher::new();
+                        data.hash(&mut hasher);
+                        hasher.finish()
+                    });
+                    if frame.is_some() && frame == last_periodic_frame {
+                        let _ = std::fs::remove_file(&screenshot_path);
+                        continue;
+                    }
+                    last_periodic_frame = frame;
+                    Some(screenshot_path)
+                }
+                screenshot => screenshot,
+            };
+
+            let Some(screenshot_path) = screenshot else {
+                continue;
+            };
+            // measured before cropping changes the size
+            let pixel_scale = if off_screen {
+                1.0
+            } else {
+                capture::pixel_scale(&screenshot_path, captured_region.as_ref())
+            };
+            let smart_crop = app_data.lock().map(|app_data| app_data.smart_crop).unwrap_or(false);
+            if smart_crop && !off_screen {
+                capture::smart_crop(&screenshot_path);
+            }
+            let screenshot_settings = app_data.lock().map(|app_data| app_data.screenshot.clone()).unwrap_or_default();
+            let (screenshot_path, image_data, mime) = match capture::prepare_upload(&screenshot_path, &screenshot_settings) {
+                Ok(upload) => upload,
+                Err(error) => {
+                    eprintln!("Error: {}", error);
+                    event_sender.send(WorkerEvent::Failed(error));
+                    continue;
+                }
+            };
+            let upload = Upload {
+                snip_task,
+                screenshot_path,
+                image_data,
+                mime,
+                captured_region,
+                pixel_scale,
+                capture_ms: capture_started.elapsed().as_millis() as u64,
+            };
+            upload_pool.submit(upload, &client, &app_data, &event_sender);
+        }
+    })
+}
+
+/// A capture ready to be sent to Mathpix.
+struct Upload {
+    snip_task: SnipTask,
+    screenshot_path: PathBuf,
+    image_data: Vec<u8>,
+    mime: &'static str,
+    captured_region: Option<CaptureRegion>,
+    pixel_scale: f32,
+    capture_ms: u64,
+}
+
+/// What happens to a capture when the queue is full.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum QueueFullPolicy {
+    /// Make room by dropping the capture that waited longest.
+    DropOldest,
+    /// Drop the new capture.
+    Reject,
+}
+
+/// How many requests run at once and how many captures may wait for one.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct WorkerLimits {
+    pub max_concurrent: usize,
+    pub max_queued: usize,
+    pub when_full: QueueFullPolicy,
+}
+
+impl Default for WorkerLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 1,
+            max_queued: 20,
+            when_full: QueueFullPolicy::Reject,
+        }
+    }
+}
+
+/// Sends events to the UI and wakes it up, so they are handled without waiting for user input.
+#[derive(Clone)]
+struct EventSender {
+    sender: Sender<WorkerEvent>,
+    ctx: egui::Context,
+}
+
+impl EventSender {
+    fn send(&self, event: WorkerEvent) {
+        // the receiver only goes away when the app is closing
+        let _ = self.sender.send(event);
+        self.ctx.request_repaint();
+    }
+}
+
+/// A result to put on the clipboard, paste or send to the editor.
+struct Delivery {
+    sequence: u64,
+    text: String,
+    editor_command: Option<String>,
+    paste_target: Option<PasteTarget>,
+}
+
+/// Delivers results one at a time on a thread of its own. Uploads finish in any order, so a
+/// result older than one already delivered is dropped instead of replacing it on the clipboard.
+#[derive(Clone)]
+struct Deliverer {
+    sender: Sender<Delivery>,
+}
+
+impl Deliverer {
+    fn start(event_sender: EventSender) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel::<Delivery>();
+        thread::spawn(move || {
+            let mut newest = None;
+            for delivery in receiver {
+                if newest.is_some_and(|newest| delivery.sequence < newest) {
+                    continue;
+                }
+                newest = Some(delivery.sequence);
+                if let Err(error) = apply_delivery(delivery) {
+                    eprintln!("Error: {}", error);
+                    event_sender.send(WorkerEvent::Failed(error));
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    fn deliver(&self, delivery: Delivery) {
+        let _ = self.sender.send(delivery);
+    }
+}
+
+fn apply_delivery(delivery: Delivery) -> Result<(), String> {
+    if let Some(command) = &delivery.editor_command {
+        return platform::send_to_editor(command, &delivery.text)
+            .map_err(|e| format!("Failed to send the result to the editor: {}", e));
+    }
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(delivery.text))
+        .map_err(|e| format!("Failed to copy the result: {}", e))?;
+    if let Some(paste_target) = &delivery.paste_target {
+        platform::paste_into(paste_target);
+    }
+    Ok(())
+}
+
+/// Add an upload to a queue, applying the queue limit.
+fn enqueue(queue: &mut VecDeque<Upload>, upload: Upload, limits: &WorkerLimits, event_sender: &EventSender) {
+    if queue.len() >= limits.max_queued.max(1) {
+        match limits.when_full {
+            QueueFullPolicy::DropOldest => {
+                queue.pop_front();
+                event_sender.send(WorkerEvent::Failed(tr("The queue is full, dropped the oldest capture").to_string()));
+            }
+            QueueFullPolicy::Reject => {
+                event_sender.send(WorkerEvent::Failed(tr("The queue is full, dropped the capture").to_string()));
+                return;
+            }
+        }
+    }
+    queue.push_back(upload);
+}
+
+#[derive(Default)]
+struct PendingUploads {
+    uploads: VecDeque<Upload>,
+    /// Threads sending uploads.
+    active: usize,
+    /// Set when a request could not reach Mathpix, cleared by the next one that gets through.
+    offline: bool,
+    /// Captures waiting for the network, sent before any newer one once it is back.
+    offline_uploads: VecDeque<Upload>,
+}
+
+impl PendingUploads {
+    fn publish_depth(&self) {
+        PENDING_UPLOADS.store(self.uploads.len() + self.active + self.offline_uploads.len(), Ordering::Relaxed);
+    }
+
+    /// Keep an upload that could not reach Mathpix, along with those waiting behind it, until the network is back.
+    fn go_offline(&mut self, upload: Upload, event_sender: &EventSender) {
+        self.offline = true;
+        // it was taken before anything still waiting, a periodic frame is stale by the time the network is back
+        if upload.snip_task.kind != TaskKind::Periodic {
+            self.offline_uploads.push_front(upload);
+        }
+        let waiting = std::mem::take(&mut self.uploads);
+        self.offline_uploads.extend(waiting.into_iter().filter(|upload| upload.snip_task.kind != TaskKind::Periodic));
+        println!("Offline, {} captures waiting", self.offline_uploads.len());
+        event_sender.send(WorkerEvent::Network { online: false, queued: self.offline_uploads.len() });
+    }
+
+    /// A request got through, send the captures that waited for the network first.
+    fn go_online(&mut self, event_sender: &EventSender) {
+        if !self.offline {
+            return;
+        }
+        self.offline = false;
+        let mut uploads = std::mem::take(&mut self.offline_uploads);
+        uploads.append(&mut self.uploads);
+        self.uploads = uploads;
+        event_sender.send(WorkerEvent::Network { online: true, queued: 0 });
+    }
+}
+
+/// Sends uploads on up to `max_concurrent` threads, which exit once the queue is empty.
+struct UploadPool {
+    pending: Arc<Mutex<PendingUploads>>,
+    deliverer: Deliverer,
+}
+
+impl UploadPool {
+    fn new(event_sender: &EventSender) -> Self {
+        Self {
+            pending: Arc::default(),
+            deliverer: Deliverer::start(event_sender.clone()),
+        }
+    }
+
+    fn submit(
+        &self,
+        upload: Upload,
+        client: &Client,
+        app_data: &Arc<Mutex<TypstScanData>>,
+        event_sender: &EventSender,
+    ) {
+        let limits = app_data.lock().map(|app_data| app_data.worker_limits.clone()).unwrap_or_default();
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        if pending.offline {
+            // a periodic frame is stale by the time the network is back
+            if upload.snip_task.kind != TaskKind::Periodic {
+                enqueue(&mut pending.offline_uploads, upload, &limits, event_sender);
+            }
+            pending.publish_depth();
+            event_sender.send(WorkerEvent::Network { online: false, queued: pending.offline_uploads.len() });
+            return;
+        }
+        enqueue(&mut pending.uploads, upload, &limits, event_sender);
+        if pending.active >= limits.max_concurrent.max(1) {
+            pending.publish_depth();
+            return;
+        }
+        self.spawn_sender(&mut pending, client, app_data, event_sender);
+    }
+
+    /// Send the oldest capture waiting for the network again, its request tells whether the network is back.
+    fn retry_offline(&self, client: &Client, app_data: &Arc<Mutex<TypstScanData>>, event_sender: &EventSender) {
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        // one request at a time, so a network that stays down does not pile up timeouts
+        if !pending.offline || pending.active > 0 {
+            return;
+        }
+        let Some(upload) = pending.offline_uploads.pop_front() else {
+            // nothing waits for the network, the next capture finds out whether it is back
+            pending.go_online(event_sender);
+            return;
+        };
+        pending.uploads.push_back(upload);
+        self.spawn_sender(&mut pending, client, app_data, event_sender);
+    }
+
+    fn spawn_sender(
+        &self,
+        pending: &mut PendingUploads,
+        client: &Client,
+        app_data: &Arc<Mutex<TypstScanData>>,
+        event_sender: &EventSender,
+    ) {
+        pending.active += 1;
+        pending.publish_depth();
+
+        let pending = self.pending.clone();
+        let client = client.clone();
+        let app_data = app_data.clone();
+        let event_sender = event_sender.clone();
+        let deliverer = self.deliverer.clone();
+        thread::spawn(move || loop {
+            // taken under the same lock `submit` counts the threads with, so no upload is left behind
+            let upload = match pending.lock() {
+                Ok(mut pending) => {
+                    let upload = pending.uploads.pop_front();
+                    if upload.is_none() {
+                        pending.active -= 1;
+                    }
+                    pending.publish_depth();
+                    upload
+                }
+                Err(_) => None,
+            };
+            let Some(upload) = upload else {
+                break;
+            };
+            let unreachable = process_upload(&client, &app_data, &event_sender, &deliverer, upload);
+            if let Ok(mut pending) = pending.lock() {
+                match unreachable {
+                    Some(upload) => pending.go_offline(upload, &event_sender),
+                    None => pending.go_online(&event_sender),
+                }
+                pending.publish_depth();
+            }
+        });
+    }
+}
+
+/// TLS options for networks that intercept HTTPS with their own certificates.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct TlsSettings {
+    /// PEM file with extra root certificates to trust.
+    pub ca_bundle: Option<PathBuf>,
+    /// Skip certificate checks, which lets anyone on the network read and change the traffic.
+    pub accept_invalid_certs: bool,
+}
+
+pub fn http_client(tls: &TlsSettings) -> Client {
+    let mut builder = Client::builder()
+        .pool_idle_timeout(None)
+        .danger_accept_invalid_certs(tls.accept_invalid_certs);
+    if let Some(path) = &tls.ca_bundle {
+        let certificates = std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|pem| reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| e.to_string()));
+        match certificates {
+            Ok(certificates) => {
+                for certificate in certificates {
+                    builder = builder.add_root_certificate(certificate);
+                }
+            }
+            Err(e) => eprintln!("Failed to load certificates from {:?}: {}", path, e),
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        eprintln!("Failed to apply the TLS settings: {:?}", e);
+        Client::new()
+    })
+}
+
+/// A request that failed, telling a network that is down apart from an error Mathpix returned.
+enum RequestError {
+    /// Mathpix could not be connected to or did not answer in time.
+    Unreachable(String),
+    Failed(String),
+}
+
+/// Recognize an upload, or reuse the result of the same image, and deliver the result.
+/// Returns the upload when Mathpix could not be reached, to send it again once the network is back.
+fn process_upload(
+    client: &Client,
+    app_data: &Arc<Mutex<TypstScanData>>,
+    event_sender: &EventSender,
+    deliverer: &Deliverer,
+    upload: Upload,
+) -> Option<Upload> {
+    let Upload {
+        snip_task,
+        screenshot_path,
+        image_data,
+        mime,
+        captured_region,
+        pixel_scale,
+        capture_ms,
+    } = upload;
+    let api_key = app_data.lock().map(|app_data| app_data.mathpix_api_key.clone()).unwrap_or_default();
+    let headers = request_headers(&api_key);
+    let preset = snip_task
+        .preset
+        .unwrap_or_else(|| app_data.lock().map(|app_data| app_data.ocr_preset).unwrap_or(OcrPreset::Equation));
+    let (extra_formats, upload_folder) = app_data
+        .lock()
+        .map(|app_data| (app_data.extra_formats, app_data.folder_for(preset).to_string()))
+        .unwrap_or_default();
+    let mut options_payload = preset.options_payload(extra_formats);
+    if !upload_folder.is_empty() {
+        options_payload["folder_id"] = json!(upload_folder);
+    }
+    // stored with the snips, so it has to stay the same across builds
+    let image_hash: String = Sha256::digest(&image_data).iter().map(|byte| format!("{:02x}", byte)).collect();
+    let cached = app_data
+        .lock()
+        .ok()
+        .filter(|app_data| app_data.reuse_results)
+        .and_then(|app_data| app_data.cached_snip(&image_hash).map(cached_result));
+    let request_started = Instant::now();
+    // a drawing is uploaded as the image rendered from its strokes, the strokes endpoint needs v3 credentials
+    let recognized = match cached {
+        Some(cached) => Ok(cached),
+        None => recognize(client, &headers, &options_payload, &image_data, mime),
+    }
+    .and_then(|mathpix_result| wait_for_snip(client, &headers, mathpix_result).map_err(RequestError::Failed));
+    let mathpix_result = match recognized {
+        Ok(mathpix_result) => mathpix_result,
+        Err(RequestError::Unreachable(error)) => {
+            eprintln!("Error: {}", error);
+            return Some(Upload {
+                snip_task,
+                screenshot_path,
+                image_data,
+                mime,
+                captured_region,
+                pixel_scale,
+                capture_ms,
+            });
+        }
+        Err(RequestError::Failed(error)) => {
+            eprintln!("Error: {}", error);
+            event_sender.send(WorkerEvent::RecognitionFailed {
+                id: snip_task.id,
+                local_image: screenshot_path.to_string_lossy().to_string(),
+                error,
+            });
+            return None;
+        }
+    };
+    let request_ms = request_started.elapsed().as_millis() as u64;
+    let ocr_ms = mathpix_result.time_ms.ocr_api_response;
+    let result = deliver(&snip_task, app_data, &screenshot_path, mathpix_result, captured_region, preset, deliverer)
+        .map(|result| TaskResult {
+            pixel_scale,
+            image_hash,
+            timings: SnipTimings {
+                capture_ms,
+                upload_ms: request_ms.saturating_sub(ocr_ms),
+                ocr_ms,
+                ..result.timings
+            },
+            ..result
+        });
+    match result {
+        Ok(result) => {
+            event_sender.send(WorkerEvent::Result(result));
+        }
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            event_sender.send(WorkerEvent::Failed(error));
+        }
+    }
+    None
+}
+
+/// Upload a screenshot to Mathpix for OCR.
+fn recognize(
+    client: &Client,
+    headers: &header::HeaderMap,
+    options_payload: &serde_json::Value,
+    image_data: &[u8],
+    mime: &str,
+) -> Result<MathpixResult, RequestError> {
+    let file_name = if mime == "image/jpeg" { "image.jpg" } else { "image.png" };
+    let form = multipart::Form::new()
+        .part(
+            "file",
+            Part::bytes(image_data.to_vec())
+                .file_name(file_name)
+                .mime_str(mime)
+                .map_err(|e| RequestError::Failed(e.to_string()))?,
+        )
+        .part(
+            "options_json",
+            Part::text(options_payload.to_string())
+                .mime_str("application/json")
+                .map_err(|e| RequestError::Failed(e.to_string()))?,
+        );
+
+    let response = client.post(SNIP_API_URL).headers(headers.clone()).multipart(form).send().map_err(|e| {
+        let error = format!("Request to Mathpix failed: {}", e);
+        if e.is_connect() || e.is_timeout() {
+            RequestError::Unreachable(error)
+        } else {
+            RequestError::Failed(error)
+        }
+    })?;
+    let status = response.status();
+    let body = response.text().map_err(|e| RequestError::Failed(format!("Request to Mathpix failed: {}", e)))?;
+    if crate::verbose() {
+        println!("Mathpix response: {}", body);
+    }
+    if let Some(error) = api_error(status, &body) {
+        return Err(RequestError::Failed(error));
+    }
+    serde_json::from_str(&body).map_err(|e| RequestError::Failed(format!("Unexpected response from Mathpix: {}", e)))
+}
+
+/// An earlier result for the same image, which costs no API call and leaves the usage counts alone.
+fn cached_result(snip_item: &SnipItem) -> MathpixResult {
+    let metadata = snip_item.metadata.clone().unwrap_or_default();
+    let image = |url: &str| ImageDetails {
+        fullsize: UrlDetail { url: url.to_string() },
+        thumbnail: UrlDetail::default(),
+    };
+    MathpixResult {
+        id: metadata.mathpix_id,
+        text: snip_item.tex.clone(),
+        latex: Some(snip_item.latex_styled.clone()).filter(|latex| !latex.is_empty()),
+        html: Some(snip_item.html.clone()).filter(|html| !html.is_empty()),
+        mmd: Some(snip_item.mmd.clone()).filter(|mmd| !mmd.is_empty()),
+        title: snip_item.title.clone(),
+        images: Images {
+            original: ImageDetails {
+                thumbnail: UrlDetail { url: snip_item.thumbnail.clone() },
+                ..image(&snip_item.original_image)
+            },
+            rendered: image(&snip_item.rendered_image),
+        },
+        confidence: snip_item.confidence.unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+/// Poll a snip Mathpix is still processing until its text is complete.
+fn wait_for_snip(client: &Client, headers: &header::HeaderMap, mut mathpix_result: MathpixResult) -> Result<MathpixResult, String> {
+    let started = Instant::now();
+    while mathpix_result.status == "in_progress" {
+        if started.elapsed() > SNIP_POLL_TIMEOUT {
+            return Err(tr("Mathpix did not finish the snip in time").to_string());
+        }
+        thread::sleep(SNIP_POLL_INTERVAL);
+        let response = client
+            .get(format!("{}/{}", SNIPS_API_URL, mathpix_result.id))
+            .headers(headers.clone())
+            .send()
+            .map_err(|e| format!("Request to Mathpix failed: {}", e))?;
+        let status = response.status();
+        let body = response.text().map_err(|e| format!("Request to Mathpix failed: {}", e))?;
+        if let Some(error) = api_error(status, &body) {
+            return Err(error);
+        }
+        mathpix_result = serde_json::from_str(&body).map_err(|e| format!("Unexpected response from Mathpix: {}", e))?;
+    }
+    Ok(mathpix_result)
+}
+
+/// Ask Mathpix for a public link to a snip in the background, for people without the app.
+pub fn request_share_link(
+    ctx: egui::Context,
+    api_key: String,
+    tls: TlsSettings,
+    mathpix_id: String,
+) -> Receiver<Result<String, String>> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(share_link(&http_client(&tls), &request_headers(&api_key), &mathpix_id));
+        ctx.request_repaint();
+    });
+    receiver
+}
+
+fn share_link(client: &Client, headers: &header::HeaderMap, mathpix_id: &str) -> Result<String, String> {
+    let response = client
+        .post(format!("{}/{}/share", SNIPS_API_URL, mathpix_id))
+        .headers(headers.clone())
+        .send()
+        .map_err(|e| format!("Request to Mathpix failed: {}", e))?;
+    let status = response.status();
+    let body = response.text().map_err(|e| format!("Request to Mathpix failed: {}", e))?;
+    if let Some(error) = api_error(status, &body) {
+        return Err(error);
+    }
+    let json: serde_json::Value =
+        serde_json::from_str(&body).map_err(|e| format!("Unexpected response from Mathpix: {}", e))?;
+    json["url"]
+        .as_str()
+        .or(json["share_url"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| tr("Mathpix did not return a share link").to_string())
+}
+
+/// A readable message for a failed request, `None` when the response is a result.
+/// Mathpix reports some failures with a success status and an `error` in the body.
+fn api_error(status: reqwest::StatusCode, body: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    let error_id = json["error_info"]["id"].as_str().unwrap_or_default();
+    let error = json["error"].as_str().or(json["error_info"]["message"].as_str());
+    if status.is_success() && error.is_none() && error_id.is_empty() {
+        return None;
+    }
+    let lowercase = body.to_lowercase();
+    let message = match (status.as_u16(), error_id) {
+        (401 | 403, _) | (_, "http_unauthorized") => tr("Mathpix rejected the API key, check it in the settings").to_string(),
+        (429, _) | (_, "http_max_requests") => tr("Too many requests to Mathpix, wait a moment and try again").to_string(),
+        (402, _) => tr("The Mathpix snip quota is used up").to_string(),
+        (413, _) | (_, "image_max_size" | "sys_request_too_large") => {
+            tr("The image is too large for Mathpix, capture a smaller region").to_string()
+        }
+        _ if lowercase.contains("quota") || lowercase.contains("limit exceeded") => {
+            tr("The Mathpix snip quota is used up").to_string()
+        }
+        (_, "image_no_content") => tr("Mathpix found nothing to recognize in the image").to_string(),
+        _ => match error {
+            Some(error) => format!("{} {}", tr("Mathpix reported an error:"), error),
+            None => format!("{} {}", tr("Mathpix returned"), status),
+        },
+    };
+    Some(message)
+}
+
+/// The settings that decide how recognized TeX becomes Typst.
+pub(crate) struct ConversionSettings<'a> {
+    pub normalizations: &'a [Normalization],
+    pub replace_rules: &'a [ReplaceRule],
+    pub typst_format: &'a TypstFormat,
+    pub cleanup: &'a OutputCleanup,
+}
+
+/// Typst for a recognized text under a preset and the output it was converted from. Fresh results and
+/// snips converted again in the UI both go through here, so they come out the same.
+pub(crate) fn convert_tex(
+    settings: &ConversionSettings,
+    preset: OcrPreset,
+    text: &str,
+    latex_styled: &str,
+    tex_source: TexSource,
+) -> (String, TexSource) {
+    // only formulas get a latex_styled, tables and chemistry need the text
+    let tex_source = if preset == OcrPreset::Equation { tex_source } else { TexSource::Text };
+    let (source_tex, typst_source) = convert::conversion_source(tex_source, text, latex_styled);
+    // prose is kept as Mathpix read it
+    if preset == OcrPreset::Text {
+        return (text.to_string(), typst_source);
+    }
+    let tex = convert::normalize(&source_tex, settings.normalizations);
+    let to_typst = |tex: &str| text_and_tex2typst(tex).unwrap_or_else(|e| format!("Error: {:?}", e));
+    let mut typst = match preset {
+        OcrPreset::Equation | OcrPreset::Document | OcrPreset::Text => to_typst(&tex),
+        OcrPreset::Table => convert::tabular_to_typst(&tex, to_typst).unwrap_or_else(|| to_typst(&tex)),
+        OcrPreset::Chemistry => convert::convert_with_smiles(&tex, to_typst),
+    };
+    for rule in settings.replace_rules.iter() {
+        typst = typst.replace(&rule.pattern, &rule.replacement);
+    }
+    (settings.cleanup.apply(&settings.typst_format.apply(&typst)), typst_source)
+}
+
+/// Convert the OCR result and hand it to the deliverer to copy it according to the clipboard mode
+/// and auto paste it.
+fn deliver(
+    snip_task: &SnipTask,
+    app_data: &Arc<Mutex<TypstScanData>>,
+    screenshot_path: &Path,
+    mut mathpix_result: MathpixResult,
+    captured_region: Option<CaptureRegion>,
+    preset: OcrPreset,
+    deliverer: &Deliverer,
+) -> Result<TaskResult, String> {
+    let conversion_started = Instant::now();
+    let cleanup = app_data.lock().map(|app_data| app_data.output_cleanup.clone()).unwrap_or_default();
+    mathpix_result.text = cleanup.apply(&mathpix_result.text);
+    // sent under this name when it was asked for in `formats`
+    if mathpix_result.latex.is_none() {
+        mathpix_result.latex = mathpix_result.latex_styled.take();
+    }
+    let mut typst_replaced = String::new();
+    let mut typst_source = TexSource::Text;
+    let mut paste_target = None;
+    let mut clipboard_mode = snip_task.clipboard_mode.unwrap_or(ClipboardMode::CopyTypst);
+    let mut tex_delimiters = TexDelimiters::Raw;
+    let mut templates = OutputTemplates::default();
+    let mut editor_command = None;
+    if let Ok(app_data) = app_data.lock() {
+        (typst_replaced, typst_source) = convert_tex(
+            &app_data.conversion_settings(),
+            preset,
+            &mathpix_result.text,
+            mathpix_result.latex.as_deref().unwrap_or_default(),
+            app_data.tex_source,
+        );
+
+        clipboard_mode = snip_task.clipboard_mode.unwrap_or(app_data.clipboard_mode);
+        tex_delimiters = app_data.tex_delimiters;
+        templates = app_data.output_templates.clone();
+        if app_data.send_to_editor.is_active() && clipboard_mode != ClipboardMode::Continuous {
+            editor_command = Some(app_data.send_to_editor.command.clone());
+        } else if app_data.auto_paste && clipboard_mode != ClipboardMode::Continuous {
+            paste_target = Some(app_data.paste_target.clone().unwrap_or_default());
+        }
+    }
+
+    let conversion_ms = conversion_started.elapsed().as_millis() as u64;
+
+    let template = match clipboard_mode {
+        ClipboardMode::Continuous => {
+            // do nothing, let the UI thread handle it
+            None
+        }
+        ClipboardMode::CopyTeX => Some(&templates.tex),
+        ClipboardMode::CopyTypst => Some(&templates.typst),
+        ClipboardMode::CopyUnicode => Some(&templates.unicode),
+    };
+    let copied = match template {
+        // prose is copied as recognized, it is not a template
+        Some(_) if preset == OcrPreset::Text => Some(mathpix_result.text.clone()),
+        Some(template) => {
+            let fields = TemplateFields {
+                typst: &typst_replaced,
+                tex: &convert::copy_tex(&mathpix_result.text, &cleanup, tex_delimiters),
+                unicode: &convert::tex_to_unicode(&mathpix_result.text),
+                title: &mathpix_result.title,
+                date: &chrono::Local::now().format("%Y-%m-%d").to_string(),
+                image_path: &screenshot_path.to_string_lossy(),
+            };
+            Some(convert::fill_template(template, &fields))
+        }
+        None => None,
+    };
+    if let Some(text) = &copied {
+        deliverer.deliver(Delivery {
+            sequence: snip_task.sequence,
+            text: text.clone(),
+            editor_command: editor_command.clone(),
+            paste_target,
+        });
+    }
+    // the editor inserts the result itself, the clipboard stays as it was
+    let copied = if editor_command.is_some() { None } else { copied };
+
+    Ok(TaskResult {
+        id: snip_task.id,
+        sequence: snip_task.sequence,
+        local_image: screenshot_path.to_string_lossy().to_string(),
+        original_image: mathpix_result.images.original.fullsize.url,
+        rendered_image: mathpix_result.images.rendered.fullsize.url,
+        thumbnail: mathpix_result.images.original.thumbnail.url,
+        text: mathpix_result.text,
+        latex: mathpix_result.latex,
+        html: mathpix_result.html.unwrap_or_default(),
+        mmd: mathpix_result.mmd.unwrap_or_default(),
+        typst: typst_replaced,
+        typst_source,
+        preset,
+        title: mathpix_result.title,
+        snip_count: mathpix_result.snip_count,
+        snip_limit: mathpix_result.snip_limit,
+        overage: mathpix_result.snip_limit > 0
+            && mathpix_result.snip_count > mathpix_result.snip_limit + mathpix_result.extra_snips,
+        region: captured_region,
+        clipboard_mode,
+        confidence: mathpix_result.confidence,
+        ocr_time_ms: mathpix_result.time_ms.ocr_api_response,
+        pixel_scale: 1.0,
+        image_hash: String::new(),
+        copied,
+        timings: SnipTimings {
+            conversion_ms,
+            ..Default::default()
+        },
+        metadata: SnipMetadata {
+            mathpix_id: mathpix_result.id,
+            auto_rotate_degrees: mathpix_result.auto_rotate_degrees,
+            auto_rotate_confidence: mathpix_result.auto_rotate_confidence,
+            ocr_version: mathpix_result.ocr_version,
+            font_size: mathpix_result.font_size,
+            created_at: mathpix_result.created_at,
+            read_request_body_ms: mathpix_result.time_ms.read_request_body,
+            folder_id: mathpix_result.folder_id,
+            share_url: String::new(),
+        },
+    })
+}
+
+/// Order in which snip tasks were started, uploads can finish in any order.
+static NEXT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+/// Snip tasks created but not picked up by the worker yet.
+static WAITING_TASKS: AtomicUsize = AtomicUsize::new(0);
+/// Uploads queued or being sent.
+static PENDING_UPLOADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Captures waiting for the worker and uploads queued or in flight, for the performance overlay.
+pub fn queue_depth() -> (usize, usize) {
+    (WAITING_TASKS.load(Ordering::Relaxed), PENDING_UPLOADS.load(Ordering::Relaxed))
+}
+
+pub(crate) struct SnipTask {
+    id: Uuid,
+    sequence: u64,
+    kind: TaskKind,
+    clipboard_mode: Option<ClipboardMode>,
+    preset: Option<OcrPreset>,
+}
+
+impl SnipTask {
+    pub(crate) fn new() -> Self {
+        Self::with_kind(TaskKind::Interactive)
+    }
+
+    pub(crate) fn with_kind(kind: TaskKind) -> Self {
+        WAITING_TASKS.fetch_add(1, Ordering::Relaxed);
+        SnipTask {
+            id: Uuid::new_v4(),
+            sequence: NEXT_SEQUENCE.fetch_add(1, Ordering::Relaxed),
+            kind,
+            clipboard_mode: None,
+            preset: None,
+        }
+    }
+
+    /// Use this clipboard mode for the result instead of the one in the settings.
+    pub(crate) fn with_clipboard_mode(mut self, clipboard_mode: ClipboardMode) -> Self {
+        self.clipboard_mode = Some(clipboard_mode);
+        self
+    }
+
+    /// Use this OCR preset instead of the one in the settings.
+    pub(crate) fn with_preset(mut self, preset: OcrPreset) -> Self {
+        self.preset = Some(preset);
+        self
+    }
+}
+
+/// What kind of content is captured, changing what Mathpix is asked for and how the result is converted.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OcrPreset {
+    Equation,
+    Document,
+    Table,
+    Chemistry,
+    /// Plain prose, copied as recognized without any math conversion.
+    Text,
+}
+
+impl OcrPreset {
+    pub const ALL: [OcrPreset; 5] =
+        [OcrPreset::Equation, OcrPreset::Document, OcrPreset::Table, OcrPreset::Chemistry, OcrPreset::Text];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OcrPreset::Equation => tr("Equation"),
+            OcrPreset::Document => tr("Document"),
+            OcrPreset::Table => tr("Table"),
+            OcrPreset::Chemistry => tr("Chemistry"),
+            OcrPreset::Text => tr("Text"),
+        }
+    }
+
+    /// Options payload (similar to the Swift `options` dictionary)
+    fn options_payload(&self, extra_formats: ExtraFormats) -> serde_json::Value {
+        let mut options_payload = json!({
             "config": {
                 "include_diagrams": true,
                 "idiomatic_eqn_arrays": true,
@@ -39,147 +1027,213 @@ pub fn start_worker(
                 "input_type": "crop"
             }
         });
-        let client = Client::builder()
-            .pool_idle_timeout(None)
-            .build()
-            .expect("Failed to create reqwest client");
-
-        for snip_task in task_receiver {
-            if let Ok(app_data) = app_data.lock() {
-                if app_data.bring_forward {
-                    #[cfg(target_os = "macos")]
-                    {
-                        let process_name = app_data.target_process_name.clone();
-                        let window_name = app_data.target_window_title.clone();
-                        let script = format!(
-                            r#"
-                            tell application "System Events"
-                                tell process "{process_name}"
-                                    set frontmost to true
-                                end tell
-                            end tell
-                        "#
-                        );
-
-                        let out = Command::new("osascript").arg("-e").arg(script).output().unwrap();
-                        println!("{:?}", out);
-                    }
-
-                    #[cfg(target_os = "windows")]
-                    {
-                        unimplemented!()
-                    }
-                }
+        let config = &mut options_payload["config"];
+        match self {
+            OcrPreset::Equation => config["include_diagrams"] = json!(false),
+            OcrPreset::Document => {}
+            OcrPreset::Table => {
+                config["include_table_html"] = json!(true);
+                config["enable_tables_fallback"] = json!(true);
             }
+            OcrPreset::Chemistry => config["include_smiles"] = json!(true),
+            OcrPreset::Text => {
+                config["include_diagrams"] = json!(false);
+                config["rm_spaces"] = json!(true);
+            }
+        }
+        if extra_formats != ExtraFormats::default() {
+            options_payload["formats"] = json!(extra_formats.names());
+        }
+        options_payload
+    }
+}
 
-            let mut headers = header::HeaderMap::new();
-            headers.insert(
-                "Authorization",
-                header::HeaderValue::from_str(&format!("Bearer {}", app_data.lock().unwrap().mathpix_api_key)).unwrap(),
-            );
-            headers.insert("Accept", header::HeaderValue::from_static("*/*"));
-            headers.insert(
-                "User-Agent",
-                header::HeaderValue::from_static("Mathpix Snip MacOS App v3.4.11(3411.2)"),
-            );
-
-            if let Some(screenshot_path) = get_screenshot() {
-                let screenshot_data = std::fs::read(&screenshot_path).expect("Failed to read screenshot file");
-                let form = multipart::Form::new()
-                    .part(
-                        "file",
-                        Part::bytes(screenshot_data).file_name("image.png").mime_str("image/png").unwrap(),
-                    )
-                    .part(
-                        "options_json",
-                        Part::text(options_payload.to_string()).mime_str("application/json").unwrap(),
-                    );
-
-                let response = client
-                    .post("https://snip-api.mathpix.com/v1/snips-multipart")
-                    .headers(headers.clone())
-                    .multipart(form)
-                    .send()
-                    .unwrap();
-
-                match response.json::<MathpixResult>() {
-                    Ok(mathpix_result) => {
-                        let typst = text_and_tex2typst(&mathpix_result.text).unwrap_or_else(|e| format!("Error: {:?}", e));
-                        let mut typst_replaced = typst.clone();
-                        if let Ok(app_data) = app_data.lock() {
-                            for rule in app_data.replace_rules.iter() {
-                                typst_replaced = typst_replaced.replace(&rule.pattern, &rule.replacement);
-                            }
-
-                            match app_data.clipboard_mode {
-                                ClipboardMode::Continuous => {
-                                    // do nothing, let the UI thread handle it
-                                }
-                                ClipboardMode::CopyTeX => {
-                                    Clipboard::new().unwrap().set_text(mathpix_result.text.clone()).unwrap();
-                                }
-                                ClipboardMode::CopyTypst => {
-                                    Clipboard::new().unwrap().set_text(typst_replaced.clone()).unwrap();
-                                }
-                            }
-                        }
-                        result_sender
-                            .send(TaskResult {
-                                id: snip_task.id,
-                                local_image: screenshot_path.to_string_lossy().to_string(),
-                                original_image: mathpix_result.images.original.fullsize.url.clone(),
-                                rendered_image: mathpix_result.images.rendered.fullsize.url.clone(),
-                                text: mathpix_result.text.clone(),
-                                latex: mathpix_result.latex.clone(),
-                                typst: typst_replaced,
-                                title: mathpix_result.title.clone(),
-                                snip_count: mathpix_result.snip_count,
-                                snip_limit: mathpix_result.snip_limit,
-                            })
-                            .unwrap();
-                    }
-                    Err(e) => {
-                        eprintln!("Error: {:?}", e);
-                    }
-                }
-            } else {
-                continue;
+/// Outputs asked from Mathpix besides `text`, each kept with the snip.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ExtraFormats {
+    pub html: bool,
+    pub mmd: bool,
+    pub latex_styled: bool,
+}
+
+impl ExtraFormats {
+    /// The `formats` of the request.
+    fn names(&self) -> Vec<&'static str> {
+        let mut names = vec!["text"];
+        for (requested, name) in [(self.html, "html"), (self.mmd, "mmd"), (self.latex_styled, "latex_styled")] {
+            if requested {
+                names.push(name);
             }
         }
-    })
+        names
+    }
 }
 
-pub(crate) struct SnipTask {
-    id: Uuid,
+/// How the screenshot for a task is obtained.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TaskKind {
+    /// Let the OS screenshot tool ask for a region.
+    Interactive,
+    /// Capture a region that was just selected in the overlay.
+    Region(CaptureRegion),
+    /// Capture the region of the previous region capture again.
+    RepeatRegion,
+    /// Capture the bounds of the frontmost window.
+    ActiveWindow,
+    /// Capture the content of the window saved in the settings.
+    SavedWindow,
+    /// Capture the last region on a timer, skipped when the screen did not change.
+    Periodic,
+    /// Bring the scrcpy mirror forward and capture its content.
+    Scrcpy,
+    /// Use the image currently on the clipboard instead of taking a screenshot.
+    ClipboardImage,
+    /// OCR a formula drawn on the canvas, each stroke being a list of `[x, y, pressure]` points.
+    Drawing(Vec<Vec<[f32; 3]>>),
 }
 
-impl SnipTask {
-    pub(crate) fn new() -> Self {
-        SnipTask { id: Uuid::new_v4() }
+const SNIP_API_URL: &str = "https://snip-api.mathpix.com/v1/snips-multipart";
+/// How often the oldest capture waiting for the network is sent again.
+const NETWORK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// A single snip is fetched from `SNIPS_API_URL/<id>`.
+const SNIPS_API_URL: &str = "https://snip-api.mathpix.com/v1/snips";
+const SNIP_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const SNIP_POLL_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(crate) fn request_headers(api_key: &str) -> header::HeaderMap {
+    let mut headers = header::HeaderMap::new();
+    headers.insert(
+        "Authorization",
+        header::HeaderValue::from_str(&format!("Bearer {}", api_key)).unwrap_or(header::HeaderValue::from_static("")),
+    );
+    headers.insert("Accept", header::HeaderValue::from_static("*/*"));
+    headers.insert(
+        "User-Agent",
+        header::HeaderValue::from_static("Mathpix Snip MacOS App v3.4.11(3411.2)"),
+    );
+    headers
+}
+
+/// Check an API key by listing the folders of the account, which costs no snip.
+pub fn validate_api_key(api_key: &str, tls: &TlsSettings) -> Result<(), String> {
+    let response = http_client(tls)
+        .get(folders::FOLDERS_API_URL)
+        .headers(request_headers(api_key))
+        .send()
+        .map_err(|e| e.to_string())?;
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => Err("The API key is invalid".to_string()),
+        status => Err(format!("Unexpected response: {}", status)),
     }
 }
 
+pub(crate) const SCRCPY_PROCESS_NAME: &str = "scrcpy";
+
+/// Messages from the worker thread to the UI.
+#[derive(Debug)]
+pub enum WorkerEvent {
+    /// Seconds left before a delayed capture starts, 0 once the countdown is over.
+    Countdown(u32),
+    /// Get the main window out of the way of a screen capture.
+    HideWindow,
+    /// The capture is done, bring the main window back.
+    RestoreWindow,
+    /// A task failed, the worker carries on with the next one.
+    Failed(String),
+    /// Whether Mathpix could be reached, and how many captures wait to be sent.
+    Network { online: bool, queued: usize },
+    /// The screenshot was taken but Mathpix could not recognize it, kept as a failed snip.
+    RecognitionFailed { id: Uuid, local_image: String, error: String },
+    Result(TaskResult),
+}
+
 #[derive(Debug)]
 pub struct TaskResult {
     pub id: Uuid,
+    /// When the task was started relative to the others, for applying results in capture order.
+    pub sequence: u64,
     pub local_image: String,
     pub original_image: String,
     pub rendered_image: String,
+    /// Small version of the capture hosted by Mathpix, for the list.
+    pub thumbnail: String,
     pub text: String,
     pub latex: Option<String>,
+    /// Empty unless requested in the extra formats.
+    pub html: String,
+    pub mmd: String,
     pub typst: String,
+    /// The output of Mathpix the Typst was converted from.
+    pub typst_source: TexSource,
+    pub preset: OcrPreset,
     pub title: String,
     pub snip_count: u64,
     pub snip_limit: u64,
+    /// Sent after the snips of the plan were used up, billed as overage.
+    pub overage: bool,
+    pub region: Option<CaptureRegion>,
+    pub clipboard_mode: ClipboardMode,
+    pub confidence: f64,
+    /// Time Mathpix spent on the OCR.
+    pub ocr_time_ms: u64,
+    pub metadata: SnipMetadata,
+    /// Device pixel ratio of the capture.
+    pub pixel_scale: f32,
+    pub image_hash: String,
+    /// What was put on the clipboard.
+    pub copied: Option<String>,
+    pub timings: SnipTimings,
+}
+
+/// How long each step from the capture to the Typst took, in milliseconds.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Default)]
+#[serde(default)]
+pub struct SnipTimings {
+    /// Taking and preparing the screenshot.
+    pub capture_ms: u64,
+    /// The request to Mathpix without the OCR itself.
+    pub upload_ms: u64,
+    pub ocr_ms: u64,
+    /// Cleaning up and converting the result.
+    pub conversion_ms: u64,
+}
+
+impl SnipTimings {
+    pub fn total_ms(&self) -> u64 {
+        self.capture_ms + self.upload_ms + self.ocr_ms + self.conversion_ms
+    }
+}
+
+/// Details of the Mathpix response kept with a snip.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SnipMetadata {
+    pub mathpix_id: String,
+    pub auto_rotate_degrees: i64,
+    pub auto_rotate_confidence: f64,
+    pub ocr_version: u64,
+    pub font_size: f64,
+    /// When Mathpix processed the request.
+    pub created_at: String,
+    pub read_request_body_ms: u64,
+    /// Mathpix folder the snip was uploaded into, empty for none.
+    pub folder_id: String,
+    /// Public link to the snip, empty until one was requested.
+    pub share_url: String,
 }
 
 // The following is the struct for the Mathpix API response
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct MathpixResult {
     id: String,
     status: String,
     text: String,
     latex: Option<String>,
+    latex_styled: Option<String>,
+    html: Option<String>,
+    mmd: Option<String>,
     title: String,
     images: Images,
     confidence: f64,
@@ -197,57 +1251,55 @@ pub struct MathpixResult {
     folder_id: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct Images {
     original: ImageDetails,
     rendered: ImageDetails,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct ImageDetails {
     fullsize: UrlDetail,
     thumbnail: UrlDetail,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct UrlDetail {
     url: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct TimeMs {
     ocr_api_response: u64,
     read_request_body: u64,
 }
 
-#[cfg(target_os = "macos")]
-fn get_screenshot() -> Option<std::path::PathBuf> {
-    let storage_path = get_storage_dir().unwrap_or_else(|| std::path::PathBuf::from("/tmp")); // Fallback to /tmp if no storage path
-    let file_name = storage_path.join(format!("screenshot_{}.png", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")));
-    std::process::Command::new("screencapture")
-        .arg("-i")
-        .arg(&file_name)
-        .output()
-        .unwrap();
-
-    // check the path if teh file exists
-    if file_name.exists() {
-        println!("Screenshot saved to: {:?}", file_name);
-        Some(file_name)
-    } else {
-        println!("Screenshot cancelled.");
-        None
-    }
-}
-
-#[cfg(target_os = "windows")]
-fn get_screenshot() -> Option<std::path::PathBuf> {
-    let storage_path = get_storage_dir().unwrap_or_else(|| std::path::PathBuf::from("/tmp")); // Fallback to /tmp if no storage path
-    let file_name = storage_path.join(format!("screenshot_{}.png", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")));
-    screen_snip::get_screen_snip(file_name.clone().into());
-    Some(file_name)
-}
+/// Queue a periodic capture every `auto_capture_interval_secs` while auto-capture is switched on.
+pub fn start_auto_capture(task_sender: Sender<SnipTask>, app_data: Arc<Mutex<TypstScanData>>) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut elapsed = 0;
+        loop {
+            thread::sleep(Duration::from_secs(1));
+            let Ok((enabled, interval)) = app_data
+                .lock()
+                .map(|app_data| (app_data.auto_capture && !app_data.quota_low(), app_data.auto_capture_interval_secs))
+            else {
+                continue;
+            };
+            // the last snips of the plan are kept for captures asked for by hand
+            if !enabled {
+                elapsed = 0;
+                continue;
+            }
 
-fn get_storage_dir() -> Option<std::path::PathBuf> {
-    eframe::storage_dir("Typst Scan")
+            elapsed += 1;
+            if elapsed >= interval.max(1) {
+                elapsed = 0;
+                let task = SnipTask::with_kind(TaskKind::Periodic).with_clipboard_mode(ClipboardMode::Continuous);
+                if task_sender.send(task).is_err() {
+                    break;
+                }
+            }
+        }
+    })
 }